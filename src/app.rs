@@ -1,13 +1,16 @@
-use git2::{Commit, Object, ObjectType, Repository};
+use std::mem;
+use std::path::PathBuf;
+
+use git2::{Commit, Object, ObjectType, Oid, Repository, Tree};
 
 use ratatui::{
-    layout::Rect,
+    layout::{Constraint, Direction, Layout, Rect},
     prelude::{Modifier, Text},
     style::{Color, Style},
     text::{Line, Span},
     widgets::{
         block::{Padding, Title},
-        Block, Borders, Paragraph,
+        Block, Borders, Clear, Paragraph, Tabs, Wrap,
     },
     Frame,
 };
@@ -22,20 +25,73 @@ use crate::{
     ui::centered_rect,
 };
 
-mod blob_pager;
+pub(crate) mod attributes;
+pub(crate) mod blob_pager;
+mod columns;
+mod commit_graph;
+mod commit_page;
+pub(crate) mod entry_metadata;
+mod error_log;
 mod external_editor;
-pub mod navigation;
+mod form;
+mod git_dir;
+mod highlight;
+mod history;
+mod history_filter;
+mod lfs;
+pub(crate) mod log_page;
+mod navigation;
+mod notes;
 mod pagination;
-mod refs_page;
+mod patch;
+mod pick;
+mod pickaxe;
+mod reflog_page;
+pub(crate) mod refs_page;
+mod repo_watcher;
+pub(crate) mod signature;
+mod signing;
+mod stash_page;
+pub(crate) mod syntax_detection;
+mod syntax_picker;
+mod tab;
 mod tree_page;
+mod workdir_page;
+mod worker;
+
+// What running the browser takes
+pub use crate::app::external_editor::ExternalEditor;
+pub use crate::app::navigation::NavigationAction;
+pub use crate::app::pick::PickFormat;
+pub use crate::app::worker::WorkerPool;
 
 use crate::{
     app::{
-        blob_pager::BlobPager,
-        external_editor::ExternalEditor,
-        navigation::{ActionInfo, NavigationAction},
+        attributes::AttributeValue,
+        blob_pager::{BlobContent, BlobPager},
+        columns::Preview,
+        commit_page::CommitPage,
+        entry_metadata::format_size,
+        error_log::{ErrorLog, LogContext, Severity},
+        form::Form,
+        history::{History, JumpList, Location},
+        history_filter::HistoryFilter,
+        log_page::LogPage,
+        navigation::ActionInfo,
+        notes::NotesRefPicker,
+        pick::Pick,
+        pickaxe::{PickaxePage, PickaxeQuery, PickaxeSelection},
+        reflog_page::ReflogPage,
         refs_page::RefsPage,
+        repo_watcher::RepoWatcher,
+        signature::SignatureFormatter,
+        signing::{SignatureStatus, Verification},
+        stash_page::{StashEntryPage, StashPage, StashPart},
+        syntax_picker::SyntaxPicker,
+        tab::Tab,
         tree_page::TreePage,
+        workdir_page::{WorkdirPage, WorkdirSelection},
+        worker::{Mailbox, Task, TaskResult},
     },
     errors::{ErrorKind, GitBrowserError},
 };
@@ -44,27 +100,90 @@ use crate::{
 pub enum AppMode {
     BrowseRefs,
     BrowseTrees,
+    BrowseWorkdir,
+    BrowseStashes,
+    BrowseStash,
+    BrowseReflog,
+    BrowseLog,
+    BrowsePickaxe,
+    ViewCommit,
     ViewBlob,
-    ExternalEditor,
+    ErrorLog,
+    SelectSyntax,
+    SelectNotesRef,
+    JumpList,
+    EditPickaxe,
+    EditHistoryFilter,
     Error,
 }
 
 pub struct App<'repo, 'syntax> {
-    pub search_input: String,
     repo: &'repo Repository,
     commit: Option<Commit<'repo>>,
+    // The note attached to the commit in the notes ref being displayed
+    note: Option<String>,
+    notes_ref: String,
+    commit_signature: Option<SignatureStatus>,
+    // The annotated tag the commit was reached through, with its signature
+    tag_signature: Option<(String, Oid, SignatureStatus)>,
+    verify_signatures: bool,
+    signature_formatter: SignatureFormatter,
+    reference: Option<String>,
+    moved_to: Option<Oid>,
+    // Where the verification of the signatures arrives
+    mailbox: Mailbox,
+    watcher: Option<RepoWatcher>,
     refs_page: RefsPage<'repo>,
     tree_pages: Vec<TreePage<'repo>>,
-    blob_pager: Option<BlobPager<'repo, 'syntax>>,
+    workdir_pages: Vec<WorkdirPage<'repo>>,
+    stash_page: Option<StashPage<'repo>>,
+    stash_entry_page: Option<StashEntryPage<'repo>>,
+    reflog_page: Option<ReflogPage<'repo>>,
+    log_page: Option<LogPage<'repo>>,
+    // The commit to search from, along with the search being entered
+    pickaxe_form: Option<(Oid, Form)>,
+    pickaxe_page: Option<PickaxePage<'repo>>,
+    // Kept for the session so that every log is filtered the same way
+    history_filter: HistoryFilter,
+    filter_form: Option<Form>,
+    blob_pager: Option<BlobPager<'syntax>>,
+    // Whether trees are shown in columns along with their parent and a
+    // preview of the selected entry
+    columns: bool,
+    preview: Option<Preview<'syntax>>,
+    syntax_picker: Option<SyntaxPicker<'syntax>>,
+    commit_page: Option<CommitPage>,
+    notes_picker: Option<NotesRefPicker>,
+    jump_list: Option<JumpList>,
     external_editor: Option<ExternalEditor>,
+    // Set in `--pick` mode, where choosing an entry or reference ends the
+    // session with it printed
+    pick: Option<PickFormat>,
+    picked: Option<String>,
     mode_history: Vec<AppMode>,
+    // The locations visited, for going back and forward
+    history: History,
+    // The fields above from `commit` on belong to the current tab. The
+    // other tabs are parked here, with an empty slot for the current one
+    tabs: Vec<Tab<'repo, 'syntax>>,
+    current_tab: usize,
     height: u16,
     active_error: Option<GitBrowserError>,
+    error_log: ErrorLog,
     editor: String,
     syntax_set: &'syntax SyntaxSet,
     theme: &'syntax highlighting::Theme,
+    workers: WorkerPool,
+    spinner_frame: usize,
 }
 
+/// Selecting this reference opens the stash page rather than the latest
+/// stash.
+const STASH_REF: &str = "refs/stash";
+
+/// Shown in the title while background work is pending.
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
 pub struct Redraw(pub bool);
 
 impl<'repo, 'syntax> App<'repo, 'syntax> {
@@ -74,43 +193,124 @@ impl<'repo, 'syntax> App<'repo, 'syntax> {
         editor: String,
         syntax_set: &'syntax SyntaxSet,
         theme: &'syntax highlighting::Theme,
-    ) -> App<'repo, 'syntax> {
+        workers: WorkerPool,
+    ) -> Result<App<'repo, 'syntax>, GitBrowserError> {
+        let refs_page = RefsPage::new(repo, workers.handle());
         let mut new = App {
-            search_input: String::new(),
             repo,
             commit: None,
-            refs_page: RefsPage::new(repo),
+            note: None,
+            notes_ref: notes::default_notes_ref(repo),
+            commit_signature: None,
+            tag_signature: None,
+            verify_signatures: false,
+            signature_formatter: SignatureFormatter::empty(),
+            reference: None,
+            moved_to: None,
+            mailbox: Mailbox::new(),
+            watcher: None,
+            refs_page,
             tree_pages: vec![],
+            workdir_pages: vec![],
+            stash_page: None,
+            stash_entry_page: None,
+            reflog_page: None,
+            log_page: None,
+            pickaxe_form: None,
+            pickaxe_page: None,
+            history_filter: HistoryFilter::default(),
+            filter_form: None,
             blob_pager: None,
+            columns: false,
+            preview: None,
+            syntax_picker: None,
+            commit_page: None,
+            notes_picker: None,
+            jump_list: None,
             external_editor: None,
+            pick: None,
+            picked: None,
             mode_history: vec![AppMode::BrowseRefs],
+            history: History::default(),
+            tabs: vec![Tab::default()],
+            current_tab: 0,
             height: 0,
             active_error: None,
+            error_log: ErrorLog::new(),
             editor,
             syntax_set,
             theme,
+            workers,
+            spinner_frame: 0,
         };
-        if let Some(object) = &commit_object {
-            match object.peel_to_commit() {
-                Ok(commit) => {
-                    new.tree_pages = vec![TreePage::new(repo, object.clone(), "".to_string())];
-                    new.mode_history = vec![AppMode::BrowseTrees];
-                    new.commit = Some(commit.clone());
-                }
-                Err(e) => panic!("Failed to get commit {}", e),
-            }
+        if let Some(object) = commit_object {
+            let commit = object.peel_to_commit().map_err(|e| {
+                GitBrowserError::git(ErrorKind::CommitReference, e).with_oid(object.id())
+            })?;
+            new.tree_pages = vec![TreePage::new(
+                repo,
+                object,
+                "".to_string(),
+                new.workers.handle(),
+            )?];
+            new.mode_history = vec![AppMode::BrowseTrees];
+            new.set_commit(commit);
+        }
+
+        new.load_mailmap();
+
+        match RepoWatcher::new(repo) {
+            Ok(watcher) => new.watcher = Some(watcher),
+            Err(e) => new.warn(e),
         }
 
-        new
+        Ok(new)
     }
 
     pub fn set_height(&mut self, h: u16) {
         self.height = h;
     }
 
-    pub fn title(&self) -> Vec<Span> {
+    /// Set the reference that the current commit was resolved from.
+    pub fn set_reference(&mut self, reference: String) {
+        self.reference = Some(reference);
+        self.load_signatures();
+    }
+
+    /// Check signatures with `gpg` or `ssh-keygen` as well as showing them.
+    pub fn set_verify_signatures(&mut self, verify: bool) {
+        self.verify_signatures = verify;
+        self.load_signatures();
+    }
+
+    /// Show trees in columns with their parent and a preview of the selected
+    /// entry.
+    pub fn set_columns(&mut self, columns: bool) {
+        self.columns = columns;
+        self.update_preview();
+    }
+
+    /// Choose a tree entry or reference to print rather than browsing.
+    pub fn set_pick(&mut self, format: PickFormat) {
+        self.pick = Some(format);
+    }
+
+    /// The chosen entry or reference formatted for printing, once one has
+    /// been picked.
+    pub fn picked(&mut self) -> Option<String> {
+        self.picked.take()
+    }
+
+    pub fn title(&self) -> Vec<Span<'_>> {
         let mut parts = vec![Span::from(" ")];
 
+        if self.workers.pending() > 0 {
+            parts.push(Span::styled(
+                format!("{} ", SPINNER[self.spinner_frame % SPINNER.len()]),
+                Style::default().fg(Color::Cyan),
+            ));
+        }
+
         parts.push(Span::styled(
             self.refs_page.title(),
             Style::default()
@@ -133,7 +333,93 @@ impl<'repo, 'syntax> App<'repo, 'syntax> {
             ));
         }
 
-        if self.tree_pages.len() > 1 || self.blob_pager.is_some() {
+        if let (Some(status), Some(_)) = (&self.commit_signature, &self.commit) {
+            let (badge, style) = status.badge();
+            parts.push(Span::styled(format!(" [{}]", badge), style));
+        }
+
+        if let Some((name, _, status)) = &self.tag_signature {
+            let (badge, style) = status.badge();
+            parts.push(Span::styled(format!(" [tag {}: {}]", name, badge), style));
+        }
+
+        if let Some(note) = &self.note {
+            let summary = note.lines().next().unwrap_or_default();
+            parts.push(Span::styled(
+                format!(" [{} {}]", notes::notes_label(&self.notes_ref), summary),
+                Style::default().fg(Color::LightBlue),
+            ));
+        }
+
+        if let (Some(reference), Some(moved_to)) = (&self.reference, self.moved_to) {
+            let short_id = moved_to.to_string();
+            parts.push(Span::styled(
+                format!(" ({} moved to {})", reference, &short_id[..7]),
+                Style::default()
+                    .fg(Color::Magenta)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+
+        if let Some(page) = self.workdir_pages.first() {
+            parts.push(Span::styled(
+                "@",
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            ));
+            parts.push(Span::styled(
+                page.revision().as_str(),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+
+        // Pages that list revisions rather than browse one
+        let listing = self
+            .stash_entry_page
+            .as_ref()
+            .map(|page| page.title())
+            .or_else(|| {
+                self.reflog_page
+                    .as_ref()
+                    .map(|page| format!("{} reflog", page.title()))
+            })
+            .or_else(|| {
+                self.log_page
+                    .as_ref()
+                    .map(|page| format!("{} log", page.title()))
+            })
+            .or_else(|| self.pickaxe_page.as_ref().map(|page| page.title()));
+        if let (Some(listing), true) = (listing, self.tree_pages.is_empty()) {
+            parts.push(Span::styled(
+                "@",
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            ));
+            parts.push(Span::styled(
+                listing,
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+
+        // Chips for the filters narrowing the log
+        if let (Some(page), true) = (&self.log_page, self.tree_pages.is_empty()) {
+            for chip in page.filter().chips() {
+                parts.push(Span::raw(" "));
+                parts.push(Span::styled(
+                    format!(" {} ", chip),
+                    Style::default().fg(Color::Black).bg(Color::Magenta),
+                ));
+            }
+        }
+
+        let titles = self.page_titles();
+        if titles.len() > 1 || self.blob_pager.is_some() {
             parts.push(Span::styled(
                 ": ",
                 Style::default()
@@ -142,10 +428,10 @@ impl<'repo, 'syntax> App<'repo, 'syntax> {
             ));
         }
 
-        for (ix, page) in self.tree_pages.iter().enumerate() {
+        for (ix, title) in titles.into_iter().enumerate() {
             let sep = if ix > 0 { "/" } else { "" };
             parts.push(Span::styled(
-                format!("{}{}", page.title(), sep),
+                format!("{}{}", title, sep),
                 Style::default()
                     .fg(Color::Blue)
                     .add_modifier(Modifier::BOLD),
@@ -157,6 +443,53 @@ impl<'repo, 'syntax> App<'repo, 'syntax> {
                 pager.title(),
                 Style::default().fg(Color::Gray),
             ));
+
+            if let Some((pointer, available)) = pager.lfs_pointer() {
+                let (badge, color) = if available {
+                    ("LFS", Color::Cyan)
+                } else {
+                    ("LFS object not available locally", Color::Red)
+                };
+                parts.push(Span::styled(
+                    format!(
+                        " [{} sha256:{} {}]",
+                        badge,
+                        &pointer.oid[..12],
+                        format_size(pointer.size)
+                    ),
+                    Style::default().fg(color).add_modifier(Modifier::BOLD),
+                ));
+                if pager.truncated() {
+                    parts.push(Span::styled(
+                        format!(
+                            " [first {} shown]",
+                            format_size(pager.content().len() as u64)
+                        ),
+                        Style::default().fg(Color::Yellow),
+                    ));
+                }
+            }
+        }
+
+        if matches!(self.mode(), AppMode::ErrorLog) {
+            parts.push(Span::styled(
+                format!(" [{}]", self.error_log.title()),
+                Style::default().fg(Color::Red),
+            ));
+        }
+
+        if let (AppMode::BrowsePickaxe, Some(page)) = (self.mode(), &self.pickaxe_page) {
+            parts.push(Span::styled(
+                format!(" [{}]", page.progress()),
+                Style::default().fg(Color::Cyan),
+            ));
+        }
+
+        if let (AppMode::SelectSyntax, Some(picker)) = (self.mode(), &self.syntax_picker) {
+            parts.push(Span::styled(
+                format!(" [{}]", picker.title()),
+                Style::default().fg(Color::Cyan),
+            ));
         }
 
         parts.push(Span::from(" "));
@@ -164,12 +497,16 @@ impl<'repo, 'syntax> App<'repo, 'syntax> {
     }
 
     pub fn draw_context_hint(&self, f: &mut Frame, area: Rect) {
-        let actions = match self.mode() {
+        let mut actions = match self.mode() {
             AppMode::BrowseRefs => {
                 vec![
                     NavigationAction::Exit,
                     NavigationAction::Back,
                     NavigationAction::Select,
+                    NavigationAction::Reflog,
+                    NavigationAction::Log,
+                    NavigationAction::Pickaxe,
+                    NavigationAction::Refresh,
                 ]
             }
             AppMode::ViewBlob => {
@@ -177,8 +514,56 @@ impl<'repo, 'syntax> App<'repo, 'syntax> {
                     NavigationAction::Exit,
                     NavigationAction::Back,
                     NavigationAction::ExternalEditor,
+                    NavigationAction::SetSyntax,
+                ]
+            }
+            AppMode::BrowseWorkdir | AppMode::BrowseStashes | AppMode::BrowseReflog => {
+                vec![
+                    NavigationAction::Exit,
+                    NavigationAction::Back,
+                    NavigationAction::Select,
+                    NavigationAction::Refresh,
+                ]
+            }
+            AppMode::BrowseLog => {
+                vec![
+                    NavigationAction::Exit,
+                    NavigationAction::Back,
+                    NavigationAction::Select,
+                    NavigationAction::Pickaxe,
+                    NavigationAction::FilterHistory,
+                    NavigationAction::Refresh,
+                ]
+            }
+            AppMode::EditPickaxe | AppMode::EditHistoryFilter => {
+                vec![
+                    NavigationAction::Exit,
+                    NavigationAction::Back,
+                    NavigationAction::Select,
+                    NavigationAction::NextField,
+                ]
+            }
+            AppMode::ViewCommit => {
+                vec![
+                    NavigationAction::Exit,
+                    NavigationAction::Back,
+                    NavigationAction::NotesRef,
+                ]
+            }
+            AppMode::BrowseStash
+            | AppMode::BrowsePickaxe
+            | AppMode::SelectSyntax
+            | AppMode::SelectNotesRef
+            | AppMode::JumpList => {
+                vec![
+                    NavigationAction::Exit,
+                    NavigationAction::Back,
+                    NavigationAction::Select,
                 ]
             }
+            AppMode::ErrorLog | AppMode::Error => {
+                vec![NavigationAction::Exit, NavigationAction::Back]
+            }
             _ => {
                 vec![
                     NavigationAction::Exit,
@@ -188,6 +573,43 @@ impl<'repo, 'syntax> App<'repo, 'syntax> {
                 ]
             }
         };
+        if self.moved_to.is_some()
+            && matches!(self.mode(), AppMode::BrowseTrees | AppMode::ViewBlob)
+        {
+            actions.push(NavigationAction::JumpToTip);
+        }
+        if self.commit.is_some() && matches!(self.mode(), AppMode::BrowseTrees | AppMode::ViewBlob)
+        {
+            actions.push(NavigationAction::CommitDetails);
+            actions.push(NavigationAction::Pickaxe);
+        }
+        if matches!(self.mode(), AppMode::BrowseTrees) {
+            actions.push(NavigationAction::ToggleColumns);
+        }
+        if self.commit.is_some() && matches!(self.mode(), AppMode::BrowseTrees | AppMode::ViewBlob)
+        {
+            actions.push(NavigationAction::DuplicateTab);
+        } else {
+            actions.push(NavigationAction::NewTab);
+        }
+        if self.tabs.len() > 1 {
+            actions.push(NavigationAction::NextTab);
+            actions.push(NavigationAction::CloseTab);
+        }
+        if self.history.can_go_back() {
+            actions.push(NavigationAction::HistoryBack);
+        }
+        if self.history.can_go_forward() {
+            actions.push(NavigationAction::HistoryForward);
+        }
+        if self.history.can_go_back() || self.history.can_go_forward() {
+            actions.push(NavigationAction::JumpList);
+        }
+        if self.pick.is_some() && matches!(self.mode(), AppMode::BrowseTrees | AppMode::BrowseRefs)
+        {
+            // Next to Select as it is the point of the session
+            actions.insert(3, NavigationAction::Pick);
+        }
         let keys_hint = actions
             .iter()
             .map(|a| ActionInfo::from(a).to_string())
@@ -199,9 +621,44 @@ impl<'repo, 'syntax> App<'repo, 'syntax> {
             .style(Style::default().fg(Color::Black).bg(Color::Gray));
         let hint = Paragraph::new(Line::from(content)).block(block);
         f.render_widget(hint, area);
+
+        let unseen = self.error_log.unseen();
+        if unseen > 0 {
+            let indicator = format!(
+                " {} new error{} ({}) ",
+                unseen,
+                if unseen == 1 { "" } else { "s" },
+                ActionInfo::from(NavigationAction::ErrorLog),
+            );
+            let width = (indicator.len() as u16).min(area.width);
+            let indicator_area = Rect {
+                x: area.x + area.width - width,
+                width,
+                ..area
+            };
+            let indicator = Paragraph::new(Span::styled(
+                indicator,
+                Style::default()
+                    .fg(Color::White)
+                    .bg(Color::Red)
+                    .add_modifier(Modifier::BOLD),
+            ));
+            f.render_widget(indicator, indicator_area);
+        }
     }
 
     pub fn draw(&mut self, f: &mut Frame, area: Rect) {
+        let area = if self.tabs.len() > 1 {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Min(0)])
+                .split(area);
+            self.draw_tab_bar(f, chunks[0]);
+            chunks[1]
+        } else {
+            area
+        };
+
         let title = Title::from(self.title());
         let content_block = Block::default()
             .padding(Padding::horizontal(1))
@@ -209,18 +666,75 @@ impl<'repo, 'syntax> App<'repo, 'syntax> {
             .style(Style::default())
             .title(title);
 
-        let viewport = if let Some(page) = match self.mode() {
+        let viewport = if self.columns && matches!(self.page_mode(), AppMode::BrowseTrees) {
+            let (current, parents) = self
+                .tree_pages
+                .split_last()
+                .expect("No tree browsing page in tree mode");
+            columns::draw_columns(
+                f,
+                area,
+                content_block,
+                parents.last(),
+                current,
+                self.preview.as_ref(),
+            )
+        } else if let Some(page) = match self.page_mode() {
             AppMode::BrowseRefs => Some(Box::<&dyn Drawable>::new(&self.refs_page)),
             AppMode::BrowseTrees => Some(Box::<&dyn Drawable>::new(
                 self.tree_pages
                     .last()
                     .expect("No tree browsing page in tree mode"),
             )),
+            AppMode::BrowseWorkdir => Some(Box::<&dyn Drawable>::new(
+                self.workdir_pages
+                    .last()
+                    .expect("No working tree page in working tree mode"),
+            )),
+            AppMode::BrowseStashes => Some(Box::<&dyn Drawable>::new(
+                self.stash_page
+                    .as_ref()
+                    .expect("No stash page in stash browsing mode"),
+            )),
+            AppMode::BrowseStash => Some(Box::<&dyn Drawable>::new(
+                self.stash_entry_page
+                    .as_ref()
+                    .expect("No stash entry page in stash entry mode"),
+            )),
+            AppMode::BrowseReflog => Some(Box::<&dyn Drawable>::new(
+                self.reflog_page
+                    .as_ref()
+                    .expect("No reflog page in reflog mode"),
+            )),
+            AppMode::BrowseLog => Some(Box::<&dyn Drawable>::new(
+                self.log_page.as_ref().expect("No log page in log mode"),
+            )),
+            AppMode::BrowsePickaxe => Some(Box::<&dyn Drawable>::new(
+                self.pickaxe_page
+                    .as_ref()
+                    .expect("No pickaxe page in pickaxe mode"),
+            )),
             AppMode::ViewBlob => Some(Box::<&dyn Drawable>::new(
                 self.blob_pager
                     .as_ref()
                     .expect("No blob browser page in blob mode"),
             )),
+            AppMode::ViewCommit => Some(Box::<&dyn Drawable>::new(
+                self.commit_page
+                    .as_ref()
+                    .expect("No commit page in commit mode"),
+            )),
+            AppMode::ErrorLog => Some(Box::<&dyn Drawable>::new(&self.error_log)),
+            AppMode::SelectSyntax => Some(Box::<&dyn Drawable>::new(
+                self.syntax_picker
+                    .as_ref()
+                    .expect("No syntax picker in syntax selection mode"),
+            )),
+            AppMode::SelectNotesRef => Some(Box::<&dyn Drawable>::new(
+                self.notes_picker
+                    .as_ref()
+                    .expect("No notes ref picker in notes ref selection mode"),
+            )),
             _ => None,
         } {
             page.draw(f, area, content_block)
@@ -230,13 +744,55 @@ impl<'repo, 'syntax> App<'repo, 'syntax> {
 
         self.set_height(viewport.height);
 
-        if let Some(error) = self.active_error {
-            self.display_error(f, &error);
+        if let Some(form) = self.form() {
+            form.draw(f, area);
         }
+
+        if let (AppMode::JumpList, Some(list)) = (self.mode(), &self.jump_list) {
+            let popup_area = centered_rect(60, 50, area);
+            let popup_block = Block::default()
+                .padding(Padding::horizontal(1))
+                .borders(Borders::ALL)
+                .title(Span::styled(
+                    format!(" {} ", list.title()),
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                ))
+                .style(Style::default().bg(Color::DarkGray));
+            f.render_widget(Clear, popup_area);
+            list.draw(f, popup_area, popup_block);
+        }
+
+        if let Some(error) = &self.active_error {
+            self.display_error(f, area, error);
+        }
+    }
+
+    fn draw_tab_bar(&self, f: &mut Frame, area: Rect) {
+        let labels: Vec<String> = self
+            .tabs
+            .iter()
+            .enumerate()
+            .map(|(index, tab)| {
+                if index == self.current_tab {
+                    format!(" {} ", self.tab_label())
+                } else {
+                    format!(" {} ", tab.label)
+                }
+            })
+            .collect();
+        let tabs = Tabs::new(labels)
+            .select(self.current_tab)
+            .style(Style::default().fg(Color::Gray))
+            .highlight_style(Style::default().fg(Color::Black).bg(Color::Cyan))
+            .padding("", "")
+            .divider("|");
+        f.render_widget(tabs, area);
     }
 
-    fn display_error(&self, f: &mut Frame, error: &GitBrowserError) {
-        let area = centered_rect(60, 25, f.size());
+    fn display_error(&self, f: &mut Frame, area: Rect, error: &GitBrowserError) {
+        let area = centered_rect(60, 25, area);
         let popup_block = Block::default()
             .padding(Padding::horizontal(1))
             .borders(Borders::ALL)
@@ -245,22 +801,217 @@ impl<'repo, 'syntax> App<'repo, 'syntax> {
                 Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
             ))
             .style(Style::default().bg(Color::DarkGray));
-        let content = Paragraph::new(Text::styled(
+        let mut lines = vec![Line::styled(
             error.as_str().to_string(),
             Style::default()
                 .fg(Color::White)
                 .add_modifier(Modifier::BOLD),
-        ))
-        .block(popup_block);
+        )];
+        lines.extend(
+            error
+                .details()
+                .into_iter()
+                .map(|detail| Line::styled(detail, Style::default().fg(Color::White))),
+        );
+        let content = Paragraph::new(Text::from(lines))
+            .wrap(Wrap { trim: false })
+            .block(popup_block);
         f.render_widget(content, area);
     }
 
     pub fn navigate(&mut self, action: &NavigationAction) -> Result<Redraw, GitBrowserError> {
+        let redraw = self.handle_action(action);
+        // Whatever happened may have changed the location or selected entry
+        self.update_history();
+        self.update_preview();
+        redraw
+    }
+
+    fn handle_action(&mut self, action: &NavigationAction) -> Result<Redraw, GitBrowserError> {
         // Handle Select and Back on self and exit early
         match (action, self.mode()) {
             (NavigationAction::ExternalEditor, AppMode::BrowseTrees) => {
                 return self.view_blob();
             }
+            (NavigationAction::SetSyntax, AppMode::ViewBlob) => {
+                let current = self
+                    .blob_pager
+                    .as_ref()
+                    .and_then(|pager| pager.syntax_name());
+                self.syntax_picker = Some(SyntaxPicker::new(self.syntax_set, current));
+                self.mode_history.push(AppMode::SelectSyntax);
+                return Ok(Redraw(false));
+            }
+            (NavigationAction::Reflog, AppMode::BrowseRefs) => {
+                if self.refs_page.selected_pseudo_revision().is_none() {
+                    let page = ReflogPage::new(self.repo, self.refs_page.selected_item())?;
+                    self.reflog_page = Some(page);
+                    self.mode_history.push(AppMode::BrowseReflog);
+                }
+                return Ok(Redraw(false));
+            }
+            (NavigationAction::Log, AppMode::BrowseRefs) => {
+                if self.refs_page.selected_pseudo_revision().is_none() {
+                    let page = LogPage::new(
+                        self.repo,
+                        self.refs_page.selected_item(),
+                        self.refs_page.decorations(),
+                        self.history_filter.clone(),
+                        self.workers.handle(),
+                    )?;
+                    self.log_page = Some(page);
+                    self.mode_history.push(AppMode::BrowseLog);
+                }
+                return Ok(Redraw(false));
+            }
+            (
+                NavigationAction::Pickaxe,
+                AppMode::BrowseRefs
+                | AppMode::BrowseTrees
+                | AppMode::ViewBlob
+                | AppMode::ViewCommit
+                | AppMode::BrowseLog,
+            ) => {
+                if let Some(start) = self.pickaxe_start()? {
+                    self.pickaxe_form = Some((start, PickaxeQuery::form()));
+                    self.mode_history.push(AppMode::EditPickaxe);
+                }
+                return Ok(Redraw(false));
+            }
+            (NavigationAction::Select, AppMode::EditPickaxe) => {
+                if let Some((start, form)) = &self.pickaxe_form {
+                    let query = PickaxeQuery::from_form(*start, form)?;
+                    self.back();
+                    let page = PickaxePage::new(self.repo, query, self.workers.handle());
+                    self.pickaxe_page = Some(page);
+                    self.mode_history.push(AppMode::BrowsePickaxe);
+                }
+                return Ok(Redraw(false));
+            }
+            (NavigationAction::FilterHistory, AppMode::BrowseLog) => {
+                self.filter_form = Some(self.history_filter.form());
+                self.mode_history.push(AppMode::EditHistoryFilter);
+                return Ok(Redraw(false));
+            }
+            (NavigationAction::Select, AppMode::EditHistoryFilter) => {
+                if let Some(form) = &self.filter_form {
+                    let filter = HistoryFilter::from_form(form)?;
+                    self.back();
+                    if let Some(page) = self.log_page.as_mut() {
+                        page.set_filter(filter.clone());
+                    }
+                    self.history_filter = filter;
+                }
+                return Ok(Redraw(false));
+            }
+            (
+                NavigationAction::Input(_)
+                | NavigationAction::DeleteChar
+                | NavigationAction::NextField
+                | NavigationAction::NextSelection
+                | NavigationAction::PreviousSelection,
+                AppMode::EditPickaxe | AppMode::EditHistoryFilter,
+            ) => {
+                if let Some(form) = self.form_mut() {
+                    match action {
+                        NavigationAction::Input(c) => form.input(*c),
+                        NavigationAction::DeleteChar => form.delete_char(),
+                        NavigationAction::PreviousSelection => form.previous_field(),
+                        _ => form.next_field(),
+                    }
+                }
+                return Ok(Redraw(false));
+            }
+            (NavigationAction::CommitDetails, AppMode::BrowseTrees | AppMode::ViewBlob) => {
+                if let Some(page) = self.new_commit_page() {
+                    self.commit_page = Some(page);
+                    self.mode_history.push(AppMode::ViewCommit);
+                }
+                return Ok(Redraw(false));
+            }
+            (
+                NavigationAction::NotesRef,
+                AppMode::BrowseTrees | AppMode::ViewBlob | AppMode::ViewCommit,
+            ) => {
+                self.notes_picker = Some(NotesRefPicker::new(self.repo, &self.notes_ref)?);
+                self.mode_history.push(AppMode::SelectNotesRef);
+                return Ok(Redraw(false));
+            }
+            (NavigationAction::Select, AppMode::SelectNotesRef) => {
+                if let Some(notes_ref) = self.notes_picker.as_ref().and_then(|p| p.selected()) {
+                    self.notes_ref = notes_ref.to_string();
+                    self.load_note();
+                }
+                if self.commit_page.is_some() {
+                    self.commit_page = self.new_commit_page();
+                }
+                self.back();
+                return Ok(Redraw(false));
+            }
+            (NavigationAction::Select, AppMode::SelectSyntax) => {
+                let syntax = self.syntax_picker.as_ref().and_then(|p| p.selected());
+                if let (Some(pager), Some(syntax)) = (self.blob_pager.as_mut(), syntax) {
+                    pager.set_syntax(syntax);
+                }
+                self.back();
+                return Ok(Redraw(false));
+            }
+            (NavigationAction::Pick, AppMode::BrowseTrees | AppMode::BrowseRefs) => {
+                self.pick()?;
+                return Ok(Redraw(false));
+            }
+            (NavigationAction::ToggleColumns, AppMode::BrowseTrees) => {
+                self.columns = !self.columns;
+                return Ok(Redraw(false));
+            }
+            (NavigationAction::HistoryBack, _) => {
+                if let Some(location) = self.history.back() {
+                    self.restore_location(&location)?;
+                }
+                return Ok(Redraw(false));
+            }
+            (NavigationAction::HistoryForward, _) => {
+                if let Some(location) = self.history.forward() {
+                    self.restore_location(&location)?;
+                }
+                return Ok(Redraw(false));
+            }
+            (NavigationAction::JumpList, AppMode::JumpList) => {
+                return Ok(Redraw(false));
+            }
+            (NavigationAction::JumpList, _) => {
+                self.jump_list = Some(JumpList::new(&self.history));
+                self.mode_history.push(AppMode::JumpList);
+                return Ok(Redraw(false));
+            }
+            (NavigationAction::Select, AppMode::JumpList) => {
+                let index = self.jump_list.as_ref().and_then(|list| list.selected());
+                self.back();
+                if let Some(location) = index.and_then(|index| self.history.jump(index)) {
+                    self.restore_location(&location)?;
+                }
+                return Ok(Redraw(false));
+            }
+            (NavigationAction::NewTab, _) => {
+                self.open_tab(Tab::new());
+                return Ok(Redraw(false));
+            }
+            (NavigationAction::DuplicateTab, _) => {
+                self.duplicate_tab()?;
+                return Ok(Redraw(false));
+            }
+            (NavigationAction::CloseTab, _) => {
+                self.close_tab();
+                return Ok(Redraw(false));
+            }
+            (NavigationAction::NextTab, _) => {
+                self.switch_tab((self.current_tab + 1) % self.tabs.len());
+                return Ok(Redraw(false));
+            }
+            (NavigationAction::PreviousTab, _) => {
+                self.switch_tab((self.current_tab + self.tabs.len() - 1) % self.tabs.len());
+                return Ok(Redraw(false));
+            }
             (NavigationAction::Select, _) => {
                 self.select()?;
                 return Ok(Redraw(false));
@@ -269,9 +1020,36 @@ impl<'repo, 'syntax> App<'repo, 'syntax> {
                 self.back();
                 return Ok(Redraw(false));
             }
+            (NavigationAction::JumpToTip, AppMode::BrowseTrees | AppMode::ViewBlob) => {
+                self.jump_to_tip()?;
+                return Ok(Redraw(false));
+            }
+            (NavigationAction::Refresh, _) => {
+                self.refresh()?;
+                return Ok(Redraw(false));
+            }
+            (NavigationAction::ErrorLog, AppMode::ErrorLog) => {
+                return Ok(Redraw(false));
+            }
+            (NavigationAction::ErrorLog, _) => {
+                self.error_log.mark_seen();
+                self.mode_history.push(AppMode::ErrorLog);
+                return Ok(Redraw(false));
+            }
             _ => {}
         }
 
+        if let NavigationAction::Tick = action {
+            self.spinner_frame = self.spinner_frame.wrapping_add(1);
+            self.receive_results();
+            if let Err(e) = self.check_for_changes() {
+                self.warn(e);
+            }
+            if let Some(pager) = self.preview.as_mut().and_then(|p| p.blob_pager_mut()) {
+                pager.next_tick(false)?;
+            }
+        }
+
         // Handle page navigation
         let page: Box<&mut dyn Navigable> = match self.mode() {
             AppMode::BrowseRefs => Box::new(&mut self.refs_page),
@@ -280,13 +1058,62 @@ impl<'repo, 'syntax> App<'repo, 'syntax> {
                     .last_mut()
                     .expect("No tree browsing page in tree mode"),
             ),
+            AppMode::BrowseWorkdir => Box::new(
+                self.workdir_pages
+                    .last_mut()
+                    .expect("No working tree page in working tree mode"),
+            ),
+            AppMode::BrowseStashes => Box::new(
+                self.stash_page
+                    .as_mut()
+                    .expect("No stash page in stash browsing mode"),
+            ),
+            AppMode::BrowseStash => Box::new(
+                self.stash_entry_page
+                    .as_mut()
+                    .expect("No stash entry page in stash entry mode"),
+            ),
+            AppMode::BrowseReflog => Box::new(
+                self.reflog_page
+                    .as_mut()
+                    .expect("No reflog page in reflog mode"),
+            ),
+            AppMode::BrowseLog => {
+                Box::new(self.log_page.as_mut().expect("No log page in log mode"))
+            }
+            AppMode::BrowsePickaxe => Box::new(
+                self.pickaxe_page
+                    .as_mut()
+                    .expect("No pickaxe page in pickaxe mode"),
+            ),
             AppMode::ViewBlob => Box::new(
                 self.blob_pager
                     .as_mut()
                     .expect("No blob browser page in blob mode"),
             ),
-            _ => {
-                return Ok(Redraw(false));
+            AppMode::ViewCommit => Box::new(
+                self.commit_page
+                    .as_mut()
+                    .expect("No commit page in commit mode"),
+            ),
+            AppMode::ErrorLog => Box::new(&mut self.error_log),
+            AppMode::SelectSyntax => Box::new(
+                self.syntax_picker
+                    .as_mut()
+                    .expect("No syntax picker in syntax selection mode"),
+            ),
+            AppMode::SelectNotesRef => Box::new(
+                self.notes_picker
+                    .as_mut()
+                    .expect("No notes ref picker in notes ref selection mode"),
+            ),
+            AppMode::JumpList => Box::new(
+                self.jump_list
+                    .as_mut()
+                    .expect("No jump list in jump list mode"),
+            ),
+            _ => {
+                return Ok(Redraw(false));
             }
         };
 
@@ -295,10 +1122,7 @@ impl<'repo, 'syntax> App<'repo, 'syntax> {
                 page.next_tick(false)?;
             }
             NavigationAction::Home => page.home(self.height),
-            NavigationAction::End => {
-                page.next_tick(true)?;
-                page.end(self.height);
-            }
+            NavigationAction::End => page.end(self.height),
             NavigationAction::PageUp => page.pageup(self.height),
             NavigationAction::PageDown => page.pagedown(self.height),
             NavigationAction::NextSelection => page.next_selection(),
@@ -310,6 +1134,29 @@ impl<'repo, 'syntax> App<'repo, 'syntax> {
             // Handled above
             NavigationAction::Select => {}
             NavigationAction::Back => {}
+            NavigationAction::ErrorLog => {}
+            NavigationAction::JumpToTip => {}
+            NavigationAction::Refresh => {}
+            NavigationAction::SetSyntax => {}
+            NavigationAction::Reflog => {}
+            NavigationAction::Log => {}
+            NavigationAction::Pickaxe => {}
+            NavigationAction::FilterHistory => {}
+            NavigationAction::NextField => {}
+            NavigationAction::Input(_) => {}
+            NavigationAction::DeleteChar => {}
+            NavigationAction::CommitDetails => {}
+            NavigationAction::NotesRef => {}
+            NavigationAction::Pick => {}
+            NavigationAction::ToggleColumns => {}
+            NavigationAction::HistoryBack => {}
+            NavigationAction::HistoryForward => {}
+            NavigationAction::JumpList => {}
+            NavigationAction::NewTab => {}
+            NavigationAction::DuplicateTab => {}
+            NavigationAction::CloseTab => {}
+            NavigationAction::NextTab => {}
+            NavigationAction::PreviousTab => {}
             // Handled outside of app
             NavigationAction::Exit => {}
         }
@@ -317,6 +1164,33 @@ impl<'repo, 'syntax> App<'repo, 'syntax> {
     }
 
     pub fn select(&mut self) -> Result<(), GitBrowserError> {
+        match self.mode() {
+            AppMode::BrowseWorkdir => return self.select_workdir(),
+            AppMode::BrowseStashes => return self.select_stash(),
+            AppMode::BrowseStash => return self.select_stash_part(),
+            AppMode::BrowsePickaxe => return self.select_pickaxe(),
+            _ => {}
+        }
+        if matches!(self.mode(), AppMode::BrowseRefs) {
+            if self.refs_page.selected_item() == STASH_REF {
+                self.stash_page = Some(StashPage::new(self.repo, self.workers.handle()));
+                self.mode_history.push(AppMode::BrowseStashes);
+                return Ok(());
+            }
+            if let Some(revision) = self.refs_page.selected_pseudo_revision() {
+                let page = WorkdirPage::new(
+                    self.repo,
+                    revision,
+                    "".to_string(),
+                    "".to_string(),
+                    self.workers.handle(),
+                )?;
+                self.workdir_pages.push(page);
+                self.mode_history.push(AppMode::BrowseWorkdir);
+                return Ok(());
+            }
+        }
+
         let page: Box<&mut dyn Navigable> = match self.mode() {
             AppMode::BrowseRefs => Box::new(&mut self.refs_page),
             AppMode::BrowseTrees => Box::new(
@@ -329,38 +1203,66 @@ impl<'repo, 'syntax> App<'repo, 'syntax> {
                     .as_mut()
                     .expect("No blob browser page in blob mode"),
             ),
+            AppMode::BrowseReflog => Box::new(
+                self.reflog_page
+                    .as_mut()
+                    .expect("No reflog page in reflog mode"),
+            ),
+            AppMode::BrowseLog => {
+                Box::new(self.log_page.as_mut().expect("No log page in log mode"))
+            }
             _ => {
                 return Ok(());
             }
         };
 
-        let (object, name) = match page.select() {
+        let (object, name) = match page.select()? {
             Some(selection) => selection,
             None => return Ok(()),
         };
+        let selected_item = page.selected_item();
 
         match object.kind() {
+            Some(ObjectType::Blob) if self.pick.is_some() => self.pick(),
             Some(ObjectType::Blob) => {
+                let language = self.tree_pages.first().map(|page| page.tree().clone());
+                let language = match language {
+                    Some(root) => self.linguist_language(&root, &self.entry_path(&selected_item)),
+                    None => None,
+                };
                 let pager = BlobPager::from_object(
                     self.repo,
                     object,
-                    page.selected_item(),
+                    selected_item,
                     self.syntax_set,
                     self.theme,
+                    self.workers.handle(),
+                    language.as_deref(),
                 )?;
                 self.blob_pager = Some(pager);
                 self.mode_history.push(AppMode::ViewBlob);
                 Ok(())
             }
             Some(ObjectType::Tree) => {
-                self.tree_pages.push(TreePage::new(self.repo, object, name));
+                self.tree_pages.push(TreePage::new(
+                    self.repo,
+                    object,
+                    name,
+                    self.workers.handle(),
+                )?);
                 self.mode_history.push(AppMode::BrowseTrees);
                 Ok(())
             }
             Some(ObjectType::Commit) => {
-                let commit = object.peel_to_commit().expect("Unable to peel commit");
-                self.commit = Some(commit);
-                self.tree_pages.push(TreePage::new(self.repo, object, name));
+                let commit = object.peel_to_commit().map_err(|e| {
+                    GitBrowserError::git(ErrorKind::CommitReference, e).with_oid(object.id())
+                })?;
+                let page = TreePage::new(self.repo, object, name, self.workers.handle())?;
+                if matches!(self.mode(), AppMode::BrowseRefs) {
+                    self.reference = Some(selected_item);
+                }
+                self.set_commit(commit);
+                self.tree_pages.push(page);
                 self.mode_history.push(AppMode::BrowseTrees);
                 Ok(())
             }
@@ -368,46 +1270,1053 @@ impl<'repo, 'syntax> App<'repo, 'syntax> {
         }
     }
 
+    /// Pick the selected tree entry or reference in `--pick` mode.
+    fn pick(&mut self) -> Result<(), GitBrowserError> {
+        let format = match &self.pick {
+            Some(format) => format,
+            None => return Ok(()),
+        };
+        let pick = match self.mode() {
+            AppMode::BrowseRefs => {
+                if self.refs_page.selected_pseudo_revision().is_some() {
+                    return Ok(());
+                }
+                let object = match self.refs_page.select()? {
+                    Some((object, _)) => object,
+                    None => return Ok(()),
+                };
+                let name = self.refs_page.selected_item();
+                Pick {
+                    rev: name.to_string(),
+                    path: "".to_string(),
+                    oid: object.id(),
+                    reference: Some(name),
+                }
+            }
+            AppMode::BrowseTrees => {
+                let page = self
+                    .tree_pages
+                    .last()
+                    .expect("No tree browsing page in tree mode");
+                let (object, commit) = match (page.select()?, &self.commit) {
+                    (Some((object, _)), Some(commit)) => (object, commit),
+                    _ => return Ok(()),
+                };
+                Pick {
+                    rev: commit.id().to_string(),
+                    path: self.entry_path(&page.selected_item()),
+                    oid: object.id(),
+                    reference: self.reference.clone(),
+                }
+            }
+            _ => return Ok(()),
+        };
+        self.picked = Some(pick.format(format));
+        Ok(())
+    }
+
     pub fn back(&mut self) {
         if let Some(mode) = self.mode_history.pop() {
-            match mode {
-                AppMode::BrowseRefs => {
+            self.close(&mode);
+            if self.mode_history.is_empty() {
+                self.mode_history.push(mode);
+            }
+        }
+        if self.tree_pages.is_empty() {
+            self.commit = None;
+            self.note = None;
+            self.commit_signature = None;
+            self.tag_signature = None;
+            self.reference = None;
+            self.moved_to = None;
+        }
+    }
+
+    /// Drop the page of a mode that has just been left.
+    fn close(&mut self, mode: &AppMode) {
+        match mode {
+            AppMode::BrowseRefs => {
+                self.tree_pages.pop();
+            }
+            AppMode::BrowseTrees => {
+                // The first page stays when there is nothing to go
+                // back to
+                if self.tree_pages.len() > 1 || !self.mode_history.is_empty() {
                     self.tree_pages.pop();
                 }
-                AppMode::BrowseTrees => {
-                    if self.tree_pages.len() > 1 {
-                        self.tree_pages.pop();
+            }
+            AppMode::BrowseWorkdir => {
+                self.workdir_pages.pop();
+            }
+            AppMode::BrowseStashes => {
+                self.stash_page = None;
+            }
+            AppMode::BrowseStash => {
+                self.stash_entry_page = None;
+            }
+            AppMode::BrowseReflog => {
+                self.reflog_page = None;
+            }
+            AppMode::BrowseLog => {
+                self.log_page = None;
+            }
+            AppMode::BrowsePickaxe => {
+                self.pickaxe_page = None;
+            }
+            AppMode::EditPickaxe => {
+                self.pickaxe_form = None;
+            }
+            AppMode::EditHistoryFilter => {
+                self.filter_form = None;
+            }
+            AppMode::ViewBlob => {
+                self.blob_pager = None;
+            }
+            AppMode::ErrorLog => {}
+            AppMode::ViewCommit => {
+                self.commit_page = None;
+            }
+            AppMode::SelectSyntax => {
+                self.syntax_picker = None;
+            }
+            AppMode::SelectNotesRef => {
+                self.notes_picker = None;
+            }
+            AppMode::JumpList => {
+                self.jump_list = None;
+            }
+            AppMode::Error => {
+                self.active_error = None;
+            }
+        }
+    }
+
+    /// Take the results of background work: the refs and failures, which go
+    /// to the whole app, and those the current tab's pages asked for. Pages
+    /// of parked tabs keep theirs until the tab is switched to.
+    fn receive_results(&mut self) {
+        let results: Vec<TaskResult> = self.workers.results().collect();
+        for result in results {
+            match result {
+                TaskResult::Refs(refs) => {
+                    self.refs_page.receive_refs(refs);
+                    let decorations = self.refs_page.decorations();
+                    let log_pages = self.tabs.iter_mut().map(|tab| &mut tab.log_page);
+                    for page in log_pages.chain([&mut self.log_page]).flatten() {
+                        page.set_decorations(decorations.clone());
                     }
                 }
-                AppMode::ViewBlob => {
-                    self.blob_pager = None;
-                }
-                AppMode::ExternalEditor => {
-                    self.external_editor = None;
-                }
-                AppMode::Error => {
-                    self.active_error = None;
+                TaskResult::Failed(e) => self.warn(e),
+                _ => {}
+            }
+        }
+
+        let mut failures = vec![];
+        let verifications: Vec<TaskResult> = self.mailbox.results().collect();
+        for result in verifications {
+            match result {
+                TaskResult::Verification { id, verification } => {
+                    self.receive_verification(id, verification)
                 }
+                TaskResult::Failed(e) => failures.push(e),
+                _ => {}
             }
-            if self.mode_history.is_empty() {
-                self.mode_history.push(mode);
+        }
+        for page in self.tree_pages.iter_mut() {
+            failures.extend(page.receive_results());
+        }
+        for page in self.workdir_pages.iter_mut() {
+            failures.extend(page.receive_results());
+        }
+        if let Some(page) = self.stash_page.as_mut() {
+            failures.extend(page.receive_results());
+        }
+        if let Some(page) = self.log_page.as_mut() {
+            failures.extend(page.receive_results());
+        }
+        if let Some(page) = self.pickaxe_page.as_mut() {
+            failures.extend(page.receive_results());
+        }
+        if let Some(pager) = self.blob_pager.as_mut() {
+            failures.extend(pager.receive_results());
+        }
+        if let Some(pager) = self.preview.as_mut().and_then(|p| p.blob_pager_mut()) {
+            failures.extend(pager.receive_results());
+        }
+        if let Some(page) = self.stash_entry_page.as_mut() {
+            failures.extend(page.receive_results());
+        }
+        for e in failures {
+            self.warn(e);
+        }
+        // The diff opens only if its stash is still the one on screen
+        let diff = match self.mode() {
+            AppMode::BrowseStash => self.stash_entry_page.as_mut().and_then(|p| p.take_diff()),
+            _ => None,
+        };
+        if let Some((name, diff)) = diff {
+            if let Err(e) = self.open_stash_diff(&name, &diff) {
+                self.warn(e);
             }
         }
+    }
+
+    /// Refresh if anything changed on disk.
+    fn check_for_changes(&mut self) -> Result<(), GitBrowserError> {
+        let changed = match &self.watcher {
+            Some(watcher) => watcher.changed()?,
+            None => false,
+        };
+        if changed {
+            self.refresh()?;
+        }
+        Ok(())
+    }
+
+    /// Reload the refs and the pages of every tab. Errors from tabs other
+    /// than the current one are only logged.
+    fn refresh(&mut self) -> Result<(), GitBrowserError> {
+        self.refs_page.refresh();
+        // The mailmap may have been added or edited
+        self.load_mailmap();
+        let current = self.current_tab;
+        let mut result = Ok(());
+        self.for_each_tab(|app| {
+            if let Err(e) = app.refresh_tab() {
+                if app.current_tab == current {
+                    result = Err(e);
+                } else {
+                    app.warn(e);
+                }
+            }
+        });
+        result
+    }
+
+    /// Reload the pages of the current tab and check whether the reference
+    /// it is browsing now points to a different commit.
+    fn refresh_tab(&mut self) -> Result<(), GitBrowserError> {
+        if let Some(page) = self.stash_page.as_mut() {
+            page.refresh();
+        }
+        if let Some(page) = self.reflog_page.as_mut() {
+            page.reload()?;
+        }
+        for page in self.workdir_pages.iter_mut() {
+            page.reload(self.workers.handle())?;
+        }
+        // Notes may have been added or edited
+        self.load_note();
+        self.check_tip()
+    }
+
+    /// Check whether the reference being browsed now points to a different
+    /// commit.
+    fn check_tip(&mut self) -> Result<(), GitBrowserError> {
+        let (reference, commit) = match (&self.reference, &self.commit) {
+            (Some(reference), Some(commit)) => (reference, commit),
+            _ => return Ok(()),
+        };
+        let tip = self
+            .repo
+            .revparse_single(reference)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|e| GitBrowserError::git(ErrorKind::Reference, e).with_path(reference))?;
+        self.moved_to = if tip.id() == commit.id() {
+            None
+        } else {
+            Some(tip.id())
+        };
+        Ok(())
+    }
+
+    /// Replace the pages being browsed with the same path at the commit the
+    /// reference has moved to.
+    fn jump_to_tip(&mut self) -> Result<(), GitBrowserError> {
+        let (oid, mut location) = match (self.moved_to, self.current_location(true)) {
+            (Some(oid), Some(location)) => (oid, location),
+            _ => return Ok(()),
+        };
+        location.commit = oid;
+        let tab = self.open_location(&location)?;
+        self.tree_pages = tab.tree_pages;
+        self.blob_pager = tab.blob_pager;
+        if let Some(commit) = tab.commit {
+            self.set_commit(commit);
+        }
+        self.moved_to = None;
+        Ok(())
+    }
+
+    /// Where the current tab is in the commit it is browsing, leaving out
+    /// the blob unless `with_blob` is set.
+    fn current_location(&self, with_blob: bool) -> Option<Location> {
+        let commit = self.commit.as_ref()?;
         if self.tree_pages.is_empty() {
-            self.commit = None;
+            return None;
+        }
+        Some(Location {
+            label: self.tab_label(),
+            commit: commit.id(),
+            reference: self.reference.clone(),
+            directories: self.tree_pages.iter().map(|page| page.title()).collect(),
+            selected: self
+                .tree_pages
+                .iter()
+                .map(|page| page.selected_item())
+                .collect(),
+            blob: self
+                .blob_pager
+                .as_ref()
+                .filter(|_| with_blob)
+                .map(|pager| (pager.name.to_string(), pager.top())),
+        })
+    }
+
+    /// The location to remember for the current tab, if it is browsing a
+    /// commit rather than a list.
+    fn location(&self) -> Option<Location> {
+        // Only a blob opened from the trees is at a path
+        let with_blob = match self.mode_history.as_slice() {
+            [.., AppMode::BrowseTrees] => false,
+            [.., AppMode::BrowseTrees, AppMode::ViewBlob] => true,
+            _ => return None,
+        };
+        self.current_location(with_blob)
+    }
+
+    fn update_history(&mut self) {
+        // Popups and details shown over a location don't leave it
+        if matches!(
+            self.mode(),
+            AppMode::ViewCommit
+                | AppMode::ErrorLog
+                | AppMode::SelectSyntax
+                | AppMode::SelectNotesRef
+                | AppMode::JumpList
+                | AppMode::EditPickaxe
+                | AppMode::EditHistoryFilter
+                | AppMode::Error
+        ) {
+            return;
+        }
+        let location = self.location();
+        self.history.visit(location);
+    }
+
+    /// Open a location from the history in place of whatever was opened
+    /// above the lists of revisions, so that Back still returns to them.
+    fn restore_location(&mut self, location: &Location) -> Result<(), GitBrowserError> {
+        let tab = self.open_location(location)?;
+        let lists = self
+            .mode_history
+            .iter()
+            .take_while(|mode| {
+                matches!(
+                    mode,
+                    AppMode::BrowseRefs
+                        | AppMode::BrowseLog
+                        | AppMode::BrowseReflog
+                        | AppMode::BrowseStashes
+                        | AppMode::BrowseStash
+                        | AppMode::BrowsePickaxe
+                )
+            })
+            .count();
+        while self.mode_history.len() > lists {
+            if let Some(mode) = self.mode_history.pop() {
+                self.close(&mode);
+            }
+        }
+        self.mode_history
+            .extend(tab.tree_pages.iter().map(|_| AppMode::BrowseTrees));
+        if tab.blob_pager.is_some() {
+            self.mode_history.push(AppMode::ViewBlob);
+        }
+        self.tree_pages = tab.tree_pages;
+        self.blob_pager = tab.blob_pager;
+        self.reference = location.reference.clone();
+        if let Some(commit) = tab.commit {
+            self.set_commit(commit);
+        }
+        self.check_tip()
+    }
+
+    /// A tab with the directories and blob of a location opened, and the
+    /// entries and line that were selected there selected again.
+    fn open_location(
+        &mut self,
+        location: &Location,
+    ) -> Result<Tab<'repo, 'syntax>, GitBrowserError> {
+        let oid = location.commit;
+        let object = self
+            .repo
+            .find_object(oid, None)
+            .map_err(|e| GitBrowserError::git(ErrorKind::CommitReference, e).with_oid(oid))?;
+        let commit = object
+            .peel_to_commit()
+            .map_err(|e| GitBrowserError::git(ErrorKind::CommitReference, e).with_oid(oid))?;
+        let root_tree = commit
+            .tree()
+            .map_err(|e| GitBrowserError::git(ErrorKind::TreeReference, e).with_oid(oid))?;
+
+        let mut directories = location.directories.iter();
+        let root_name = directories.next().cloned().unwrap_or_default();
+        let mut tree_pages = vec![TreePage::new(
+            self.repo,
+            object,
+            root_name,
+            self.workers.handle(),
+        )?];
+        let mut path = PathBuf::new();
+        for name in directories {
+            path.push(name);
+            let object = root_tree
+                .get_path(&path)
+                .and_then(|entry| entry.to_object(self.repo))
+                .map_err(|e| {
+                    GitBrowserError::git(ErrorKind::TreeEntry, e)
+                        .with_oid(oid)
+                        .with_path(path.to_string_lossy())
+                })?;
+            tree_pages.push(TreePage::new(
+                self.repo,
+                object,
+                name.to_string(),
+                self.workers.handle(),
+            )?);
+        }
+        for (page, name) in tree_pages.iter_mut().zip(&location.selected) {
+            page.select_name(name);
+        }
+
+        let blob_pager = match &location.blob {
+            Some((name, top)) => {
+                path.push(name);
+                let language = self.linguist_language(&root_tree, &path.to_string_lossy());
+                let object = root_tree
+                    .get_path(&path)
+                    .and_then(|entry| entry.to_object(self.repo))
+                    .map_err(|e| {
+                        GitBrowserError::git(ErrorKind::TreeEntry, e)
+                            .with_oid(oid)
+                            .with_path(path.to_string_lossy())
+                    })?;
+                let mut pager = BlobPager::from_object(
+                    self.repo,
+                    object,
+                    name.to_string(),
+                    self.syntax_set,
+                    self.theme,
+                    self.workers.handle(),
+                    language.as_deref(),
+                )?;
+                pager.scroll_to(*top);
+                Some(pager)
+            }
+            None => None,
+        };
+
+        Ok(Tab {
+            commit: Some(commit),
+            tree_pages,
+            blob_pager,
+            ..Tab::default()
+        })
+    }
+
+    /// Open a new tab browsing the same path as the current one, or at the
+    /// references if the current tab isn't browsing a commit.
+    fn duplicate_tab(&mut self) -> Result<(), GitBrowserError> {
+        // Only a blob opened from the trees is at a path
+        let viewing_blob = matches!(
+            self.mode_history.as_slice(),
+            [.., AppMode::BrowseTrees, AppMode::ViewBlob]
+        );
+        let location = match self.current_location(viewing_blob) {
+            Some(location) => location,
+            None => {
+                self.open_tab(Tab::new());
+                return Ok(());
+            }
+        };
+        let mut tab = self.open_location(&location)?;
+        if let Some(AppMode::BrowseRefs) = self.mode_history.first() {
+            tab.mode_history.push(AppMode::BrowseRefs);
+        }
+        tab.mode_history
+            .extend(tab.tree_pages.iter().map(|_| AppMode::BrowseTrees));
+        if tab.blob_pager.is_some() {
+            tab.mode_history.push(AppMode::ViewBlob);
+        }
+        let reference = self.reference.clone();
+        let commit = tab.commit.take();
+        self.open_tab(tab);
+        self.reference = reference;
+        if let Some(commit) = commit {
+            self.set_commit(commit);
+        }
+        Ok(())
+    }
+
+    /// Switch to a new tab, placed after the current one.
+    fn open_tab(&mut self, tab: Tab<'repo, 'syntax>) {
+        let index = self.current_tab + 1;
+        self.tabs.insert(index, tab);
+        self.switch_tab(index);
+    }
+
+    /// Close the current tab, unless it is the only one.
+    fn close_tab(&mut self) {
+        if self.tabs.len() < 2 {
+            return;
+        }
+        let closing = self.current_tab;
+        let next = if closing + 1 < self.tabs.len() {
+            closing + 1
+        } else {
+            closing - 1
+        };
+        self.switch_tab(next);
+        // Dropping the tab cancels the work its pages were waiting for
+        self.tabs.remove(closing);
+        if closing < next {
+            self.current_tab -= 1;
+        }
+    }
+
+    fn switch_tab(&mut self, index: usize) {
+        if index == self.current_tab || index >= self.tabs.len() {
+            return;
+        }
+        let parked = self.park();
+        self.tabs[self.current_tab] = parked;
+        let tab = mem::take(&mut self.tabs[index]);
+        self.unpark(tab);
+        self.current_tab = index;
+    }
+
+    /// Run `f` with each tab in turn as the current one.
+    fn for_each_tab(&mut self, mut f: impl FnMut(&mut Self)) {
+        let current = self.current_tab;
+        for index in 0..self.tabs.len() {
+            self.switch_tab(index);
+            f(self);
+        }
+        self.switch_tab(current);
+    }
+
+    /// Move the state of the current tab out of the app.
+    fn park(&mut self) -> Tab<'repo, 'syntax> {
+        // Highlighting is only worth doing for what is on screen. It starts
+        // again once the tab is switched back to
+        if let Some(pager) = self.blob_pager.as_mut() {
+            pager.suspend();
+        }
+        if let Some(pager) = self.preview.as_mut().and_then(|p| p.blob_pager_mut()) {
+            pager.suspend();
+        }
+        Tab {
+            label: self.tab_label(),
+            commit: self.commit.take(),
+            note: self.note.take(),
+            commit_signature: self.commit_signature.take(),
+            tag_signature: self.tag_signature.take(),
+            reference: self.reference.take(),
+            moved_to: self.moved_to.take(),
+            mailbox: mem::take(&mut self.mailbox),
+            tree_pages: mem::take(&mut self.tree_pages),
+            workdir_pages: mem::take(&mut self.workdir_pages),
+            stash_page: self.stash_page.take(),
+            stash_entry_page: self.stash_entry_page.take(),
+            reflog_page: self.reflog_page.take(),
+            log_page: self.log_page.take(),
+            pickaxe_form: self.pickaxe_form.take(),
+            pickaxe_page: self.pickaxe_page.take(),
+            filter_form: self.filter_form.take(),
+            blob_pager: self.blob_pager.take(),
+            preview: self.preview.take(),
+            syntax_picker: self.syntax_picker.take(),
+            commit_page: self.commit_page.take(),
+            notes_picker: self.notes_picker.take(),
+            jump_list: self.jump_list.take(),
+            mode_history: mem::take(&mut self.mode_history),
+            history: mem::take(&mut self.history),
+            active_error: self.active_error.take(),
+        }
+    }
+
+    fn unpark(&mut self, tab: Tab<'repo, 'syntax>) {
+        self.commit = tab.commit;
+        self.note = tab.note;
+        self.commit_signature = tab.commit_signature;
+        self.tag_signature = tab.tag_signature;
+        self.reference = tab.reference;
+        self.moved_to = tab.moved_to;
+        self.mailbox = tab.mailbox;
+        self.tree_pages = tab.tree_pages;
+        self.workdir_pages = tab.workdir_pages;
+        self.stash_page = tab.stash_page;
+        self.stash_entry_page = tab.stash_entry_page;
+        self.reflog_page = tab.reflog_page;
+        self.log_page = tab.log_page;
+        self.pickaxe_form = tab.pickaxe_form;
+        self.pickaxe_page = tab.pickaxe_page;
+        self.filter_form = tab.filter_form;
+        self.blob_pager = tab.blob_pager;
+        self.preview = tab.preview;
+        self.syntax_picker = tab.syntax_picker;
+        self.commit_page = tab.commit_page;
+        self.notes_picker = tab.notes_picker;
+        self.jump_list = tab.jump_list;
+        self.mode_history = tab.mode_history;
+        self.history = tab.history;
+        self.active_error = tab.active_error;
+    }
+
+    /// Preview the entry selected in the current tree when trees are shown in
+    /// columns, loading blobs once their metadata has arrived.
+    fn update_preview(&mut self) {
+        if !self.columns || !matches!(self.mode(), AppMode::BrowseTrees) {
+            self.preview = None;
+            return;
+        }
+        let (entry, metadata) = match self.tree_pages.last() {
+            Some(page) => (
+                page.selected_entry().map(|entry| entry.to_owned()),
+                page.selected_metadata().copied(),
+            ),
+            None => return,
+        };
+        let entry = match entry {
+            Some(entry) => entry,
+            None => {
+                self.preview = None;
+                return;
+            }
+        };
+        let current = self.preview.as_ref().is_some_and(|preview| {
+            preview.id() == entry.id() && !(preview.is_loading() && metadata.is_some())
+        });
+        if current {
+            return;
+        }
+
+        let name = String::from_utf8_lossy(entry.name_bytes()).to_string();
+        let root = self.tree_pages.first().map(|page| page.tree().clone());
+        let language = match (root, entry.kind(), metadata) {
+            (Some(root), Some(ObjectType::Blob), Some(_)) => {
+                self.linguist_language(&root, &self.entry_path(&name))
+            }
+            _ => None,
+        };
+        self.preview = Some(Preview::load(
+            self.repo,
+            &entry,
+            metadata.as_ref(),
+            self.syntax_set,
+            self.theme,
+            self.workers.handle(),
+            language.as_deref(),
+        ));
+    }
+
+    /// Where the current tab is, like `main:src/app.rs`, for the tab bar.
+    fn tab_label(&self) -> String {
+        let revision = match (&self.reference, &self.commit) {
+            (Some(reference), _) => ["refs/heads/", "refs/remotes/", "refs/tags/", "refs/"]
+                .iter()
+                .find_map(|prefix| reference.strip_prefix(prefix))
+                .unwrap_or(reference)
+                .to_string(),
+            (None, Some(commit)) => commit.id().to_string()[..7].to_string(),
+            (None, None) => match self.workdir_pages.first() {
+                Some(page) => page.revision().as_str().to_string(),
+                None => "refs".to_string(),
+            },
+        };
+        let mut path: Vec<String> = self.page_titles().into_iter().skip(1).collect();
+        if let Some(pager) = &self.blob_pager {
+            path.push(pager.name.to_string());
+        }
+        if path.is_empty() {
+            revision
+        } else {
+            format!("{}:{}", revision, path.join("/"))
         }
     }
 
     pub fn error(&mut self, error: GitBrowserError) {
+        self.error_log
+            .record(Severity::Error, &error, self.log_context());
+        // The popup already shows this error
+        self.error_log.mark_seen();
         self.active_error = Some(error);
         self.mode_history.push(AppMode::Error);
     }
 
+    /// Record an error in the error log without interrupting the user.
+    pub fn warn(&mut self, error: GitBrowserError) {
+        self.error_log
+            .record(Severity::Warning, &error, self.log_context());
+    }
+
+    fn log_context(&self) -> LogContext {
+        LogContext {
+            mode: format!("{:?}", self.mode()),
+            reference: self
+                .reference
+                .clone()
+                .or_else(|| self.commit.as_ref().map(|commit| commit.id().to_string()))
+                .or_else(|| {
+                    self.workdir_pages
+                        .first()
+                        .map(|page| page.revision().as_str().to_string())
+                }),
+            path: self.path(),
+        }
+    }
+
+    /// The path of an entry in the tree currently being browsed.
+    fn entry_path(&self, name: &str) -> String {
+        let mut parts: Vec<String> = self.page_titles().into_iter().skip(1).collect();
+        parts.push(name.to_string());
+        parts.join("/")
+    }
+
+    /// The titles of the directory pages being browsed, starting with the
+    /// root.
+    fn page_titles(&self) -> Vec<String> {
+        if self.workdir_pages.is_empty() {
+            self.tree_pages.iter().map(|page| page.title()).collect()
+        } else {
+            self.workdir_pages.iter().map(|page| page.title()).collect()
+        }
+    }
+
+    fn load_mailmap(&mut self) {
+        if let Err(e) = self.signature_formatter.reload(self.repo) {
+            self.warn(e);
+        }
+    }
+
+    fn set_commit(&mut self, commit: Commit<'repo>) {
+        self.commit = Some(commit);
+        self.load_note();
+        self.load_signatures();
+    }
+
+    /// Look up the signatures of the commit being browsed and the tag it
+    /// was reached through, and start verifying them if asked to.
+    fn load_signatures(&mut self) {
+        let id = match &self.commit {
+            Some(commit) => commit.id(),
+            None => return,
+        };
+        self.commit_signature = match signing::commit_signature(self.repo, id) {
+            Ok(status) => Some(status),
+            Err(e) => {
+                self.warn(e);
+                None
+            }
+        };
+
+        let tag = self
+            .reference
+            .as_ref()
+            .and_then(|reference| self.repo.revparse_single(reference).ok())
+            .and_then(|object| object.into_tag().ok());
+        self.tag_signature = match tag {
+            Some(tag) => match signing::tag_signature(self.repo, &tag) {
+                Ok(status) => Some((tag.name().unwrap_or_default().to_string(), tag.id(), status)),
+                Err(e) => {
+                    self.warn(e);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        if !self.verify_signatures {
+            return;
+        }
+        let signatures = [
+            self.commit_signature.as_ref().map(|status| (id, status)),
+            self.tag_signature
+                .as_ref()
+                .map(|(_, tag_id, status)| (*tag_id, status)),
+        ];
+        for (id, status) in signatures.into_iter().flatten() {
+            if let SignatureStatus::Signed(info) = status {
+                self.workers.handle().submit_to(
+                    Task::VerifySignature {
+                        id,
+                        method: info.method,
+                        block: info.block.to_string(),
+                        signed_data: info.signed_data.clone(),
+                    },
+                    None,
+                    &self.mailbox,
+                );
+            }
+        }
+    }
+
+    fn receive_verification(&mut self, id: Oid, verification: Verification) {
+        let commit_id = self.commit.as_ref().map(|commit| commit.id());
+        let status = match (&mut self.commit_signature, &mut self.tag_signature) {
+            (Some(status), _) if commit_id == Some(id) => status,
+            (_, Some((_, tag_id, status))) if *tag_id == id => status,
+            // No longer browsing what was verified
+            _ => return,
+        };
+        if let SignatureStatus::Signed(info) = status {
+            info.verification = Some(verification);
+        }
+        if self.commit_page.is_some() {
+            self.commit_page = self.new_commit_page();
+        }
+    }
+
+    fn new_commit_page(&self) -> Option<CommitPage> {
+        let commit = self.commit.as_ref()?;
+        let mut signatures = vec![];
+        if let Some(status) = &self.commit_signature {
+            signatures.push(("Signature".to_string(), status));
+        }
+        if let Some((name, _, status)) = &self.tag_signature {
+            signatures.push((format!("Tag {} signature", name), status));
+        }
+        Some(CommitPage::new(
+            commit,
+            &self.notes_ref,
+            self.note.as_deref(),
+            &signatures,
+            &self.signature_formatter,
+        ))
+    }
+
+    /// Look up the note attached to the commit being browsed.
+    fn load_note(&mut self) {
+        let id = match &self.commit {
+            Some(commit) => commit.id(),
+            None => return,
+        };
+        self.note = match notes::find_note(self.repo, &self.notes_ref, id) {
+            Ok(note) => note,
+            Err(e) => {
+                self.warn(e);
+                None
+            }
+        };
+    }
+
+    /// Open the stash entry selected on the stash page.
+    fn select_stash(&mut self) -> Result<(), GitBrowserError> {
+        let selection = match &self.stash_page {
+            Some(page) => page.select()?,
+            None => None,
+        };
+        if let Some((object, name)) = selection {
+            self.stash_entry_page = Some(StashEntryPage::new(object, name, self.workers.handle())?);
+            self.mode_history.push(AppMode::BrowseStash);
+        }
+        Ok(())
+    }
+
+    /// Browse a tree saved in a stash entry, or view its diff.
+    fn select_stash_part(&mut self) -> Result<(), GitBrowserError> {
+        let page = match self.stash_entry_page.as_mut() {
+            Some(page) => page,
+            None => return Ok(()),
+        };
+        let part = match page.selected_part() {
+            Some(part) => part,
+            None => return Ok(()),
+        };
+        if part == StashPart::Diff {
+            // Opened by `open_stash_diff` once it has been computed
+            page.load_diff();
+            return Ok(());
+        }
+
+        let commit = page.part_commit(part)?;
+        let reference = page.revision(part);
+        let page = TreePage::new(
+            self.repo,
+            commit.as_object().clone(),
+            "".to_string(),
+            self.workers.handle(),
+        )?;
+        self.reference = Some(reference);
+        self.set_commit(commit);
+        self.tree_pages.push(page);
+        self.mode_history.push(AppMode::BrowseTrees);
+        Ok(())
+    }
+
+    /// View the diff of a stash entry.
+    fn open_stash_diff(&mut self, name: &str, diff: &[u8]) -> Result<(), GitBrowserError> {
+        let content = BlobContent::from_file(self.repo, diff)?;
+        let pager = BlobPager::new(
+            content,
+            format!("{}.diff", name),
+            self.syntax_set,
+            self.theme,
+            self.workers.handle(),
+            None,
+        )?;
+        self.blob_pager = Some(pager);
+        self.mode_history.push(AppMode::ViewBlob);
+        Ok(())
+    }
+
+    /// The commit a pickaxe search started from the current page would
+    /// search back from.
+    fn pickaxe_start(&self) -> Result<Option<Oid>, GitBrowserError> {
+        let revision = match self.mode() {
+            AppMode::BrowseRefs => {
+                if self.refs_page.selected_pseudo_revision().is_some() {
+                    return Ok(None);
+                }
+                self.refs_page.selected_item()
+            }
+            AppMode::BrowseLog => match &self.log_page {
+                Some(page) => page.selected_item(),
+                None => return Ok(None),
+            },
+            _ => return Ok(self.commit.as_ref().map(|commit| commit.id())),
+        };
+        let commit = self
+            .repo
+            .revparse_single(&revision)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|e| GitBrowserError::git(ErrorKind::Revision, e).with_path(&revision))?;
+        Ok(Some(commit.id()))
+    }
+
+    /// Open the patch or file selected in the results of a pickaxe search.
+    fn select_pickaxe(&mut self) -> Result<(), GitBrowserError> {
+        let selection = match &self.pickaxe_page {
+            Some(page) => page.selection()?,
+            None => return Ok(()),
+        };
+        let pager = match selection {
+            Some(PickaxeSelection::Diff(name, patch)) => BlobPager::new(
+                BlobContent::from_file(self.repo, &patch)?,
+                name,
+                self.syntax_set,
+                self.theme,
+                self.workers.handle(),
+                None,
+            )?,
+            Some(PickaxeSelection::Blob(commit, path, object)) => {
+                let language = match commit.tree() {
+                    Ok(root) => self.linguist_language(&root, &path),
+                    Err(_) => None,
+                };
+                BlobPager::from_object(
+                    self.repo,
+                    object,
+                    path,
+                    self.syntax_set,
+                    self.theme,
+                    self.workers.handle(),
+                    language.as_deref(),
+                )?
+            }
+            None => return Ok(()),
+        };
+        self.blob_pager = Some(pager);
+        self.mode_history.push(AppMode::ViewBlob);
+        Ok(())
+    }
+
+    /// Open the directory or file selected in the index or working tree.
+    fn select_workdir(&mut self) -> Result<(), GitBrowserError> {
+        let page = match self.workdir_pages.last() {
+            Some(page) => page,
+            None => return Ok(()),
+        };
+        let revision = page.revision();
+        match page.selection()? {
+            Some(WorkdirSelection::Directory(name)) => {
+                let path = self.entry_path(&name);
+                let page =
+                    WorkdirPage::new(self.repo, revision, path, name, self.workers.handle())?;
+                self.workdir_pages.push(page);
+                self.mode_history.push(AppMode::BrowseWorkdir);
+            }
+            Some(WorkdirSelection::File(name, content)) => {
+                let path = self.entry_path(&name);
+                let language = match attributes::workdir_attribute(
+                    self.repo,
+                    revision,
+                    &path,
+                    "linguist-language",
+                ) {
+                    Ok(Some(AttributeValue::Value(language))) => Some(language),
+                    Ok(_) => None,
+                    Err(e) => {
+                        self.warn(e);
+                        None
+                    }
+                };
+                let pager = BlobPager::new(
+                    content,
+                    name,
+                    self.syntax_set,
+                    self.theme,
+                    self.workers.handle(),
+                    language.as_deref(),
+                )
+                .map_err(|e| e.with_path(&path))?;
+                self.blob_pager = Some(pager);
+                self.mode_history.push(AppMode::ViewBlob);
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// The `linguist-language` attribute of a path, which overrides syntax
+    /// detection.
+    fn linguist_language(&mut self, root: &Tree<'repo>, path: &str) -> Option<String> {
+        match attributes::attribute(self.repo, root, path, "linguist-language") {
+            Ok(Some(AttributeValue::Value(language))) => Some(language),
+            Ok(_) => None,
+            Err(e) => {
+                self.warn(e);
+                None
+            }
+        }
+    }
+
+    /// The path of the tree or blob currently being browsed.
+    fn path(&self) -> Option<String> {
+        let mut parts: Vec<String> = self.page_titles().into_iter().skip(1).collect();
+        if let Some(pager) = &self.blob_pager {
+            parts.push(pager.title());
+        }
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join("/"))
+        }
+    }
+
+    /// Ask for the blob being viewed or selected to be opened in the
+    /// external editor, which is done by whoever runs the app as it needs
+    /// the terminal.
     pub fn view_blob(&mut self) -> Result<Redraw, GitBrowserError> {
         self.external_editor = match self.mode() {
             AppMode::ViewBlob => {
                 if let Some(pager) = &self.blob_pager {
-                    Some(ExternalEditor::new(&pager.blob, &pager.name, &self.editor))
+                    Some(ExternalEditor::new(
+                        pager.content(),
+                        &pager.name,
+                        &self.editor,
+                    ))
                 } else {
                     return Ok(Redraw(false));
                 }
@@ -418,7 +2327,7 @@ impl<'repo, 'syntax> App<'repo, 'syntax> {
                     None => return Ok(Redraw(false)),
                 };
 
-                let (object, name) = match page.select() {
+                let (object, name) = match page.select()? {
                     Some(selection) => selection,
                     None => return Ok(Redraw(false)),
                 };
@@ -427,27 +2336,65 @@ impl<'repo, 'syntax> App<'repo, 'syntax> {
                     return Ok(Redraw(false));
                 }
 
-                let blob = object
-                    .into_blob()
-                    .map_err(|_| GitBrowserError::Error(ErrorKind::BlobReference))?;
+                let oid = object.id();
+                let blob = object.into_blob().map_err(|_| {
+                    GitBrowserError::message(ErrorKind::BlobReference, "Object is not a blob")
+                        .with_oid(oid)
+                        .with_path(&name)
+                })?;
 
-                Some(ExternalEditor::new(&blob, &name, &self.editor))
+                let content = BlobContent::new(self.repo, oid, blob.content())
+                    .map_err(|e| e.with_path(&name))?;
+                Some(ExternalEditor::new(&content.data, &name, &self.editor))
             }
             _ => return Ok(Redraw(false)),
         };
-        self.mode_history.push(AppMode::ExternalEditor);
-        if let Some(external_editor) = &mut self.external_editor {
-            if let Some(e) = external_editor.display().err() {
-                self.back();
-                return Err(e);
-            };
-        }
-        // We need to go back to the previous mode after the blocking editor display
-        self.back();
-        Ok(Redraw(true))
+        Ok(Redraw(false))
+    }
+
+    /// The blob to open in the external editor, if one was asked for with
+    /// C-e. It should be opened with [`ExternalEditor::open`] while the
+    /// terminal is handed over to it.
+    pub fn take_external_editor(&mut self) -> Option<ExternalEditor> {
+        self.external_editor.take()
     }
 
     pub fn mode(&self) -> &AppMode {
         self.mode_history.last().expect("no application mode found")
     }
+
+    /// The mode whose page is drawn, which is the one beneath any form or
+    /// popup shown over it.
+    fn page_mode(&self) -> &AppMode {
+        self.mode_history
+            .iter()
+            .rev()
+            .find(|mode| {
+                !matches!(
+                    mode,
+                    AppMode::EditPickaxe
+                        | AppMode::EditHistoryFilter
+                        | AppMode::JumpList
+                        | AppMode::Error
+                )
+            })
+            .unwrap_or(self.mode())
+    }
+
+    /// The form being filled in, if any.
+    fn form(&self) -> Option<&Form> {
+        match self.mode() {
+            AppMode::EditPickaxe => self.pickaxe_form.as_ref().map(|(_, form)| form),
+            AppMode::EditHistoryFilter => self.filter_form.as_ref(),
+            _ => None,
+        }
+    }
+
+    fn form_mut(&mut self) -> Option<&mut Form> {
+        match self.mode() {
+            AppMode::EditPickaxe => self.pickaxe_form.as_mut().map(|(_, form)| form),
+            AppMode::EditHistoryFilter => self.filter_form.as_mut(),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,302 @@
+use std::path::Path;
+
+use git2::{AttrCheckFlags, AttrValue, ObjectType, Repository, Tree};
+
+use crate::app::workdir_page::PseudoRevision;
+use crate::errors::{ErrorKind, GitBrowserError};
+
+const ATTRIBUTES_FILE: &str = ".gitattributes";
+
+/// The state of a gitattributes attribute for a path.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AttributeValue {
+    /// `attr`
+    Set,
+    /// `-attr`
+    Unset,
+    /// `attr=value`
+    Value(String),
+}
+
+/// Look up an attribute for a path using the `.gitattributes` files in a
+/// tree, rather than those in the working directory.
+///
+/// As with git, files deeper in the tree take precedence over those closer
+/// to the root, and later lines take precedence over earlier ones.
+pub fn attribute(
+    repo: &Repository,
+    root: &Tree,
+    path: &str,
+    name: &str,
+) -> Result<Option<AttributeValue>, GitBrowserError> {
+    let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+    let mut value = None;
+    let mut tree = root.clone();
+    for (depth, component) in components.iter().enumerate() {
+        let relative = components[depth..].join("/");
+        if let Some(content) = attributes_file(repo, &tree)? {
+            for line in content.lines() {
+                if let Some(state) = match_line(line, &relative, name) {
+                    value = state;
+                }
+            }
+        }
+
+        if depth + 1 == components.len() {
+            break;
+        }
+        let subtree = match tree.get_name(component) {
+            Some(entry) if entry.kind() == Some(ObjectType::Tree) => entry
+                .to_object(repo)
+                .and_then(|object| object.peel_to_tree())
+                .map_err(|e| {
+                    GitBrowserError::git(ErrorKind::Attributes, e)
+                        .with_oid(entry.id())
+                        .with_path(components[..=depth].join("/"))
+                })?,
+            _ => break,
+        };
+        tree = subtree;
+    }
+    Ok(value)
+}
+
+/// Look up an attribute for a path using the `.gitattributes` files in the
+/// index or the working tree.
+pub fn workdir_attribute(
+    repo: &Repository,
+    revision: PseudoRevision,
+    path: &str,
+    name: &str,
+) -> Result<Option<AttributeValue>, GitBrowserError> {
+    let flags = match revision {
+        PseudoRevision::Index => AttrCheckFlags::INDEX_ONLY,
+        PseudoRevision::Worktree => AttrCheckFlags::FILE_THEN_INDEX,
+    };
+    let value = repo
+        .get_attr_bytes(Path::new(path), name, flags)
+        .map_err(|e| GitBrowserError::git(ErrorKind::Attributes, e).with_path(path))?;
+    Ok(match AttrValue::from_bytes(value) {
+        AttrValue::True => Some(AttributeValue::Set),
+        AttrValue::False => Some(AttributeValue::Unset),
+        AttrValue::String(value) => Some(AttributeValue::Value(value.to_string())),
+        AttrValue::Bytes(value) => Some(AttributeValue::Value(
+            String::from_utf8_lossy(value).to_string(),
+        )),
+        AttrValue::Unspecified => None,
+    })
+}
+
+fn attributes_file(repo: &Repository, tree: &Tree) -> Result<Option<String>, GitBrowserError> {
+    let entry = match tree.get_name(ATTRIBUTES_FILE) {
+        Some(entry) if entry.kind() == Some(ObjectType::Blob) => entry,
+        _ => return Ok(None),
+    };
+    let blob = repo.find_blob(entry.id()).map_err(|e| {
+        GitBrowserError::git(ErrorKind::Attributes, e)
+            .with_oid(entry.id())
+            .with_path(ATTRIBUTES_FILE)
+    })?;
+    Ok(Some(String::from_utf8_lossy(blob.content()).to_string()))
+}
+
+/// If the line's pattern matches the path and it mentions the attribute,
+/// the state it leaves the attribute in. `Some(None)` means the attribute
+/// was made unspecified with `!attr`.
+fn match_line(line: &str, path: &str, name: &str) -> Option<Option<AttributeValue>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let mut fields = line.split_whitespace();
+    let pattern = fields.next()?;
+    // Macro definitions and quoted patterns aren't supported
+    if pattern.starts_with("[attr]") || pattern.starts_with('"') {
+        return None;
+    }
+    if !pattern_matches(pattern, path) {
+        return None;
+    }
+
+    let mut state = None;
+    for field in fields {
+        if let Some(unset) = field.strip_prefix('-') {
+            if unset == name {
+                state = Some(Some(AttributeValue::Unset));
+            }
+        } else if let Some(unspecified) = field.strip_prefix('!') {
+            if unspecified == name {
+                state = Some(None);
+            }
+        } else if let Some((key, value)) = field.split_once('=') {
+            if key == name {
+                state = Some(Some(AttributeValue::Value(value.to_string())));
+            }
+        } else if field == name {
+            state = Some(Some(AttributeValue::Set));
+        }
+    }
+    state
+}
+
+/// Patterns without a slash match the file name at any depth, otherwise
+/// they match the path relative to the directory of the attributes file.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    if pattern.contains('/') {
+        let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+        glob_match(pattern.as_bytes(), path.as_bytes())
+    } else {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        glob_match(pattern.as_bytes(), name.as_bytes())
+    }
+}
+
+/// Match a path against a glob pattern, where `*` and `?` don't match `/`
+/// and `**` matches any number of directories.
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => {
+            let rest = match rest {
+                [b'/', rest @ ..] => rest,
+                [] => return true,
+                rest => rest,
+            };
+            // Only try to match the rest at the start of a path component
+            (0..=text.len())
+                .filter(|&i| i == 0 || text[i - 1] == b'/')
+                .any(|i| glob_match(rest, &text[i..]))
+        }
+        [b'*', rest @ ..] => {
+            for i in 0..=text.len() {
+                if glob_match(rest, &text[i..]) {
+                    return true;
+                }
+                if text.get(i) == Some(&b'/') {
+                    break;
+                }
+            }
+            false
+        }
+        [b'?', rest @ ..] => match text {
+            [c, text @ ..] if *c != b'/' => glob_match(rest, text),
+            _ => false,
+        },
+        [b'[', class @ ..] => match class.iter().skip(1).position(|&c| c == b']') {
+            Some(end) => match text {
+                [c, text @ ..] => {
+                    class_matches(&class[..end + 1], *c) && glob_match(&class[end + 2..], text)
+                }
+                [] => false,
+            },
+            // Not a valid class, so match the bracket literally
+            None => text.first() == Some(&b'[') && glob_match(class, &text[1..]),
+        },
+        [b'\\', c, rest @ ..] | [c, rest @ ..] => match text {
+            [t, text @ ..] if t == c => glob_match(rest, text),
+            _ => false,
+        },
+    }
+}
+
+/// Whether a character is in a bracket expression like `a-z_` or `!0-9`.
+fn class_matches(class: &[u8], c: u8) -> bool {
+    let (negated, class) = match class {
+        [b'!' | b'^', class @ ..] => (true, class),
+        class => (false, class),
+    };
+    let mut matched = false;
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == b'-' {
+            matched |= class[i] <= c && c <= class[i + 2];
+            i += 3;
+        } else {
+            matched |= class[i] == c;
+            i += 1;
+        }
+    }
+    matched != negated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        glob_match(pattern.as_bytes(), path.as_bytes())
+    }
+
+    #[test]
+    fn glob_match_of_double_star() {
+        assert!(matches("**/foo", "foo"));
+        assert!(matches("**/foo", "a/b/foo"));
+        assert!(!matches("**/foo", "a/xfoo"));
+        assert!(matches("a/**", "a/b"));
+        assert!(matches("a/**", "a/b/c"));
+        assert!(!matches("a/**", "b/c"));
+        assert!(matches("a/**/b", "a/b"));
+        assert!(matches("a/**/b", "a/x/y/b"));
+    }
+
+    #[test]
+    fn glob_match_of_star_and_question_mark() {
+        assert!(matches("*.rs", "main.rs"));
+        assert!(!matches("*.rs", "src/main.rs"));
+        assert!(matches("src/*.rs", "src/main.rs"));
+        assert!(!matches("src/*.rs", "src/app/main.rs"));
+        assert!(matches("a?c", "abc"));
+        assert!(!matches("a?c", "a/c"));
+    }
+
+    #[test]
+    fn glob_match_of_classes() {
+        assert!(matches("file[0-9]", "file7"));
+        assert!(!matches("file[0-9]", "filex"));
+        assert!(matches("file[!0-9]", "filex"));
+        assert!(!matches("file[!0-9]", "file7"));
+        assert!(matches("file[^a-c_]", "filed"));
+        assert!(!matches("file[^a-c_]", "file_"));
+        assert!(matches("[]]", "]"));
+        assert!(matches("a[b", "a[b"));
+    }
+
+    #[test]
+    fn match_line_states() {
+        assert_eq!(
+            match_line(
+                "*.md linguist-language=Markdown",
+                "README.md",
+                "linguist-language"
+            ),
+            Some(Some(AttributeValue::Value("Markdown".to_string())))
+        );
+        assert_eq!(
+            match_line("*.bin binary -diff", "data.bin", "diff"),
+            Some(Some(AttributeValue::Unset))
+        );
+        assert_eq!(
+            match_line("*.bin binary", "data.bin", "binary"),
+            Some(Some(AttributeValue::Set))
+        );
+        assert_eq!(
+            match_line("*.bin !binary", "data.bin", "binary"),
+            Some(None)
+        );
+        assert_eq!(match_line("*.bin binary", "data.bin", "diff"), None);
+        assert_eq!(match_line("*.bin binary", "data.txt", "binary"), None);
+        assert_eq!(match_line("# *.bin binary", "data.bin", "binary"), None);
+        assert_eq!(match_line("[attr]bin -diff", "bin", "diff"), None);
+    }
+
+    #[test]
+    fn match_line_patterns() {
+        // Without a slash the pattern matches the file name at any depth
+        assert!(match_line("*.rs text", "src/main.rs", "text").is_some());
+        // With one it is anchored to the attributes file's directory
+        assert!(match_line("/main.rs text", "main.rs", "text").is_some());
+        assert!(match_line("/main.rs text", "src/main.rs", "text").is_none());
+        assert!(match_line("docs/** text", "docs/a/b.md", "text").is_some());
+        assert!(match_line("docs/** text", "src/docs/b.md", "text").is_none());
+    }
+}
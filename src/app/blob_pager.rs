@@ -1,157 +1,335 @@
-use std::collections::VecDeque;
-use std::ffi::OsStr;
-use std::path::Path;
-use std::time::Instant;
+use std::ops::Range;
+use std::sync::mpsc::{channel, Sender};
+use std::sync::Arc;
 
-use git2::{Blob, Object, Repository};
+use git2::{Object, ObjectType, Oid, Repository};
 
 use ratatui::{
     layout::Rect,
-    prelude::{Color, Line, Modifier, Span, Style},
+    prelude::{Line, Modifier, Span, Style},
     widgets::{Block, Paragraph},
     Frame,
 };
 
 use color_eyre::Result;
 
-use syntect::easy::HighlightLines;
 use syntect::highlighting;
-use syntect::parsing::SyntaxSet;
+use syntect::parsing::{SyntaxReference, SyntaxSet};
 
+use crate::app::entry_metadata::looks_binary;
+use crate::app::highlight::{HighlightedLine, SourceLines, TuiColor, CHECKPOINT_INTERVAL};
+use crate::app::lfs::{self, LfsPointer};
+use crate::app::syntax_detection::detect_syntax;
+use crate::app::worker::{CancelToken, Mailbox, Task, TaskResult, WorkerHandle};
 use crate::errors::{ErrorKind, GitBrowserError};
 use crate::traits::{Drawable, Navigable};
 
-pub struct BlobPager<'repo, 'syntax> {
-    top: usize,
-    // repo: &'repo Repository,
-    pub blob: Blob<'repo>,
-    pub name: String,
-    background_style: Style,
-    syntax_set: &'syntax SyntaxSet,
-    // syntax: Option<SyntaxReference>,
-    // theme: &'syntax highlighting::Theme,
-    highlighter: Option<HighlightLines<'syntax>>,
-    raw_lines: VecDeque<String>,
-    lines: Vec<HighlightedLine>,
-}
-
-struct HighlightedLine {
-    pub components: Vec<(Style, String)>,
+/// The number of lines highlighted at a time around the visible area.
+const WINDOW_LINES: usize = 512;
+
+/// Only this much of a Git LFS object is loaded to be viewed.
+const MAX_LFS_VIEW_SIZE: u64 = 16 * 1024 * 1024;
+
+/// The content of a blob or file to view.
+pub struct BlobContent {
+    /// The id of the blob, or the id the file would have as a blob.
+    pub id: Oid,
+    pub data: Arc<[u8]>,
+    /// Set if the blob is a Git LFS pointer, with whether the object it
+    /// points to was available locally. When it was, `data` is the object,
+    /// or as much of it as was loaded.
+    pub lfs: Option<(LfsPointer, bool)>,
 }
 
-impl<'a> From<Vec<(highlighting::Style, &'a str)>> for HighlightedLine {
-    fn from(value: Vec<(highlighting::Style, &'a str)>) -> HighlightedLine {
-        HighlightedLine {
-            components: value
-                .iter()
-                .map(|(style, text)| (TuiStyle::from(style).0, text.to_string()))
-                .collect(),
-        }
+impl BlobContent {
+    /// Content read from a blob or file, resolving Git LFS pointers to the
+    /// start of the object they point to.
+    pub fn new(repo: &Repository, id: Oid, data: &[u8]) -> Result<BlobContent, GitBrowserError> {
+        BlobContent::with_limit(repo, id, data, MAX_LFS_VIEW_SIZE)
     }
-}
 
-struct TuiColor(Color);
-struct TuiStyle(Style);
+    /// Content read from a blob or file, loading up to `limit` bytes of the
+    /// object a Git LFS pointer points to.
+    pub fn with_limit(
+        repo: &Repository,
+        id: Oid,
+        data: &[u8],
+        limit: u64,
+    ) -> Result<BlobContent, GitBrowserError> {
+        let lfs = lfs::resolve(repo, data, limit).map_err(|e| e.with_oid(id))?;
+        Ok(match lfs {
+            Some(lfs) => match lfs.content {
+                Some(content) => BlobContent {
+                    id,
+                    data: content.into(),
+                    lfs: Some((lfs.pointer, true)),
+                },
+                None => BlobContent {
+                    id,
+                    data: data.into(),
+                    lfs: Some((lfs.pointer, false)),
+                },
+            },
+            None => BlobContent {
+                id,
+                data: data.into(),
+                lfs: None,
+            },
+        })
+    }
 
-impl From<&highlighting::Color> for TuiColor {
-    fn from(color: &highlighting::Color) -> TuiColor {
-        TuiColor(Color::Rgb(color.r, color.g, color.b))
+    /// Content of a file that may not be in the repository.
+    pub fn from_file(repo: &Repository, data: &[u8]) -> Result<BlobContent, GitBrowserError> {
+        let id = Oid::hash_object(ObjectType::Blob, data).unwrap_or(Oid::zero());
+        BlobContent::new(repo, id, data)
     }
 }
 
-impl From<&highlighting::Style> for TuiStyle {
-    fn from(style: &highlighting::Style) -> TuiStyle {
-        TuiStyle(
-            Style::default()
-                .fg(TuiColor::from(&style.foreground).0)
-                .bg(TuiColor::from(&style.background).0),
-        )
-    }
+pub struct BlobPager<'syntax> {
+    top: usize,
+    pub id: Oid,
+    pub name: String,
+    content: Arc<[u8]>,
+    lfs: Option<(LfsPointer, bool)>,
+    // Only used when there is a syntax to highlight with
+    background_style: Style,
+    syntax: Option<&'syntax SyntaxReference>,
+    workers: WorkerHandle,
+    // Ranges of lines to highlight, sent to the worker highlighting the blob
+    highlight_requests: Option<Sender<Range<usize>>>,
+    mailbox: Mailbox,
+    cancel: CancelToken,
+    // Byte offset of the start of each line in the blob
+    line_offsets: Vec<usize>,
+    window_start: usize,
+    window: Vec<HighlightedLine>,
+    // The start of the window that has been requested from the worker
+    requested_window: Option<usize>,
 }
 
-impl<'repo, 'syntax> BlobPager<'repo, 'syntax> {
+impl<'syntax> BlobPager<'syntax> {
     pub fn new(
-        _repo: &'repo Repository,
-        blob: Blob<'repo>,
+        content: BlobContent,
         name: String,
         syntax_set: &'syntax SyntaxSet,
         theme: &'syntax highlighting::Theme,
-    ) -> BlobPager<'repo, 'syntax> {
-        let content = match std::str::from_utf8(blob.content()) {
-            Ok(v) => v,
-            Err(e) => panic!("unable to decode utf8 {}", e),
-        };
-        let raw_lines: VecDeque<String> = content.lines().map(|line| line.to_string()).collect();
-
-        let syntax = {
-            let extension = Path::new(&name).extension().and_then(OsStr::to_str);
-            if let Some(ext) = extension {
-                syntax_set.find_syntax_by_extension(ext).cloned()
-            } else if let Some(line) = raw_lines.front() {
-                syntax_set.find_syntax_by_first_line(line).cloned()
-            } else {
-                None
-            }
-        };
-        let background_style = match syntax {
-            Some(_) => {
-                if let Some(color) = theme.settings.background {
-                    Style::default().bg(TuiColor::from(&color).0)
-                } else {
-                    Style::default()
-                }
-            }
-            _ => Style::default(),
+        workers: &WorkerHandle,
+        linguist_language: Option<&str>,
+    ) -> Result<BlobPager<'syntax>, GitBrowserError> {
+        if looks_binary(&content.data) {
+            return Err(GitBrowserError::message(
+                ErrorKind::BinaryFile,
+                format!("{} bytes of binary data", content.data.len()),
+            )
+            .with_oid(content.id)
+            .with_path(name));
+        }
+        let text = std::str::from_utf8(&content.data).map_err(|e| {
+            GitBrowserError::message(ErrorKind::Encoding, e.to_string())
+                .with_oid(content.id)
+                .with_path(&name)
+        })?;
+        let line_offsets = SourceLines::new(text).into_offsets();
+
+        let syntax = detect_syntax(syntax_set, &name, text, linguist_language);
+        let background_style = match theme.settings.background {
+            Some(color) => Style::default().bg(TuiColor::from(&color).0),
+            None => Style::default(),
         };
 
-        let highlighter = syntax.as_ref().map(|s| HighlightLines::new(s, theme));
-
-        BlobPager {
+        let mut pager = BlobPager {
             top: 0,
-            // repo: repo,
-            blob: blob.clone(),
+            id: content.id,
             name,
+            content: content.data,
+            lfs: content.lfs,
             background_style,
-            syntax_set,
-            // syntax,
-            // theme,
-            highlighter,
-            raw_lines,
-            lines: vec![],
+            syntax,
+            workers: workers.clone(),
+            highlight_requests: None,
+            mailbox: Mailbox::new(),
+            cancel: CancelToken::new(),
+            line_offsets,
+            window_start: 0,
+            window: vec![],
+            requested_window: None,
+        };
+        pager.start_highlighting();
+        Ok(pager)
+    }
+
+    /// The content being viewed, which is the object an LFS pointer points
+    /// to when it is available.
+    pub fn content(&self) -> &[u8] {
+        &self.content
+    }
+
+    /// The LFS pointer the blob contains, and whether the object it points
+    /// to is available locally.
+    pub fn lfs_pointer(&self) -> Option<(&LfsPointer, bool)> {
+        self.lfs
+            .as_ref()
+            .map(|(pointer, available)| (pointer, *available))
+    }
+
+    /// The line shown at the top of the screen.
+    pub fn top(&self) -> usize {
+        self.top
+    }
+
+    /// Scroll so that a line is at the top of the screen.
+    pub fn scroll_to(&mut self, top: usize) {
+        self.top = top.min(self.len().saturating_sub(1));
+    }
+
+    /// Whether only the start of an LFS object was loaded.
+    pub fn truncated(&self) -> bool {
+        self.lfs.as_ref().is_some_and(|(pointer, available)| {
+            *available && pointer.size > self.content.len() as u64
+        })
+    }
+
+    /// The name of the syntax the blob is highlighted with.
+    pub fn syntax_name(&self) -> Option<&str> {
+        self.syntax.map(|syntax| syntax.name.as_str())
+    }
+
+    /// Highlight the blob with a different syntax.
+    pub fn set_syntax(&mut self, syntax: &'syntax SyntaxReference) {
+        self.syntax = Some(syntax);
+        self.start_highlighting();
+    }
+
+    /// Start highlighting the blob from scratch, replacing any previous
+    /// worker.
+    fn start_highlighting(&mut self) {
+        self.window_start = 0;
+        self.window = vec![];
+        self.start_worker();
+    }
+
+    /// Start a worker highlighting the blob, replacing any previous one.
+    fn start_worker(&mut self) {
+        // Dropping the previous token and sender stops the previous worker
+        self.cancel = CancelToken::new();
+        self.requested_window = None;
+        self.highlight_requests = self.syntax.map(|syntax| {
+            let (sender, requests) = channel();
+            self.workers.submit_to(
+                Task::Highlight {
+                    blob: self.id,
+                    content: self.content.clone(),
+                    syntax: syntax.name.to_string(),
+                    requests,
+                },
+                Some(&self.cancel),
+                &self.mailbox,
+            );
+            sender
+        });
+    }
+
+    /// Stop highlighting while the pager isn't shown, keeping the lines
+    /// already highlighted. Highlighting starts again on the next tick.
+    pub fn suspend(&mut self) {
+        self.cancel = CancelToken::new();
+        self.highlight_requests = None;
+        self.requested_window = None;
+    }
+
+    fn len(&self) -> usize {
+        self.line_offsets.len()
+    }
+
+    /// The text of a line, including its line ending.
+    fn line(&self, index: usize) -> &str {
+        let start = self.line_offsets[index];
+        let end = self
+            .line_offsets
+            .get(index + 1)
+            .copied()
+            .unwrap_or(self.content.len());
+        // The content was checked to be valid UTF-8 on creation and lines
+        // always start after a newline
+        std::str::from_utf8(&self.content[start..end]).unwrap_or_default()
+    }
+
+    /// Whether the lines around `top` have been highlighted.
+    fn window_is_current(&self) -> bool {
+        let visible_end = (self.top + WINDOW_LINES / 2).min(self.len());
+        self.top >= self.window_start && visible_end <= self.window_start + self.window.len()
+    }
+
+    /// Take the lines that have been highlighted since last time, returning
+    /// what failed.
+    pub fn receive_results(&mut self) -> Vec<GitBrowserError> {
+        let mut failures = vec![];
+        for result in self.mailbox.results() {
+            let (syntax, start, lines) = match result {
+                TaskResult::Highlight {
+                    syntax,
+                    start,
+                    lines,
+                } => (syntax, start, lines),
+                TaskResult::Failed(e) => {
+                    failures.push(e);
+                    continue;
+                }
+                _ => continue,
+            };
+            // Results from before the syntax was changed may still be in
+            // flight
+            if self.syntax.map(|s| s.name.as_str()) != Some(syntax.as_str()) {
+                continue;
+            }
+            if self.requested_window == Some(start) {
+                self.requested_window = None;
+            }
+            self.window_start = start;
+            self.window = lines;
         }
+        failures
     }
 
-    pub fn from_object(
+    pub fn from_object<'repo>(
         repo: &'repo Repository,
         object: Object<'repo>,
         name: String,
         syntax_set: &'syntax SyntaxSet,
         theme: &'syntax highlighting::Theme,
+        workers: &WorkerHandle,
+        linguist_language: Option<&str>,
     ) -> Result<Self, GitBrowserError> {
+        let oid = object.id();
         match object.into_blob() {
             Ok(blob) => {
-                if blob.is_binary() {
-                    Err(GitBrowserError::Error(ErrorKind::BinaryFile))
-                } else {
-                    Ok(BlobPager::new(repo, blob, name, syntax_set, theme))
-                }
+                let content =
+                    BlobContent::new(repo, oid, blob.content()).map_err(|e| e.with_path(&name))?;
+                BlobPager::new(content, name, syntax_set, theme, workers, linguist_language)
             }
-            Err(_) => Err(GitBrowserError::Error(ErrorKind::BlobReference)),
+            Err(object) => Err(GitBrowserError::message(
+                ErrorKind::BlobReference,
+                format!(
+                    "Object is a {}, not a blob",
+                    object.kind().map_or("unknown", |k| k.str())
+                ),
+            )
+            .with_oid(oid)
+            .with_path(name)),
         }
     }
 }
 
-impl<'repo, 'syntax> Drawable<'repo> for BlobPager<'repo, 'syntax> {
+impl<'repo, 'syntax> Drawable<'repo> for BlobPager<'syntax> {
     fn draw(&self, f: &mut Frame, area: Rect, content_block_ext: Block) -> Rect {
-        let content_block = content_block_ext.style(self.background_style);
+        let content_block = match self.syntax {
+            Some(_) => content_block_ext.style(self.background_style),
+            None => content_block_ext,
+        };
 
         let viewport = content_block.inner(area);
         let height: usize = viewport.height.into();
-        let bottom = if self.top + height > self.lines.len() {
-            self.lines.len()
-        } else {
-            self.top + height
-        };
+        let bottom = (self.top + height).min(self.len());
         let filler: Vec<Line> = if bottom - self.top < height {
             let v: Vec<Line> = vec![Line::styled(
                 "~",
@@ -163,20 +341,25 @@ impl<'repo, 'syntax> Drawable<'repo> for BlobPager<'repo, 'syntax> {
             vec![]
         };
 
-        let lines: Vec<Line> = self.lines[self.top..bottom]
-            .iter()
-            .enumerate()
-            .map(|(index, highlighted_line)| {
+        let lines: Vec<Line> = (self.top..bottom)
+            .map(|index| {
                 let tmp = format!("{}", bottom);
                 let width = tmp.len();
-                let formatted = format!("{:width$} | ", index + self.top);
+                let formatted = format!("{:width$} | ", index);
                 let lineno = Span::styled(formatted, Style::default().add_modifier(Modifier::DIM));
 
-                let mut spans = highlighted_line
-                    .components
-                    .iter()
-                    .map(|(style, text)| Span::styled(text, *style))
-                    .collect();
+                let mut spans = match index
+                    .checked_sub(self.window_start)
+                    .and_then(|offset| self.window.get(offset))
+                {
+                    Some(highlighted_line) => highlighted_line
+                        .components
+                        .iter()
+                        .map(|(style, text)| Span::styled(text, *style))
+                        .collect(),
+                    // Not highlighted yet
+                    None => vec![Span::raw(self.line(index).trim_end_matches(['\r', '\n']))],
+                };
                 let mut line = vec![lineno];
                 line.append(&mut spans);
 
@@ -199,28 +382,20 @@ impl<'repo, 'syntax> Drawable<'repo> for BlobPager<'repo, 'syntax> {
     }
 }
 
-impl<'repo, 'syntax> Navigable<'repo> for BlobPager<'repo, 'syntax> {
+impl<'repo, 'syntax> Navigable<'repo> for BlobPager<'syntax> {
     fn home(&mut self, _page_size: u16) {
         self.top = 0;
     }
 
     fn end(&mut self, page_size: u16) {
         let h: usize = page_size.into();
-        self.top = if !self.lines.is_empty() {
-            self.lines.len() - h
-        } else {
-            0
-        };
+        self.top = self.len().saturating_sub(h);
     }
 
     fn pagedown(&mut self, page_size: u16) {
         let h: usize = page_size.into();
-        let top = self.top + h;
-        self.top = if top > self.lines.len() {
-            self.lines.len() - 1
-        } else {
-            top
-        }
+        let top = self.top.saturating_add(h);
+        self.top = top.min(self.len().saturating_sub(1));
     }
 
     fn pageup(&mut self, page_size: u16) {
@@ -234,7 +409,7 @@ impl<'repo, 'syntax> Navigable<'repo> for BlobPager<'repo, 'syntax> {
 
     fn next_selection(&mut self) {
         // Always keep the last line on the screen
-        if self.top < self.lines.len() - 1 {
+        if self.top + 1 < self.len() {
             self.top += 1;
         }
     }
@@ -245,32 +420,33 @@ impl<'repo, 'syntax> Navigable<'repo> for BlobPager<'repo, 'syntax> {
         }
     }
 
-    fn select(&self) -> Option<(Object<'repo>, String)> {
-        None
+    fn select(&self) -> Result<Option<(Object<'repo>, String)>, GitBrowserError> {
+        Ok(None)
     }
 
     fn selected_item(&self) -> String {
         "".to_string()
     }
 
-    fn next_tick(&mut self, block: bool) -> Result<(), GitBrowserError> {
-        if self.raw_lines.is_empty() {
+    fn next_tick(&mut self, _block: bool) -> Result<(), GitBrowserError> {
+        if self.syntax.is_none() || self.window_is_current() {
             return Ok(());
         }
-
-        let iteration = Instant::now();
-        while (block || iteration.elapsed().as_millis() < 150) && !self.raw_lines.is_empty() {
-            let text = self.raw_lines.pop_front().unwrap();
-            let line = match &mut self.highlighter {
-                Some(h) => HighlightedLine::from(h.highlight_line(&text, self.syntax_set).unwrap()),
-                _ => HighlightedLine {
-                    components: vec![(Style::default(), text.to_string())],
-                },
-            };
-
-            self.lines.push(line);
+        if self.highlight_requests.is_none() {
+            self.start_worker();
+        }
+        // Windows start on a checkpoint so that the worker doesn't need to
+        // parse lines it won't return
+        let start = self.top / CHECKPOINT_INTERVAL * CHECKPOINT_INTERVAL;
+        if self.requested_window == Some(start) {
+            return Ok(());
+        }
+        if let Some(requests) = &self.highlight_requests {
+            // The worker only stops once the sender is dropped, so this
+            // can't fail while we hold it
+            let _ = requests.send(start..start + WINDOW_LINES);
+            self.requested_window = Some(start);
         }
-
         Ok(())
     }
 }
@@ -0,0 +1,191 @@
+use git2::{ObjectType, Oid, Repository, TreeEntry};
+
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    prelude::Modifier,
+    style::{Color, Style},
+    text::Line,
+    widgets::{block::Padding, Block, Borders, List, ListItem, Paragraph},
+    Frame,
+};
+
+use syntect::highlighting;
+use syntect::parsing::SyntaxSet;
+
+use crate::app::blob_pager::BlobPager;
+use crate::app::entry_metadata::EntryMetadata;
+use crate::app::lfs::LfsPointer;
+use crate::app::tree_page::TreePage;
+use crate::app::worker::WorkerHandle;
+use crate::traits::{Display, Drawable};
+
+/// Blobs larger than this, or pointing to LFS objects larger than this, are
+/// not loaded to be previewed.
+const MAX_PREVIEW_SIZE: usize = 256 * 1024;
+
+/// What is shown of the selected entry in the last column of the column
+/// layout.
+pub enum Preview<'syntax> {
+    /// The names of a directory's entries, with their styles.
+    Tree(Oid, Vec<(String, Style)>),
+    Blob(Oid, Box<BlobPager<'syntax>>),
+    /// Waiting for the metadata saying whether a blob can be shown.
+    Loading(Oid),
+    /// Why the entry isn't shown.
+    Message(Oid, String),
+}
+
+impl<'syntax> Preview<'syntax> {
+    /// Preview a tree entry. Blobs are only loaded once their metadata shows
+    /// that they are small text files.
+    pub fn load(
+        repo: &Repository,
+        entry: &TreeEntry,
+        metadata: Option<&EntryMetadata>,
+        syntax_set: &'syntax SyntaxSet,
+        theme: &'syntax highlighting::Theme,
+        workers: &WorkerHandle,
+        linguist_language: Option<&str>,
+    ) -> Preview<'syntax> {
+        let id = entry.id();
+        let name = String::from_utf8_lossy(entry.name_bytes()).to_string();
+        let object = match entry.kind() {
+            Some(ObjectType::Tree | ObjectType::Blob) => entry.to_object(repo),
+            _ => return Preview::Message(id, "Submodule".to_string()),
+        };
+        let object = match object {
+            Ok(object) => object,
+            Err(e) => return Preview::Message(id, e.message().to_string()),
+        };
+        if let Some(tree) = object.as_tree() {
+            // Only names are shown, so the metadata isn't loaded
+            let names = tree
+                .iter()
+                .filter(|entry| entry.kind().is_some())
+                .map(|entry| entry.display_name(false))
+                .collect();
+            return Preview::Tree(id, names);
+        }
+
+        match metadata {
+            None => Preview::Loading(id),
+            Some(EntryMetadata {
+                binary: Some(true), ..
+            }) => Preview::Message(id, "Binary file".to_string()),
+            Some(EntryMetadata {
+                size: Some(size), ..
+            }) if *size > MAX_PREVIEW_SIZE => {
+                Preview::Message(id, "Too large to preview".to_string())
+            }
+            // The pointer is small, but the object it points to needn't be
+            Some(_)
+                if object
+                    .as_blob()
+                    .and_then(|blob| LfsPointer::parse(blob.content()))
+                    .is_some_and(|pointer| pointer.size > MAX_PREVIEW_SIZE as u64) =>
+            {
+                Preview::Message(id, "Too large to preview".to_string())
+            }
+            Some(_) => match BlobPager::from_object(
+                repo,
+                object,
+                name,
+                syntax_set,
+                theme,
+                workers,
+                linguist_language,
+            ) {
+                Ok(pager) => Preview::Blob(id, Box::new(pager)),
+                Err(e) => Preview::Message(id, e.to_string()),
+            },
+        }
+    }
+
+    /// The id of the entry being previewed.
+    pub fn id(&self) -> Oid {
+        match self {
+            Preview::Tree(id, _)
+            | Preview::Blob(id, _)
+            | Preview::Loading(id)
+            | Preview::Message(id, _) => *id,
+        }
+    }
+
+    pub fn is_loading(&self) -> bool {
+        matches!(self, Preview::Loading(_))
+    }
+
+    pub fn blob_pager_mut(&mut self) -> Option<&mut BlobPager<'syntax>> {
+        match self {
+            Preview::Blob(_, pager) => Some(pager.as_mut()),
+            _ => None,
+        }
+    }
+
+    fn draw(&self, f: &mut Frame, area: Rect, block: Block) {
+        match self {
+            Preview::Tree(_, names) => {
+                let items: Vec<ListItem> = names
+                    .iter()
+                    .map(|(name, style)| ListItem::new(Line::styled(name.to_string(), *style)))
+                    .collect();
+                f.render_widget(List::new(items).block(block), area);
+            }
+            Preview::Blob(_, pager) => {
+                pager.draw(f, area, block);
+            }
+            Preview::Loading(_) => draw_message(f, area, block, "..."),
+            Preview::Message(_, message) => draw_message(f, area, block, message),
+        }
+    }
+}
+
+/// Draw the parent directory, the current directory and a preview of the
+/// selected entry side by side inside the content block, returning the
+/// viewport of the current directory.
+pub fn draw_columns(
+    f: &mut Frame,
+    area: Rect,
+    content_block: Block,
+    parent: Option<&TreePage>,
+    current: &TreePage,
+    preview: Option<&Preview>,
+) -> Rect {
+    let inner = content_block.inner(area);
+    f.render_widget(content_block, area);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(20),
+            Constraint::Percentage(35),
+            Constraint::Percentage(45),
+        ])
+        .split(inner);
+
+    let separated = || {
+        Block::default()
+            .borders(Borders::RIGHT)
+            .border_style(Style::default().fg(Color::DarkGray))
+            .padding(Padding::right(1))
+    };
+    match parent {
+        Some(page) => {
+            page.draw_names(f, columns[0], separated());
+        }
+        None => f.render_widget(separated(), columns[0]),
+    }
+    let viewport = current.draw(f, columns[1], separated().padding(Padding::horizontal(1)));
+    if let Some(preview) = preview {
+        preview.draw(f, columns[2], Block::default().padding(Padding::left(1)));
+    }
+    viewport
+}
+
+fn draw_message(f: &mut Frame, area: Rect, block: Block, message: &str) {
+    let message = Paragraph::new(Line::styled(
+        message.to_string(),
+        Style::default().fg(Color::Gray).add_modifier(Modifier::DIM),
+    ))
+    .block(block);
+    f.render_widget(message, area);
+}
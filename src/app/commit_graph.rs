@@ -0,0 +1,284 @@
+use std::collections::HashSet;
+
+use git2::Oid;
+
+/// One column of a row of the graph, which is drawn as the symbol for the
+/// lane followed by the connector to the next lane.
+#[derive(Clone, Copy, Debug)]
+pub struct GraphCell {
+    pub symbol: char,
+    pub connector: char,
+    /// The lane whose colour the cell is drawn in.
+    pub lane: usize,
+}
+
+/// Lays out commits in lanes like `git log --graph`, one commit at a time
+/// so that history can be loaded as it is needed.
+///
+/// Each lane holds the commit it is waiting for. A commit takes the first
+/// lane waiting for it, any other lanes waiting for it merge into that one,
+/// and its parents take over its lane or open new ones.
+#[derive(Default)]
+pub struct CommitGraph {
+    lanes: Vec<Option<Oid>>,
+    seen: HashSet<Oid>,
+}
+
+impl CommitGraph {
+    pub fn new() -> CommitGraph {
+        CommitGraph::default()
+    }
+
+    /// Add the next commit, returning the row to draw next to it.
+    pub fn add(&mut self, id: Oid, parents: &[Oid]) -> Vec<GraphCell> {
+        self.seen.insert(id);
+        let before = self.lanes.clone();
+        let column = match before.iter().position(|lane| *lane == Some(id)) {
+            Some(column) => column,
+            None => self.free_lane(&before),
+        };
+
+        let merged: Vec<usize> = (0..before.len())
+            .filter(|&i| i != column && before[i] == Some(id))
+            .collect();
+        for &i in &merged {
+            self.lanes[i] = None;
+        }
+
+        let parents = self.unseen(parents);
+        self.set_lane(column, parents.first().copied());
+        let mut opened = vec![];
+        let mut joined = vec![];
+        for &parent in parents.iter().skip(1) {
+            match self.lanes.iter().position(|lane| *lane == Some(parent)) {
+                Some(lane) => joined.push(lane),
+                None => {
+                    let lane = self.free_lane(&before);
+                    self.set_lane(lane, Some(parent));
+                    opened.push(lane);
+                }
+            }
+        }
+
+        let ends = merged.iter().chain(&opened).chain(&joined);
+        let start = ends.clone().copied().min().unwrap_or(column).min(column);
+        let end = ends.copied().max().unwrap_or(column).max(column);
+        let width = self.lanes.len().max(before.len());
+        let row = (0..width)
+            .map(|i| {
+                let waiting = before.get(i).copied().flatten().is_some();
+                // Lanes between others that merge or open are crossed
+                let inner = i > start && i < end;
+                let (symbol, lane) = if i == column {
+                    ('●', i)
+                } else if merged.contains(&i) {
+                    (
+                        if inner {
+                            '┴'
+                        } else if i > column {
+                            '┘'
+                        } else {
+                            '└'
+                        },
+                        i,
+                    )
+                } else if opened.contains(&i) {
+                    (
+                        if inner {
+                            '┬'
+                        } else if i > column {
+                            '┐'
+                        } else {
+                            '┌'
+                        },
+                        i,
+                    )
+                } else if joined.contains(&i) {
+                    (
+                        if inner {
+                            '┼'
+                        } else if i > column {
+                            '┤'
+                        } else {
+                            '├'
+                        },
+                        i,
+                    )
+                } else if inner {
+                    (if waiting { '┼' } else { '─' }, column)
+                } else if waiting {
+                    ('│', i)
+                } else {
+                    (' ', i)
+                };
+                let connector = if i >= start && i < end { '─' } else { ' ' };
+                GraphCell {
+                    symbol,
+                    connector,
+                    lane,
+                }
+            })
+            .collect();
+
+        while self.lanes.last() == Some(&None) {
+            self.lanes.pop();
+        }
+        row
+    }
+
+    /// Pass over a commit that isn't shown, so that lanes waiting for it
+    /// wait for its parents instead. As there is no row to draw them
+    /// merging in, lanes that would end up waiting for a commit another lane
+    /// is already waiting for end here.
+    pub fn skip(&mut self, id: Oid, parents: &[Oid]) {
+        self.seen.insert(id);
+        let waiting: Vec<usize> = (0..self.lanes.len())
+            .filter(|&i| self.lanes[i] == Some(id))
+            .collect();
+        if waiting.is_empty() {
+            return;
+        }
+        let parents = self.unseen(parents);
+        let first = parents.first().copied();
+        let taken = first.is_some()
+            && (0..self.lanes.len()).any(|i| self.lanes[i] == first && !waiting.contains(&i));
+        for (n, &lane) in waiting.iter().enumerate() {
+            self.lanes[lane] = if n == 0 && !taken { first } else { None };
+        }
+        let before = self.lanes.clone();
+        for &parent in parents.iter().skip(1) {
+            if !self.lanes.contains(&Some(parent)) {
+                let lane = self.free_lane(&before);
+                self.set_lane(lane, Some(parent));
+            }
+        }
+        while self.lanes.last() == Some(&None) {
+            self.lanes.pop();
+        }
+    }
+
+    /// Parents that have already been passed, which can happen when commit
+    /// times are skewed, would leave lanes waiting forever, so are left out.
+    fn unseen(&self, parents: &[Oid]) -> Vec<Oid> {
+        parents
+            .iter()
+            .filter(|parent| !self.seen.contains(parent))
+            .copied()
+            .collect()
+    }
+
+    /// A lane that is empty in this row and the last, so that lanes ending
+    /// and starting on the same row aren't drawn on top of each other.
+    fn free_lane(&mut self, before: &[Option<Oid>]) -> usize {
+        let free = (0..self.lanes.len())
+            .find(|&i| self.lanes[i].is_none() && before.get(i).copied().flatten().is_none());
+        match free {
+            Some(lane) => lane,
+            None => {
+                self.lanes.push(None);
+                self.lanes.len() - 1
+            }
+        }
+    }
+
+    fn set_lane(&mut self, lane: usize, id: Option<Oid>) {
+        if lane >= self.lanes.len() {
+            self.lanes.resize(lane + 1, None);
+        }
+        self.lanes[lane] = id;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(n: u8) -> Oid {
+        Oid::from_bytes(&[n; 20]).unwrap()
+    }
+
+    /// Add commits given as `(commit, parents)`, drawing each row like it is
+    /// shown.
+    fn draw(graph: &mut CommitGraph, commits: &[(u8, &[u8])]) -> Vec<String> {
+        commits
+            .iter()
+            .map(|(commit, parents)| {
+                let parents: Vec<Oid> = parents.iter().map(|&n| id(n)).collect();
+                let row: String = graph
+                    .add(id(*commit), &parents)
+                    .iter()
+                    .flat_map(|cell| [cell.symbol, cell.connector])
+                    .collect();
+                row.trim_end().to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn linear_history() {
+        let mut graph = CommitGraph::new();
+        let rows = draw(&mut graph, &[(3, &[2]), (2, &[1]), (1, &[])]);
+        assert_eq!(rows, ["●", "●", "●"]);
+        assert!(graph.lanes.is_empty());
+    }
+
+    #[test]
+    fn fork_and_merge() {
+        let mut graph = CommitGraph::new();
+        let rows = draw(&mut graph, &[(4, &[3, 2]), (3, &[1]), (2, &[1]), (1, &[])]);
+        assert_eq!(rows, ["●─┐", "● │", "│ ●", "●─┘"]);
+        assert!(graph.lanes.is_empty());
+    }
+
+    #[test]
+    fn merge_into_existing_lane() {
+        let mut graph = CommitGraph::new();
+        // The second parent of 5 is already waited for by the lane of 4
+        let rows = draw(
+            &mut graph,
+            &[(4, &[2]), (5, &[3, 2]), (3, &[1]), (2, &[1]), (1, &[])],
+        );
+        assert_eq!(rows, ["●", "├─●", "│ ●", "● │", "●─┘"]);
+    }
+
+    #[test]
+    fn octopus_merge() {
+        let mut graph = CommitGraph::new();
+        let rows = draw(
+            &mut graph,
+            &[(5, &[4, 3, 2]), (4, &[1]), (3, &[1]), (2, &[1]), (1, &[])],
+        );
+        assert_eq!(rows, ["●─┬─┐", "● │ │", "│ ● │", "│ │ ●", "●─┴─┘"]);
+        assert!(graph.lanes.is_empty());
+    }
+
+    #[test]
+    fn skewed_parent() {
+        let mut graph = CommitGraph::new();
+        // 1 is listed before 3, as if its time were later, so the lane that
+        // would wait for it is never opened
+        let rows = draw(&mut graph, &[(2, &[1]), (1, &[]), (3, &[1])]);
+        assert_eq!(rows, ["●", "●", "●"]);
+        assert!(graph.lanes.is_empty());
+    }
+
+    #[test]
+    fn skipped_lanes_converge() {
+        let mut graph = CommitGraph::new();
+        assert_eq!(draw(&mut graph, &[(5, &[4, 3])]), ["●─┐"]);
+        // Both sides of the merge are hidden and have the same parent
+        graph.skip(id(4), &[id(2)]);
+        graph.skip(id(3), &[id(2)]);
+        assert_eq!(graph.lanes, [Some(id(2))]);
+        assert_eq!(draw(&mut graph, &[(2, &[1]), (1, &[])]), ["●", "●"]);
+    }
+
+    #[test]
+    fn skipped_lane_joins_waiting_lane() {
+        let mut graph = CommitGraph::new();
+        draw(&mut graph, &[(5, &[4, 3])]);
+        // The lane of 3 goes on to 4, which the first lane is waiting for
+        graph.skip(id(3), &[id(4)]);
+        assert_eq!(graph.lanes, [Some(id(4))]);
+    }
+}
@@ -0,0 +1,204 @@
+use git2::{Commit, Object};
+
+use ratatui::{
+    layout::Rect,
+    prelude::Modifier,
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Paragraph},
+    Frame,
+};
+
+use crate::app::notes::notes_label;
+use crate::app::signature::SignatureFormatter;
+use crate::app::signing::{SignatureStatus, Verification};
+use crate::errors::GitBrowserError;
+use crate::traits::{Drawable, Navigable};
+
+/// Shows the metadata and message of a commit, along with its notes.
+pub struct CommitPage {
+    id: String,
+    lines: Vec<Line<'static>>,
+    top: usize,
+}
+
+impl CommitPage {
+    /// Signatures are labelled, as there may be one on the tag the commit
+    /// was reached through as well as on the commit itself.
+    pub fn new(
+        commit: &Commit,
+        notes_ref: &str,
+        note: Option<&str>,
+        signatures: &[(String, &SignatureStatus)],
+        people: &SignatureFormatter,
+    ) -> CommitPage {
+        let dim = Style::default().add_modifier(Modifier::DIM);
+        let mut lines = vec![Line::styled(
+            format!("commit {}", commit.id()),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )];
+        for parent in commit.parent_ids() {
+            lines.push(Line::styled(format!("Parent:    {}", parent), dim));
+        }
+        lines.push(Line::raw(format!(
+            "Author:    {}",
+            people.format(&commit.author())
+        )));
+        lines.push(Line::raw(format!(
+            "Committer: {}",
+            people.format(&commit.committer())
+        )));
+        lines.push(Line::raw(""));
+        let message = String::from_utf8_lossy(commit.message_bytes()).to_string();
+        lines.extend(
+            message
+                .lines()
+                .map(|line| Line::raw(format!("    {}", line))),
+        );
+        if let Some(note) = note {
+            lines.push(Line::raw(""));
+            lines.push(Line::styled(
+                notes_label(notes_ref),
+                Style::default()
+                    .fg(Color::LightBlue)
+                    .add_modifier(Modifier::BOLD),
+            ));
+            lines.extend(note.lines().map(|line| Line::raw(format!("    {}", line))));
+        }
+        for (label, status) in signatures {
+            lines.push(Line::raw(""));
+            lines.extend(signature_lines(label, status));
+        }
+        CommitPage {
+            id: commit.id().to_string(),
+            lines,
+            top: 0,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.lines.len()
+    }
+}
+
+fn signature_lines(label: &str, status: &SignatureStatus) -> Vec<Line<'static>> {
+    let heading = Style::default()
+        .fg(Color::LightBlue)
+        .add_modifier(Modifier::BOLD);
+    let info = match status {
+        SignatureStatus::Unsigned => {
+            return vec![Line::styled(format!("{}: unsigned", label), heading)]
+        }
+        SignatureStatus::Signed(info) => info,
+    };
+    let mut lines = vec![Line::styled(
+        format!(
+            "{}: {} key {}",
+            label,
+            info.method.as_str(),
+            info.key_id.as_deref().unwrap_or("unknown")
+        ),
+        heading,
+    )];
+    let (_, style) = status.badge();
+    let verification = match &info.verification {
+        None => None,
+        Some(Verification::Good(Some(signer))) => Some(format!("Good signature from {}", signer)),
+        Some(Verification::Good(None)) => {
+            Some("Good signature, but no principal matched".to_string())
+        }
+        Some(Verification::ExpiredSignature(signer)) => {
+            Some(format!("Expired signature from {}", signer))
+        }
+        Some(Verification::ExpiredKey(signer)) => Some(format!(
+            "Good signature from {}, but the key has expired",
+            signer
+        )),
+        Some(Verification::RevokedKey(signer)) => Some(format!(
+            "Good signature from {}, but the key has been REVOKED",
+            signer
+        )),
+        Some(Verification::Bad) => Some("BAD signature".to_string()),
+        Some(Verification::UnknownKey) => Some("Signed with an unknown key".to_string()),
+        Some(Verification::Unverifiable(reason)) => {
+            Some(format!("Could not check the signature: {}", reason))
+        }
+        Some(Verification::Unavailable(reason)) => Some(format!("Not verified: {}", reason)),
+    };
+    if let Some(verification) = verification {
+        lines.push(Line::styled(verification, style));
+    }
+    lines.extend(info.block.lines().map(|line| {
+        Line::styled(
+            format!("    {}", line),
+            Style::default().add_modifier(Modifier::DIM),
+        )
+    }));
+    lines
+}
+
+impl<'repo> Drawable<'repo> for CommitPage {
+    fn draw(&self, f: &mut Frame, area: Rect, content_block: Block) -> Rect {
+        let viewport = content_block.inner(area);
+        let lines: Vec<Line> = self
+            .lines
+            .iter()
+            .skip(self.top)
+            .take(viewport.height.into())
+            .cloned()
+            .collect();
+        let content = Paragraph::new(lines).block(content_block);
+        f.render_widget(content, area);
+        viewport
+    }
+
+    fn title(&self) -> String {
+        self.id.to_string()
+    }
+}
+
+impl<'repo> Navigable<'repo> for CommitPage {
+    fn home(&mut self, _page_size: u16) {
+        self.top = 0;
+    }
+
+    fn end(&mut self, page_size: u16) {
+        let h: usize = page_size.into();
+        self.top = self.len().saturating_sub(h);
+    }
+
+    fn pagedown(&mut self, page_size: u16) {
+        let h: usize = page_size.into();
+        let top = self.top.saturating_add(h);
+        self.top = top.min(self.len().saturating_sub(1));
+    }
+
+    fn pageup(&mut self, page_size: u16) {
+        let h: usize = page_size.into();
+        self.top = self.top.saturating_sub(h);
+    }
+
+    fn next_selection(&mut self) {
+        if self.top + 1 < self.len() {
+            self.top += 1;
+        }
+    }
+
+    fn previous_selection(&mut self) {
+        self.top = self.top.saturating_sub(1);
+    }
+
+    fn select(&self) -> Result<Option<(Object<'repo>, String)>, GitBrowserError> {
+        Ok(None)
+    }
+
+    fn selected_item(&self) -> String {
+        "".to_string()
+    }
+
+    fn next_tick(&mut self, _block: bool) -> Result<(), GitBrowserError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+use std::io::Read;
+
+use git2::{ObjectType, Repository, TreeEntry};
+
+use crate::errors::{ErrorKind, GitBrowserError};
+
+/// The number of bytes inspected when deciding if a blob is binary. This is
+/// the same heuristic git uses.
+const SNIFF_LENGTH: usize = 8000;
+
+/// Loose blobs larger than this are streamed rather than loaded in full to
+/// decide if they are binary.
+const MAX_LOAD_SIZE: usize = 1024 * 1024;
+
+/// Information about a tree entry that is expensive to compute.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EntryMetadata {
+    pub size: Option<usize>,
+    /// Whether the blob looks binary, or `None` if the entry isn't a blob.
+    pub binary: Option<bool>,
+}
+
+impl EntryMetadata {
+    pub fn load(repo: &Repository, entry: &TreeEntry) -> Result<EntryMetadata, GitBrowserError> {
+        if entry.kind() != Some(ObjectType::Blob) {
+            return Ok(EntryMetadata::default());
+        }
+
+        let error = |e| {
+            GitBrowserError::git(ErrorKind::BlobReference, e)
+                .with_oid(entry.id())
+                .with_path(String::from_utf8_lossy(entry.name_bytes()))
+        };
+        let odb = repo.odb().map_err(error)?;
+        let (size, _kind) = odb.read_header(entry.id()).map_err(error)?;
+
+        let binary = if size <= MAX_LOAD_SIZE {
+            repo.find_blob(entry.id()).map_err(error)?.is_binary()
+        } else {
+            match odb.reader(entry.id()) {
+                Ok((reader, _size, _kind)) => {
+                    let mut head = Vec::with_capacity(SNIFF_LENGTH);
+                    reader
+                        .take(SNIFF_LENGTH as u64)
+                        .read_to_end(&mut head)
+                        .map_err(|e| {
+                            GitBrowserError::io(ErrorKind::BlobReference, e)
+                                .with_oid(entry.id())
+                                .with_path(String::from_utf8_lossy(entry.name_bytes()))
+                        })?;
+                    looks_binary(&head)
+                }
+                // Packed objects can't be streamed, so the whole object has
+                // to be loaded even though only its start is looked at
+                Err(_) => looks_binary(repo.find_blob(entry.id()).map_err(error)?.content()),
+            }
+        };
+
+        Ok(EntryMetadata {
+            size: Some(size),
+            binary: Some(binary),
+        })
+    }
+
+    /// The size of the entry in a short human readable form.
+    pub fn display_size(&self) -> String {
+        match self.size {
+            Some(size) => format_size(size as u64),
+            None => "".to_string(),
+        }
+    }
+}
+
+/// Whether content looks binary, using the same heuristic as git of looking
+/// for a NUL byte near the start.
+pub fn looks_binary(content: &[u8]) -> bool {
+    content.iter().take(SNIFF_LENGTH).any(|&b| b == 0)
+}
+
+/// A size in bytes in a short human readable form.
+pub fn format_size(size: u64) -> String {
+    let units = ["B", "K", "M", "G", "T"];
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < units.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{}{}", size, units[unit])
+    } else {
+        format!("{:.1}{}", value, units[unit])
+    }
+}
@@ -0,0 +1,207 @@
+use chrono::{DateTime, Local};
+
+use git2::Object;
+
+use ratatui::{
+    layout::Rect,
+    prelude::Modifier,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Paragraph},
+    Frame,
+};
+
+use crate::errors::GitBrowserError;
+use crate::traits::{Drawable, Navigable};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+
+    fn style(&self) -> Style {
+        match *self {
+            Severity::Error => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            Severity::Warning => Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        }
+    }
+}
+
+/// Where the user was in the application when an error was recorded.
+#[derive(Clone, Debug, Default)]
+pub struct LogContext {
+    pub mode: String,
+    pub reference: Option<String>,
+    pub path: Option<String>,
+}
+
+pub struct LogEntry {
+    timestamp: DateTime<Local>,
+    severity: Severity,
+    summary: String,
+    details: Vec<String>,
+    context: LogContext,
+}
+
+impl LogEntry {
+    fn lines(&self) -> Vec<Line<'_>> {
+        let mut header = vec![
+            Span::styled(
+                self.timestamp.format("%H:%M:%S ").to_string(),
+                Style::default().add_modifier(Modifier::DIM),
+            ),
+            Span::styled(
+                format!("{:8}", self.severity.as_str()),
+                self.severity.style(),
+            ),
+            Span::styled(
+                self.summary.to_string(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+        ];
+        let mut location = vec![self.context.mode.to_string()];
+        if let Some(reference) = &self.context.reference {
+            location.push(reference.to_string());
+        }
+        if let Some(path) = &self.context.path {
+            location.push(path.to_string());
+        }
+        header.push(Span::styled(
+            format!(" [{}]", location.join(" ")),
+            Style::default().fg(Color::Gray).add_modifier(Modifier::DIM),
+        ));
+
+        let mut lines = vec![Line::from(header)];
+        lines.extend(self.details.iter().map(|detail| {
+            Line::styled(
+                format!("{:17}{}", "", detail),
+                Style::default().fg(Color::Gray),
+            )
+        }));
+        lines
+    }
+}
+
+/// History of every error and warning raised during the session.
+#[derive(Default)]
+pub struct ErrorLog {
+    entries: Vec<LogEntry>,
+    unseen: usize,
+    top: usize,
+}
+
+impl ErrorLog {
+    pub fn new() -> ErrorLog {
+        ErrorLog::default()
+    }
+
+    pub fn record(&mut self, severity: Severity, error: &GitBrowserError, context: LogContext) {
+        self.entries.push(LogEntry {
+            timestamp: Local::now(),
+            severity,
+            summary: error.as_str().to_string(),
+            details: error.details(),
+            context,
+        });
+        self.unseen += 1;
+    }
+
+    /// The number of entries recorded since the log was last viewed.
+    pub fn unseen(&self) -> usize {
+        self.unseen
+    }
+
+    pub fn mark_seen(&mut self) {
+        self.unseen = 0;
+    }
+
+    fn line_count(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|entry| 1 + entry.details.len())
+            .sum()
+    }
+}
+
+impl<'repo> Drawable<'repo> for ErrorLog {
+    fn draw(&self, f: &mut Frame, area: Rect, content_block: Block) -> Rect {
+        let viewport = content_block.inner(area);
+        let lines: Vec<Line> = if self.entries.is_empty() {
+            vec![Line::styled(
+                "No errors or warnings",
+                Style::default().add_modifier(Modifier::DIM),
+            )]
+        } else {
+            // Most recent first
+            self.entries
+                .iter()
+                .rev()
+                .flat_map(|entry| entry.lines())
+                .skip(self.top)
+                .take(viewport.height.into())
+                .collect()
+        };
+        let content = Paragraph::new(lines).block(content_block);
+        f.render_widget(content, area);
+        viewport
+    }
+
+    fn title(&self) -> String {
+        "error log".to_string()
+    }
+}
+
+impl<'repo> Navigable<'repo> for ErrorLog {
+    fn home(&mut self, _page_size: u16) {
+        self.top = 0;
+    }
+
+    fn end(&mut self, page_size: u16) {
+        let h: usize = page_size.into();
+        self.top = self.line_count().saturating_sub(h);
+    }
+
+    fn pagedown(&mut self, page_size: u16) {
+        let h: usize = page_size.into();
+        let top = self.top.saturating_add(h);
+        self.top = top.min(self.line_count().saturating_sub(1));
+    }
+
+    fn pageup(&mut self, page_size: u16) {
+        let h: usize = page_size.into();
+        self.top = self.top.saturating_sub(h);
+    }
+
+    fn next_selection(&mut self) {
+        if self.top + 1 < self.line_count() {
+            self.top += 1;
+        }
+    }
+
+    fn previous_selection(&mut self) {
+        self.top = self.top.saturating_sub(1);
+    }
+
+    fn select(&self) -> Result<Option<(Object<'repo>, String)>, GitBrowserError> {
+        Ok(None)
+    }
+
+    fn selected_item(&self) -> String {
+        "".to_string()
+    }
+
+    fn next_tick(&mut self, _block: bool) -> Result<(), GitBrowserError> {
+        Ok(())
+    }
+}
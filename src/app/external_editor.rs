@@ -1,67 +1,76 @@
 use std::io::Write as _;
-use std::process::Command;
+use std::process::{Command, Stdio};
 
 use color_eyre::Result;
 
-use git2::Blob;
-
 use tempfile::{Builder, NamedTempFile};
 
-use crate::{
-    errors::{ErrorKind, GitBrowserError},
-    tui,
-};
+use crate::errors::{ErrorKind, GitBrowserError};
 
+/// A blob to be opened in the user's editor, which the app asks for rather
+/// than opening itself as the terminal belongs to whoever runs the app.
 pub struct ExternalEditor {
     editor: String,
     name: String,
     content: Vec<u8>,
 }
 
-impl<'repo> ExternalEditor {
-    pub fn new(blob: &'repo Blob, name: &str, editor: &str) -> Self {
+impl ExternalEditor {
+    pub fn new(content: &[u8], name: &str, editor: &str) -> Self {
         ExternalEditor {
             editor: editor.to_string(),
             name: name.to_string(),
-            content: blob.content().to_owned(),
+            content: content.to_owned(),
         }
     }
 
-    fn spawn_editor(&self, tempfile: &mut NamedTempFile) -> Result<(), GitBrowserError> {
+    /// The name of the blob.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The command the blob is opened with.
+    pub fn editor(&self) -> &str {
+        &self.editor
+    }
+
+    fn spawn_editor(
+        &self,
+        tempfile: &mut NamedTempFile,
+        stdout: Stdio,
+    ) -> Result<(), GitBrowserError> {
         let file = tempfile.as_file_mut();
-        file.write_all(&self.content).expect("failed to write file");
+        file.write_all(&self.content)
+            .map_err(|e| GitBrowserError::io(ErrorKind::TemporaryFile, e).with_path(&self.name))?;
 
         let mut command = Command::new(&self.editor)
             .arg(tempfile.path())
+            .stdout(stdout)
             .spawn()
-            .map_err(|_| GitBrowserError::Error(ErrorKind::Subprocess))?;
+            .map_err(|e| GitBrowserError::io(ErrorKind::Subprocess, e).with_path(&self.editor))?;
 
         let status = command
             .wait()
-            .map_err(|_| GitBrowserError::Error(ErrorKind::Subprocess))?;
+            .map_err(|e| GitBrowserError::io(ErrorKind::Subprocess, e).with_path(&self.editor))?;
 
         if status.success() {
             Ok(())
         } else {
-            Err(GitBrowserError::Error(ErrorKind::Subprocess))
+            Err(
+                GitBrowserError::message(ErrorKind::Subprocess, status.to_string())
+                    .with_path(&self.editor),
+            )
         }
     }
 
-    pub fn display(&self) -> Result<(), GitBrowserError> {
+    /// Write the blob to a temporary file and wait for the editor to exit.
+    /// The terminal has to be restored first, and `stdout` is where the
+    /// editor draws, which is the terminal when stdout is being captured.
+    pub fn open(&self, stdout: Stdio) -> Result<(), GitBrowserError> {
         let mut tempfile = Builder::new()
             .suffix(&self.name)
             .tempfile()
-            .map_err(|_| GitBrowserError::Error(ErrorKind::TemporaryFile))?;
-
-        tui::restore().map_err(|_| GitBrowserError::Error(ErrorKind::TerminalInit))?;
-        eprintln!("Opening {} with {} ...", self.name, self.editor);
-
-        let error = self.spawn_editor(&mut tempfile).err();
-        tui::init().map_err(|_| GitBrowserError::Error(ErrorKind::TerminalInit))?;
-
-        if let Some(e) = error {
-            return Err(e);
-        }
-        Ok(())
+            .map_err(|e| GitBrowserError::io(ErrorKind::TemporaryFile, e))?;
+        self.spawn_editor(&mut tempfile, stdout)
     }
 }
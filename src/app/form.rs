@@ -0,0 +1,165 @@
+use ratatui::{
+    layout::Rect,
+    prelude::Modifier,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph},
+    Frame,
+};
+
+use crate::ui::centered_rect;
+
+/// A field of a form, which is either free text or one of a fixed set of
+/// choices.
+pub struct FormField {
+    label: &'static str,
+    value: String,
+    choices: &'static [&'static str],
+}
+
+impl FormField {
+    pub fn text(label: &'static str, value: &str) -> FormField {
+        FormField {
+            label,
+            value: value.to_string(),
+            choices: &[],
+        }
+    }
+
+    /// A field that cycles through `choices`, starting at `value`.
+    pub fn choice(label: &'static str, choices: &'static [&'static str], value: &str) -> FormField {
+        let value = choices
+            .iter()
+            .find(|choice| **choice == value)
+            .unwrap_or(&choices[0]);
+        FormField {
+            label,
+            value: value.to_string(),
+            choices,
+        }
+    }
+
+    fn next_choice(&mut self) {
+        let index = self
+            .choices
+            .iter()
+            .position(|choice| *choice == self.value)
+            .unwrap_or_default();
+        self.value = self.choices[(index + 1) % self.choices.len()].to_string();
+    }
+}
+
+/// A popup of fields drawn over the current page, for entering searches
+/// and the like.
+pub struct Form {
+    title: &'static str,
+    fields: Vec<FormField>,
+    selected_index: usize,
+}
+
+impl Form {
+    pub fn new(title: &'static str, fields: Vec<FormField>) -> Form {
+        Form {
+            title,
+            fields,
+            selected_index: 0,
+        }
+    }
+
+    /// The value of the field with the given label.
+    pub fn value(&self, label: &str) -> &str {
+        self.fields
+            .iter()
+            .find(|field| field.label == label)
+            .map(|field| field.value.as_str())
+            .unwrap_or_default()
+    }
+
+    /// Type a character into the selected field. Space moves choice fields
+    /// on to their next choice.
+    pub fn input(&mut self, c: char) {
+        if let Some(field) = self.fields.get_mut(self.selected_index) {
+            if field.choices.is_empty() {
+                field.value.push(c);
+            } else if c == ' ' {
+                field.next_choice();
+            }
+        }
+    }
+
+    pub fn delete_char(&mut self) {
+        if let Some(field) = self.fields.get_mut(self.selected_index) {
+            if field.choices.is_empty() {
+                field.value.pop();
+            }
+        }
+    }
+
+    pub fn next_field(&mut self) {
+        self.selected_index = (self.selected_index + 1) % self.fields.len();
+    }
+
+    pub fn previous_field(&mut self) {
+        self.selected_index = match self.selected_index {
+            0 => self.fields.len() - 1,
+            index => index - 1,
+        };
+    }
+
+    /// Draw the form as a popup centred over `area`.
+    pub fn draw(&self, f: &mut Frame, area: Rect) {
+        let label_width = self
+            .fields
+            .iter()
+            .map(|field| field.label.len())
+            .max()
+            .unwrap_or_default()
+            + 2;
+        let height = self.fields.len() as u16 + 2;
+        let outer = centered_rect(60, 100, area);
+        let area = Rect {
+            y: outer.y + outer.height.saturating_sub(height) / 2,
+            height: height.min(outer.height),
+            ..outer
+        };
+        let popup_block = Block::default()
+            .padding(Padding::horizontal(1))
+            .borders(Borders::ALL)
+            .title(Span::styled(
+                format!(" {} ", self.title),
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ))
+            .style(Style::default().bg(Color::DarkGray));
+        let lines: Vec<Line> = self
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let style = if index == self.selected_index {
+                    Style::default().fg(Color::Black).bg(Color::Cyan)
+                } else {
+                    Style::default().fg(Color::White)
+                };
+                let value = if field.choices.is_empty() && index == self.selected_index {
+                    // Show where typing goes
+                    format!("{}_", field.value)
+                } else {
+                    field.value.to_string()
+                };
+                Line::from(vec![
+                    Span::styled(
+                        format!("{:label_width$}", format!("{}:", field.label)),
+                        Style::default()
+                            .fg(Color::White)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(value, style),
+                ])
+            })
+            .collect();
+        f.render_widget(Clear, area);
+        f.render_widget(Paragraph::new(lines).block(popup_block), area);
+    }
+}
@@ -0,0 +1,23 @@
+use std::fs;
+use std::path::PathBuf;
+
+use git2::Repository;
+
+/// The directory shared by every worktree of a repository, which holds the
+/// refs and objects. A linked worktree has a git directory of its own under
+/// `worktrees/` that only holds its `HEAD` and index, with a `commondir`
+/// file pointing back to the shared one.
+pub fn common_dir(repo: &Repository) -> PathBuf {
+    let git_dir = repo.path();
+    if !repo.is_worktree() {
+        return git_dir.to_path_buf();
+    }
+    match fs::read_to_string(git_dir.join("commondir")) {
+        // Usually relative to the worktree's git directory
+        Ok(common_dir) => {
+            let common_dir = git_dir.join(common_dir.trim_end_matches(['\r', '\n']));
+            common_dir.canonicalize().unwrap_or(common_dir)
+        }
+        Err(_) => git_dir.to_path_buf(),
+    }
+}
@@ -0,0 +1,183 @@
+use std::ops::Range;
+
+use ratatui::prelude::{Color, Style};
+
+use syntect::highlighting::{self, HighlightIterator, HighlightState, Highlighter};
+use syntect::parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet};
+
+use crate::errors::{ErrorKind, GitBrowserError};
+
+/// The parser state is saved every this many lines so that highlighting can
+/// start from anywhere in the file without parsing it from the top.
+pub const CHECKPOINT_INTERVAL: usize = 128;
+
+/// Parser state at the start of a line.
+type Checkpoint = (ParseState, ScopeStack);
+
+#[derive(Clone)]
+pub struct HighlightedLine {
+    pub components: Vec<(Style, String)>,
+}
+
+impl<'a> From<Vec<(highlighting::Style, &'a str)>> for HighlightedLine {
+    fn from(value: Vec<(highlighting::Style, &'a str)>) -> HighlightedLine {
+        HighlightedLine {
+            components: value
+                .iter()
+                .map(|(style, text)| {
+                    (
+                        TuiStyle::from(style).0,
+                        text.trim_end_matches(['\r', '\n']).to_string(),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+pub struct TuiColor(pub Color);
+pub struct TuiStyle(pub Style);
+
+impl From<&highlighting::Color> for TuiColor {
+    fn from(color: &highlighting::Color) -> TuiColor {
+        TuiColor(Color::Rgb(color.r, color.g, color.b))
+    }
+}
+
+impl From<&highlighting::Style> for TuiStyle {
+    fn from(style: &highlighting::Style) -> TuiStyle {
+        TuiStyle(
+            Style::default()
+                .fg(TuiColor::from(&style.foreground).0)
+                .bg(TuiColor::from(&style.background).0),
+        )
+    }
+}
+
+/// Text split into lines without copying it.
+pub struct SourceLines<'a> {
+    content: &'a str,
+    // Byte offset of the start of each line
+    offsets: Vec<usize>,
+}
+
+impl<'a> SourceLines<'a> {
+    pub fn new(content: &'a str) -> SourceLines<'a> {
+        let mut offsets = vec![];
+        if !content.is_empty() {
+            offsets.push(0);
+            offsets.extend(
+                content
+                    .match_indices('\n')
+                    .map(|(index, _)| index + 1)
+                    .filter(|&offset| offset < content.len()),
+            );
+        }
+        SourceLines { content, offsets }
+    }
+
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn into_offsets(self) -> Vec<usize> {
+        self.offsets
+    }
+
+    /// The text of a line, including its line ending.
+    pub fn line(&self, index: usize) -> &'a str {
+        let start = self.offsets[index];
+        let end = self
+            .offsets
+            .get(index + 1)
+            .copied()
+            .unwrap_or(self.content.len());
+        &self.content[start..end]
+    }
+}
+
+/// Incrementally parses text, saving the parser state every
+/// CHECKPOINT_INTERVAL lines so that highlighting can start from anywhere
+/// in the text without parsing it from the top.
+pub struct Checkpoints<'a> {
+    lines: SourceLines<'a>,
+    syntax_set: &'a SyntaxSet,
+    // Parser state at the start of line `index * CHECKPOINT_INTERVAL`
+    checkpoints: Vec<Checkpoint>,
+}
+
+impl<'a> Checkpoints<'a> {
+    pub fn new(
+        lines: SourceLines<'a>,
+        syntax: &SyntaxReference,
+        syntax_set: &'a SyntaxSet,
+    ) -> Checkpoints<'a> {
+        Checkpoints {
+            lines,
+            syntax_set,
+            checkpoints: vec![(ParseState::new(syntax), ScopeStack::new())],
+        }
+    }
+
+    /// Whether the whole text has been parsed.
+    pub fn is_complete(&self) -> bool {
+        self.checkpoints.len() * CHECKPOINT_INTERVAL >= self.lines.len()
+    }
+
+    /// Parse the lines after the last checkpoint to record another.
+    pub fn step(&mut self) -> Result<(), GitBrowserError> {
+        if self.is_complete() {
+            return Ok(());
+        }
+        let (mut parse_state, mut scope_stack) = self
+            .checkpoints
+            .last()
+            .cloned()
+            .expect("there is always an initial checkpoint");
+        let start = (self.checkpoints.len() - 1) * CHECKPOINT_INTERVAL;
+        let end = (start + CHECKPOINT_INTERVAL).min(self.lines.len());
+        for index in start..end {
+            let ops = parse_state
+                .parse_line(self.lines.line(index), self.syntax_set)
+                .map_err(|e| GitBrowserError::message(ErrorKind::Highlight, e.to_string()))?;
+            for (_, op) in ops {
+                scope_stack.apply(&op).map_err(|e| {
+                    GitBrowserError::message(ErrorKind::Highlight, format!("{:?}", e))
+                })?;
+            }
+        }
+        self.checkpoints.push((parse_state, scope_stack));
+        Ok(())
+    }
+
+    /// Highlight the given lines, parsing up to the start of them first if
+    /// necessary.
+    pub fn highlight(
+        &mut self,
+        lines: Range<usize>,
+        highlighter: &Highlighter,
+    ) -> Result<Vec<HighlightedLine>, GitBrowserError> {
+        let checkpoint_index = lines.start / CHECKPOINT_INTERVAL;
+        while self.checkpoints.len() <= checkpoint_index && !self.is_complete() {
+            self.step()?;
+        }
+        let checkpoint_index = checkpoint_index.min(self.checkpoints.len() - 1);
+        let (mut parse_state, scope_stack) = self.checkpoints[checkpoint_index].clone();
+        let mut highlight_state = HighlightState::new(highlighter, scope_stack);
+
+        let end = lines.end.min(self.lines.len());
+        let mut highlighted = Vec::with_capacity(end.saturating_sub(lines.start));
+        for index in checkpoint_index * CHECKPOINT_INTERVAL..end {
+            let line = self.lines.line(index);
+            let ops = parse_state
+                .parse_line(line, self.syntax_set)
+                .map_err(|e| GitBrowserError::message(ErrorKind::Highlight, e.to_string()))?;
+            let regions: Vec<(highlighting::Style, &str)> =
+                HighlightIterator::new(&mut highlight_state, &ops, line, highlighter).collect();
+            if index >= lines.start {
+                highlighted.push(HighlightedLine::from(regions));
+            }
+        }
+        Ok(highlighted)
+    }
+}
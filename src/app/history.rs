@@ -0,0 +1,380 @@
+use git2::{Object, Oid};
+
+use ratatui::{
+    layout::Rect,
+    prelude::Modifier,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem},
+    Frame,
+};
+
+use crate::app::pagination::pagination;
+use crate::errors::GitBrowserError;
+use crate::traits::{Drawable, Navigable};
+
+/// The number of locations remembered by each tab.
+const MAX_LOCATIONS: usize = 100;
+
+/// A place in a commit that can be returned to, with what was selected
+/// there.
+#[derive(Clone, Debug)]
+pub struct Location {
+    /// Where the location is, like `main:src/app.rs`.
+    pub label: String,
+    pub commit: Oid,
+    /// The reference the commit was reached through, if any.
+    pub reference: Option<String>,
+    /// The titles of the directory pages, starting with the root.
+    pub directories: Vec<String>,
+    /// The name of the entry selected in each directory.
+    pub selected: Vec<String>,
+    /// The blob being viewed and the line at the top of the screen.
+    pub blob: Option<(String, usize)>,
+}
+
+impl Location {
+    /// Whether two locations are the same place, whatever is selected there.
+    fn same_place(&self, other: &Location) -> bool {
+        self.commit == other.commit
+            && self.directories == other.directories
+            && self.blob.as_ref().map(|(name, _)| name) == other.blob.as_ref().map(|(name, _)| name)
+    }
+}
+
+/// The locations visited in a tab, for going back and forward like in a web
+/// browser. Going somewhere new after going back forgets the locations that
+/// were ahead.
+#[derive(Default)]
+pub struct History {
+    locations: Vec<Location>,
+    // The location the tab is at, or `None` when it is on a page that isn't
+    // a location, such as the list of references
+    index: Option<usize>,
+}
+
+impl History {
+    /// Note where the tab is after an action, or that it is somewhere that
+    /// isn't a location.
+    pub fn visit(&mut self, location: Option<Location>) {
+        match (self.index, location) {
+            (Some(index), Some(location)) if self.locations[index].same_place(&location) => {
+                // Keep the selection up to date for coming back to
+                self.locations[index] = location;
+            }
+            (Some(index), location) => {
+                self.locations.truncate(index + 1);
+                self.index = None;
+                if let Some(location) = location {
+                    self.push(location);
+                }
+            }
+            (None, Some(location)) => self.push(location),
+            (None, None) => {}
+        }
+    }
+
+    fn push(&mut self, location: Location) {
+        self.locations.push(location);
+        if self.locations.len() > MAX_LOCATIONS {
+            self.locations.remove(0);
+        }
+        self.index = Some(self.locations.len() - 1);
+    }
+
+    /// Move to the previous location, returning it to be opened.
+    pub fn back(&mut self) -> Option<Location> {
+        match self.index {
+            Some(0) => None,
+            Some(index) => self.jump(index - 1),
+            // The last location is the one that was left
+            None => self.jump(self.locations.len().checked_sub(1)?),
+        }
+    }
+
+    /// Move to the next location, returning it to be opened.
+    pub fn forward(&mut self) -> Option<Location> {
+        let index = self.index?;
+        self.jump(index + 1)
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        match self.index {
+            Some(index) => index > 0,
+            None => !self.locations.is_empty(),
+        }
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        self.index
+            .is_some_and(|index| index + 1 < self.locations.len())
+    }
+
+    /// Move to a location, keeping those before and after it.
+    pub fn jump(&mut self, index: usize) -> Option<Location> {
+        let location = self.locations.get(index)?.clone();
+        self.index = Some(index);
+        Some(location)
+    }
+}
+
+/// Lists the locations visited in a tab, most recent first, so that one can
+/// be jumped to.
+pub struct JumpList {
+    locations: Vec<Location>,
+    current: Option<usize>,
+    selected_index: usize,
+}
+
+impl JumpList {
+    pub fn new(history: &History) -> JumpList {
+        let locations: Vec<Location> = history.locations.iter().rev().cloned().collect();
+        let current = history.index.map(|index| locations.len() - 1 - index);
+        JumpList {
+            selected_index: current.unwrap_or(0),
+            current,
+            locations,
+        }
+    }
+
+    /// The index in the history of the selected location.
+    pub fn selected(&self) -> Option<usize> {
+        if self.selected_index < self.len() {
+            Some(self.len() - 1 - self.selected_index)
+        } else {
+            None
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.locations.len()
+    }
+}
+
+impl<'repo> Drawable<'repo> for JumpList {
+    fn draw(&self, f: &mut Frame, area: Rect, content_block: Block) -> Rect {
+        let viewport = content_block.inner(area);
+
+        let visible = viewport.height;
+        let (_page, _pages, page_start_index) =
+            pagination(self.len(), visible.into(), self.selected_index);
+
+        let list_items: Vec<ListItem> = self
+            .locations
+            .iter()
+            .enumerate()
+            .skip(page_start_index)
+            .take(visible.into())
+            .map(|(index, location)| {
+                let style = if index == self.selected_index {
+                    Style::default().fg(Color::Black).bg(Color::Cyan)
+                } else {
+                    Style::default().fg(Color::Gray)
+                };
+                let marker = if Some(index) == self.current {
+                    "* "
+                } else {
+                    "  "
+                };
+                let mut spans = vec![
+                    Span::styled(marker, Style::default().add_modifier(Modifier::DIM)),
+                    Span::styled(location.label.to_string(), style),
+                ];
+                if let Some((_, top)) = location.blob {
+                    spans.push(Span::styled(
+                        format!(" line {}", top),
+                        Style::default().add_modifier(Modifier::DIM),
+                    ));
+                }
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+        let content = List::new(list_items).block(content_block);
+        f.render_widget(content, area);
+        viewport
+    }
+
+    fn title(&self) -> String {
+        "Jump list".to_string()
+    }
+}
+
+impl<'repo> Navigable<'repo> for JumpList {
+    fn home(&mut self, _page_size: u16) {
+        self.selected_index = 0;
+    }
+
+    fn end(&mut self, _page_size: u16) {
+        self.selected_index = self.len().saturating_sub(1);
+    }
+
+    fn pagedown(&mut self, page_size: u16) {
+        let h: usize = page_size.into();
+        let index = self.selected_index.saturating_add(h);
+        self.selected_index = index.min(self.len().saturating_sub(1));
+    }
+
+    fn pageup(&mut self, page_size: u16) {
+        let h: usize = page_size.into();
+        self.selected_index = self.selected_index.saturating_sub(h);
+    }
+
+    fn next_selection(&mut self) {
+        if self.selected_index + 1 < self.len() {
+            self.selected_index += 1;
+        } else {
+            self.selected_index = 0;
+        }
+    }
+
+    fn previous_selection(&mut self) {
+        if self.selected_index > 0 {
+            self.selected_index -= 1;
+        } else {
+            self.selected_index = self.len().saturating_sub(1);
+        }
+    }
+
+    fn select(&self) -> Result<Option<(Object<'repo>, String)>, GitBrowserError> {
+        // The app opens the selected location
+        Ok(None)
+    }
+
+    fn selected_item(&self) -> String {
+        self.locations
+            .get(self.selected_index)
+            .map(|location| location.label.to_string())
+            .unwrap_or_default()
+    }
+
+    fn next_tick(&mut self, _block: bool) -> Result<(), GitBrowserError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(n: u8, blob: Option<(&str, usize)>) -> Location {
+        Location {
+            label: n.to_string(),
+            commit: Oid::from_bytes(&[n; 20]).unwrap(),
+            reference: None,
+            directories: vec!["/".to_string()],
+            selected: vec![],
+            blob: blob.map(|(name, top)| (name.to_string(), top)),
+        }
+    }
+
+    fn visit_all(history: &mut History, locations: &[u8]) {
+        for &n in locations {
+            history.visit(Some(location(n, None)));
+        }
+    }
+
+    fn label(location: Option<Location>) -> Option<String> {
+        location.map(|location| location.label)
+    }
+
+    fn labels(history: &History) -> Vec<String> {
+        history
+            .locations
+            .iter()
+            .map(|location| location.label.clone())
+            .collect()
+    }
+
+    #[test]
+    fn back_and_forward() {
+        let mut history = History::default();
+        assert!(!history.can_go_back());
+        assert_eq!(label(history.back()), None);
+        visit_all(&mut history, &[1, 2, 3]);
+        assert!(!history.can_go_forward());
+        assert_eq!(label(history.back()), Some("2".to_string()));
+        assert_eq!(label(history.back()), Some("1".to_string()));
+        assert!(!history.can_go_back());
+        assert_eq!(label(history.back()), None);
+        assert_eq!(label(history.forward()), Some("2".to_string()));
+        assert_eq!(label(history.forward()), Some("3".to_string()));
+        assert_eq!(label(history.forward()), None);
+        assert_eq!(labels(&history), ["1", "2", "3"]);
+    }
+
+    #[test]
+    fn visit_after_back_forgets_forward() {
+        let mut history = History::default();
+        visit_all(&mut history, &[1, 2, 3]);
+        history.back();
+        history.back();
+        history.visit(Some(location(4, None)));
+        assert_eq!(labels(&history), ["1", "4"]);
+        assert!(!history.can_go_forward());
+        assert_eq!(label(history.forward()), None);
+        assert_eq!(label(history.back()), Some("1".to_string()));
+    }
+
+    #[test]
+    fn leaving_the_locations() {
+        let mut history = History::default();
+        visit_all(&mut history, &[1, 2, 3]);
+        history.back();
+        // Somewhere that isn't a location, like the list of references
+        history.visit(None);
+        assert_eq!(labels(&history), ["1", "2"]);
+        assert!(history.can_go_back());
+        assert!(!history.can_go_forward());
+        assert_eq!(label(history.forward()), None);
+        history.visit(None);
+        assert_eq!(labels(&history), ["1", "2"]);
+        // Going back returns to the location that was left
+        assert_eq!(label(history.back()), Some("2".to_string()));
+        assert_eq!(label(history.back()), Some("1".to_string()));
+
+        let mut history = History::default();
+        history.visit(None);
+        visit_all(&mut history, &[1]);
+        history.visit(None);
+        visit_all(&mut history, &[2]);
+        assert_eq!(labels(&history), ["1", "2"]);
+        assert_eq!(label(history.back()), Some("1".to_string()));
+    }
+
+    #[test]
+    fn same_place_is_merged() {
+        let mut history = History::default();
+        history.visit(Some(location(1, None)));
+        let mut moved = location(1, None);
+        moved.selected = vec!["src".to_string()];
+        history.visit(Some(moved));
+        history.visit(Some(location(1, Some(("main.rs", 0)))));
+        history.visit(Some(location(1, Some(("main.rs", 40)))));
+        assert_eq!(history.locations.len(), 2);
+        assert_eq!(history.locations[0].selected, ["src"]);
+        assert_eq!(history.locations[1].blob, Some(("main.rs".to_string(), 40)));
+
+        // Another blob, directory or commit is a new location
+        history.visit(Some(location(1, Some(("lib.rs", 0)))));
+        let mut subdirectory = location(1, None);
+        subdirectory.directories.push("src".to_string());
+        history.visit(Some(subdirectory));
+        history.visit(Some(location(2, None)));
+        assert_eq!(history.locations.len(), 5);
+    }
+
+    #[test]
+    fn oldest_locations_are_forgotten() {
+        let mut history = History::default();
+        let visited: Vec<u8> = (0..MAX_LOCATIONS as u8 + 5).collect();
+        visit_all(&mut history, &visited);
+        assert_eq!(history.locations.len(), MAX_LOCATIONS);
+        assert_eq!(history.locations[0].label, "5");
+        assert_eq!(history.index, Some(MAX_LOCATIONS - 1));
+        let mut oldest = None;
+        while let Some(location) = history.back() {
+            oldest = Some(location);
+        }
+        assert_eq!(label(oldest), Some("5".to_string()));
+    }
+}
@@ -0,0 +1,230 @@
+use chrono::{Local, NaiveDate};
+
+use git2::{Commit, DiffOptions, Repository};
+
+use syntect::parsing::Regex;
+
+use crate::app::form::{Form, FormField};
+use crate::app::signature::SignatureFormatter;
+use crate::errors::{ErrorKind, GitBrowserError};
+
+const AUTHOR_FIELD: &str = "Author";
+const COMMITTER_FIELD: &str = "Committer";
+const SINCE_FIELD: &str = "Since";
+const UNTIL_FIELD: &str = "Until";
+const MESSAGE_FIELD: &str = "Message";
+const MERGES_FIELD: &str = "Merges";
+const PATHS_FIELD: &str = "Paths";
+const MERGES_CHOICES: &[&str] = &["all commits", "only merges", "no merges"];
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Whether merge commits are shown.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MergeFilter {
+    #[default]
+    All,
+    Only,
+    Exclude,
+}
+
+/// Narrows the history shown in the log, like the options of `git log`.
+/// Every filter that is set has to match for a commit to be shown.
+#[derive(Clone, Debug, Default)]
+pub struct HistoryFilter {
+    author: Option<Regex>,
+    committer: Option<Regex>,
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+    message: Option<Regex>,
+    merges: MergeFilter,
+    paths: Vec<String>,
+}
+
+impl HistoryFilter {
+    /// A form for editing the filters, filled in with the current ones.
+    pub fn form(&self) -> Form {
+        let pattern = |regex: &Option<Regex>| {
+            regex
+                .as_ref()
+                .map(|regex| regex.regex_str().to_string())
+                .unwrap_or_default()
+        };
+        let date = |date: &Option<NaiveDate>| {
+            date.map(|date| date.format(DATE_FORMAT).to_string())
+                .unwrap_or_default()
+        };
+        let merges = match self.merges {
+            MergeFilter::All => MERGES_CHOICES[0],
+            MergeFilter::Only => MERGES_CHOICES[1],
+            MergeFilter::Exclude => MERGES_CHOICES[2],
+        };
+        Form::new(
+            "Filter history",
+            vec![
+                FormField::text(AUTHOR_FIELD, &pattern(&self.author)),
+                FormField::text(COMMITTER_FIELD, &pattern(&self.committer)),
+                FormField::text(SINCE_FIELD, &date(&self.since)),
+                FormField::text(UNTIL_FIELD, &date(&self.until)),
+                FormField::text(MESSAGE_FIELD, &pattern(&self.message)),
+                FormField::choice(MERGES_FIELD, MERGES_CHOICES, merges),
+                FormField::text(PATHS_FIELD, &self.paths.join(" ")),
+            ],
+        )
+    }
+
+    pub fn from_form(form: &Form) -> Result<HistoryFilter, GitBrowserError> {
+        let merges = match form.value(MERGES_FIELD) {
+            value if value == MERGES_CHOICES[1] => MergeFilter::Only,
+            value if value == MERGES_CHOICES[2] => MergeFilter::Exclude,
+            _ => MergeFilter::All,
+        };
+        Ok(HistoryFilter {
+            author: regex_field(form, AUTHOR_FIELD)?,
+            committer: regex_field(form, COMMITTER_FIELD)?,
+            since: date_field(form, SINCE_FIELD)?,
+            until: date_field(form, UNTIL_FIELD)?,
+            message: regex_field(form, MESSAGE_FIELD)?,
+            merges,
+            paths: form
+                .value(PATHS_FIELD)
+                .split_whitespace()
+                .map(|path| path.to_string())
+                .collect(),
+        })
+    }
+
+    /// A short description of each filter that is set.
+    pub fn chips(&self) -> Vec<String> {
+        let mut chips = vec![];
+        if let Some(author) = &self.author {
+            chips.push(format!("author: {}", author.regex_str()));
+        }
+        if let Some(committer) = &self.committer {
+            chips.push(format!("committer: {}", committer.regex_str()));
+        }
+        if let Some(since) = self.since {
+            chips.push(format!("since: {}", since.format(DATE_FORMAT)));
+        }
+        if let Some(until) = self.until {
+            chips.push(format!("until: {}", until.format(DATE_FORMAT)));
+        }
+        if let Some(message) = &self.message {
+            chips.push(format!("message: {}", message.regex_str()));
+        }
+        match self.merges {
+            MergeFilter::All => {}
+            MergeFilter::Only => chips.push("merges only".to_string()),
+            MergeFilter::Exclude => chips.push("no merges".to_string()),
+        }
+        chips.extend(self.paths.iter().map(|path| format!("path: {}", path)));
+        chips
+    }
+
+    /// Whether a commit passes every filter. The paths are checked last as
+    /// they need the commit to be diffed.
+    pub fn matches(
+        &self,
+        repo: &Repository,
+        commit: &Commit,
+        people: &SignatureFormatter,
+    ) -> Result<bool, git2::Error> {
+        let merge = commit.parent_count() > 1;
+        match self.merges {
+            MergeFilter::Only if !merge => return Ok(false),
+            MergeFilter::Exclude if merge => return Ok(false),
+            _ => {}
+        }
+
+        let time = commit.time().seconds();
+        if self.since.is_some_and(|since| time < start_of_day(since)) {
+            return Ok(false);
+        }
+        // The until date is included
+        let day_after = self.until.and_then(|until| until.succ_opt());
+        if day_after.is_some_and(|day_after| time >= start_of_day(day_after)) {
+            return Ok(false);
+        }
+
+        let person_matches = |regex: &Option<Regex>, signature: &git2::Signature| {
+            regex.as_ref().is_none_or(|regex| {
+                let (name, email) = people.name_and_email(signature);
+                regex.is_match(&format!("{} <{}>", name, email))
+            })
+        };
+        if !person_matches(&self.author, &commit.author())
+            || !person_matches(&self.committer, &commit.committer())
+        {
+            return Ok(false);
+        }
+
+        if let Some(message) = &self.message {
+            if !message.is_match(&String::from_utf8_lossy(commit.message_bytes())) {
+                return Ok(false);
+            }
+        }
+
+        if self.paths.is_empty() {
+            return Ok(true);
+        }
+        self.changes_paths(repo, commit)
+    }
+
+    /// Whether the commit changed any of the paths. A merge only counts if
+    /// it differs from every parent, as otherwise it just brought in
+    /// changes made elsewhere, which is how `git log` simplifies history.
+    fn changes_paths(&self, repo: &Repository, commit: &Commit) -> Result<bool, git2::Error> {
+        let tree = commit.tree()?;
+        let mut options = DiffOptions::new();
+        for path in &self.paths {
+            options.pathspec(path);
+        }
+        if commit.parent_count() == 0 {
+            let diff = repo.diff_tree_to_tree(None, Some(&tree), Some(&mut options))?;
+            return Ok(diff.deltas().len() > 0);
+        }
+        for parent in commit.parents() {
+            let parent_tree = parent.tree()?;
+            let diff =
+                repo.diff_tree_to_tree(Some(&parent_tree), Some(&tree), Some(&mut options))?;
+            if diff.deltas().len() == 0 {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+fn regex_field(form: &Form, label: &str) -> Result<Option<Regex>, GitBrowserError> {
+    let pattern = form.value(label);
+    if pattern.is_empty() {
+        return Ok(None);
+    }
+    match Regex::try_compile(pattern) {
+        None => Ok(Some(Regex::new(pattern.to_string()))),
+        Some(e) => Err(GitBrowserError::message(ErrorKind::History, e.to_string())
+            .with_path(format!("{}: {}", label, pattern))),
+    }
+}
+
+fn date_field(form: &Form, label: &str) -> Result<Option<NaiveDate>, GitBrowserError> {
+    let date = form.value(label);
+    if date.is_empty() {
+        return Ok(None);
+    }
+    NaiveDate::parse_from_str(date, DATE_FORMAT)
+        .map(Some)
+        .map_err(|_| {
+            GitBrowserError::message(ErrorKind::History, "Dates are written as YYYY-MM-DD")
+                .with_path(format!("{}: {}", label, date))
+        })
+}
+
+/// Midnight at the start of a day in local time, in seconds since the
+/// epoch.
+fn start_of_day(date: NaiveDate) -> i64 {
+    date.and_hms_opt(0, 0, 0)
+        .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+        .map(|midnight| midnight.timestamp())
+        .unwrap_or_default()
+}
@@ -0,0 +1,183 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use git2::Repository;
+
+use crate::app::git_dir::common_dir;
+use crate::errors::{ErrorKind, GitBrowserError};
+
+/// Pointer files are never larger than this, so larger blobs aren't parsed.
+const MAX_POINTER_SIZE: usize = 1024;
+
+const POINTER_VERSION: &str = "version https://git-lfs.github.com/spec/v1";
+
+/// The contents of a Git LFS pointer file.
+#[derive(Clone, Debug)]
+pub struct LfsPointer {
+    /// The SHA-256 of the object, in hex.
+    pub oid: String,
+    pub size: u64,
+}
+
+/// A blob that is a Git LFS pointer, with the start of the object it points
+/// to if it is in the local object store.
+pub struct LfsObject {
+    pub pointer: LfsPointer,
+    pub content: Option<Vec<u8>>,
+}
+
+impl LfsPointer {
+    pub fn parse(content: &[u8]) -> Option<LfsPointer> {
+        if content.len() > MAX_POINTER_SIZE {
+            return None;
+        }
+        let text = std::str::from_utf8(content).ok()?;
+        let mut lines = text.lines();
+        if lines.next()? != POINTER_VERSION {
+            return None;
+        }
+        let mut oid = None;
+        let mut size = None;
+        for line in lines {
+            match line.split_once(' ') {
+                Some(("oid", value)) => oid = value.strip_prefix("sha256:"),
+                Some(("size", value)) => size = value.parse().ok(),
+                _ => {}
+            }
+        }
+        let oid = oid?;
+        // The oid becomes part of a path, so don't trust anything else
+        if oid.len() != 64 || !oid.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        Some(LfsPointer {
+            oid: oid.to_ascii_lowercase(),
+            size: size?,
+        })
+    }
+
+    /// Where the object is kept in the local object store, which is shared
+    /// by every worktree.
+    fn object_path(&self, repo: &Repository) -> PathBuf {
+        common_dir(repo)
+            .join("lfs")
+            .join("objects")
+            .join(&self.oid[0..2])
+            .join(&self.oid[2..4])
+            .join(&self.oid)
+    }
+
+    /// Open the object in the local object store, or `None` if it hasn't
+    /// been fetched.
+    pub fn open(&self, repo: &Repository) -> Result<Option<File>, GitBrowserError> {
+        let path = self.object_path(repo);
+        let error = |e| GitBrowserError::io(ErrorKind::Lfs, e).with_path(path.to_string_lossy());
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(error(e)),
+        };
+        let size = file.metadata().map_err(error)?.len();
+        if size != self.size {
+            return Err(GitBrowserError::message(
+                ErrorKind::Lfs,
+                format!(
+                    "Object is {} bytes but the pointer says {}",
+                    size, self.size
+                ),
+            )
+            .with_path(path.to_string_lossy()));
+        }
+        Ok(Some(file))
+    }
+
+    /// Load up to `limit` bytes from the start of the object, or `None` if
+    /// it hasn't been fetched.
+    pub fn load(&self, repo: &Repository, limit: u64) -> Result<Option<Vec<u8>>, GitBrowserError> {
+        let file = match self.open(repo)? {
+            Some(file) => file,
+            None => return Ok(None),
+        };
+        let mut content = Vec::with_capacity(self.size.min(limit) as usize);
+        file.take(limit).read_to_end(&mut content).map_err(|e| {
+            GitBrowserError::io(ErrorKind::Lfs, e)
+                .with_path(self.object_path(repo).to_string_lossy())
+        })?;
+        Ok(Some(content))
+    }
+}
+
+/// If the content is an LFS pointer, load up to `limit` bytes of the object
+/// it points to.
+pub fn resolve(
+    repo: &Repository,
+    content: &[u8],
+    limit: u64,
+) -> Result<Option<LfsObject>, GitBrowserError> {
+    let pointer = match LfsPointer::parse(content) {
+        Some(pointer) => pointer,
+        None => return Ok(None),
+    };
+    let content = pointer.load(repo, limit)?;
+    Ok(Some(LfsObject { pointer, content }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OID: &str = "4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393";
+
+    fn pointer(oid: &str, size: &str) -> String {
+        format!("{}\noid sha256:{}\nsize {}\n", POINTER_VERSION, oid, size)
+    }
+
+    #[test]
+    fn parse_valid_pointer() {
+        let parsed = LfsPointer::parse(pointer(OID, "12345").as_bytes()).unwrap();
+        assert_eq!(parsed.oid, OID);
+        assert_eq!(parsed.size, 12345);
+    }
+
+    #[test]
+    fn parse_upper_case_oid() {
+        let content = pointer(&OID.to_ascii_uppercase(), "1");
+        let parsed = LfsPointer::parse(content.as_bytes()).unwrap();
+        assert_eq!(parsed.oid, OID);
+    }
+
+    #[test]
+    fn parse_wrong_version() {
+        let content = pointer(OID, "1").replace("spec/v1", "spec/v2");
+        assert!(LfsPointer::parse(content.as_bytes()).is_none());
+        let content = format!("\n{}", pointer(OID, "1"));
+        assert!(LfsPointer::parse(content.as_bytes()).is_none());
+    }
+
+    #[test]
+    fn parse_invalid_oid() {
+        assert!(LfsPointer::parse(pointer(&OID[1..], "1").as_bytes()).is_none());
+        let non_hex = format!("{}g", &OID[1..]);
+        assert!(LfsPointer::parse(pointer(&non_hex, "1").as_bytes()).is_none());
+        let traversal = format!("../{}", &OID[3..]);
+        assert!(LfsPointer::parse(pointer(&traversal, "1").as_bytes()).is_none());
+        let content = pointer(OID, "1").replace("sha256:", "sha1:");
+        assert!(LfsPointer::parse(content.as_bytes()).is_none());
+    }
+
+    #[test]
+    fn parse_missing_size() {
+        let content = format!("{}\noid sha256:{}\n", POINTER_VERSION, OID);
+        assert!(LfsPointer::parse(content.as_bytes()).is_none());
+        assert!(LfsPointer::parse(pointer(OID, "-1").as_bytes()).is_none());
+        assert!(LfsPointer::parse(pointer(OID, "big").as_bytes()).is_none());
+    }
+
+    #[test]
+    fn parse_too_large() {
+        let mut content = pointer(OID, "1");
+        content.push_str(&"x".repeat(MAX_POINTER_SIZE));
+        assert!(LfsPointer::parse(content.as_bytes()).is_none());
+    }
+}
@@ -0,0 +1,425 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Sender};
+
+use git2::{Object, Oid, Repository, Revwalk, Sort};
+
+use ratatui::{
+    layout::Rect,
+    prelude::Modifier,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem},
+    Frame,
+};
+
+use crate::app::commit_graph::{CommitGraph, GraphCell};
+use crate::app::history_filter::HistoryFilter;
+use crate::app::pagination::pagination;
+use crate::app::signature::{format_time, SignatureFormatter};
+use crate::app::worker::{CancelToken, Mailbox, Task, TaskResult, WorkerHandle};
+use crate::errors::{ErrorKind, GitBrowserError};
+use crate::traits::{Drawable, Navigable};
+
+/// How many commits are read from the history at a time.
+const BATCH_SIZE: usize = 200;
+
+/// The colours lanes of the graph cycle through.
+const LANE_COLORS: [Color; 6] = [
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+];
+
+pub struct LogEntry {
+    id: Oid,
+    summary: String,
+    author: String,
+    /// When the commit was made, in seconds since the epoch.
+    time: i64,
+    graph: Vec<GraphCell>,
+}
+
+/// Reads the history of a revision a batch at a time, keeping the commits
+/// that pass the filter. Filtering by paths diffs every commit, so this is
+/// done by a worker.
+pub struct HistoryReader<'repo> {
+    repo: &'repo Repository,
+    revision: String,
+    revwalk: Revwalk<'repo>,
+    graph: CommitGraph,
+    people: SignatureFormatter,
+    filter: HistoryFilter,
+}
+
+impl<'repo> HistoryReader<'repo> {
+    pub fn new(
+        repo: &'repo Repository,
+        revision: String,
+        people: SignatureFormatter,
+        filter: HistoryFilter,
+    ) -> Result<HistoryReader<'repo>, GitBrowserError> {
+        Ok(HistoryReader {
+            repo,
+            revwalk: walk(repo, &revision)?,
+            revision,
+            graph: CommitGraph::new(),
+            people,
+            filter,
+        })
+    }
+
+    /// Read the next batch of commits, returning those that are shown and
+    /// whether the end of the history was reached. Fewer may be shown than
+    /// were read if some are filtered out.
+    pub fn read_batch(
+        &mut self,
+        cancelled: &AtomicBool,
+    ) -> Result<(Vec<LogEntry>, bool), GitBrowserError> {
+        let mut entries = vec![];
+        for _ in 0..BATCH_SIZE {
+            if cancelled.load(Ordering::Relaxed) {
+                break;
+            }
+            let id = match self.revwalk.next() {
+                Some(id) => id.map_err(|e| {
+                    GitBrowserError::git(ErrorKind::History, e).with_path(&self.revision)
+                })?,
+                None => return Ok((entries, true)),
+            };
+            let commit = self
+                .repo
+                .find_commit(id)
+                .map_err(|e| GitBrowserError::git(ErrorKind::CommitReference, e).with_oid(id))?;
+            let parents: Vec<Oid> = commit.parent_ids().collect();
+            let shown = self
+                .filter
+                .matches(self.repo, &commit, &self.people)
+                .map_err(|e| GitBrowserError::git(ErrorKind::History, e).with_oid(id))?;
+            if !shown {
+                self.graph.skip(id, &parents);
+                continue;
+            }
+            entries.push(LogEntry {
+                id,
+                summary: String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default())
+                    .to_string(),
+                author: self.people.name(&commit.author()),
+                time: commit.time().seconds(),
+                graph: self.graph.add(id, &parents),
+            });
+        }
+        Ok((entries, false))
+    }
+}
+
+/// Lists the history of a revision with a graph of its branches and
+/// merges, narrowed by a filter. The history is read as it is scrolled
+/// through, as it may be far too long to read up front.
+pub struct LogPage<'repo> {
+    repo: &'repo Repository,
+    revision: String,
+    workers: WorkerHandle,
+    mailbox: Mailbox,
+    // Asks the worker reading the history for another batch, or `None`
+    // once there is nothing more to read
+    requests: Option<Sender<()>>,
+    // Whether a batch has been asked for and not arrived yet
+    loading: bool,
+    // Stops the worker when the page is closed or the filter changes
+    cancel: CancelToken,
+    entries: Vec<LogEntry>,
+    decorations: HashMap<Oid, Vec<String>>,
+    filter: HistoryFilter,
+    selected_index: usize,
+}
+
+impl<'repo> LogPage<'repo> {
+    pub fn new(
+        repo: &'repo Repository,
+        revision: String,
+        decorations: HashMap<Oid, Vec<String>>,
+        filter: HistoryFilter,
+        workers: &WorkerHandle,
+    ) -> Result<LogPage<'repo>, GitBrowserError> {
+        // Check the revision here so that a bad one is reported straight
+        // away rather than by the worker
+        walk(repo, &revision)?;
+        let mut page = LogPage {
+            repo,
+            revision,
+            workers: workers.clone(),
+            mailbox: Mailbox::new(),
+            requests: None,
+            loading: false,
+            cancel: CancelToken::new(),
+            entries: vec![],
+            decorations,
+            filter,
+            selected_index: 0,
+        };
+        page.start_reading();
+        Ok(page)
+    }
+
+    /// Start a worker reading the history from the start, replacing any
+    /// previous one.
+    fn start_reading(&mut self) {
+        // Dropping the previous token and sender stops the previous worker,
+        // and results it already sent are left in the previous mailbox
+        self.cancel = CancelToken::new();
+        self.mailbox = Mailbox::new();
+        let (sender, requests) = channel();
+        self.workers.submit_to(
+            Task::Log {
+                revision: self.revision.to_string(),
+                filter: self.filter.clone(),
+                requests,
+            },
+            Some(&self.cancel),
+            &self.mailbox,
+        );
+        self.requests = Some(sender);
+        self.entries.clear();
+        self.selected_index = 0;
+        self.load_more();
+    }
+
+    pub fn filter(&self) -> &HistoryFilter {
+        &self.filter
+    }
+
+    /// Show the history again with a different filter.
+    pub fn set_filter(&mut self, filter: HistoryFilter) {
+        self.filter = filter;
+        self.start_reading();
+    }
+
+    /// Replace the branch and tag names shown next to commits.
+    pub fn set_decorations(&mut self, decorations: HashMap<Oid, Vec<String>>) {
+        self.decorations = decorations;
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the whole history has been read.
+    fn finished(&self) -> bool {
+        self.requests.is_none()
+    }
+
+    /// Ask for the next batch of commits, unless one is on its way.
+    fn load_more(&mut self) {
+        if self.loading {
+            return;
+        }
+        match &self.requests {
+            Some(requests) if requests.send(()).is_ok() => self.loading = true,
+            // The worker stopped, having failed
+            _ => self.requests = None,
+        }
+    }
+
+    /// Add the commits read since last time, returning why reading failed
+    /// if it did.
+    pub fn receive_results(&mut self) -> Vec<GitBrowserError> {
+        let mut failures = vec![];
+        for result in self.mailbox.results() {
+            match result {
+                TaskResult::Log { entries, finished } => {
+                    self.entries.extend(entries);
+                    self.loading = false;
+                    if finished {
+                        self.requests = None;
+                    }
+                }
+                TaskResult::Failed(e) => {
+                    self.loading = false;
+                    self.requests = None;
+                    failures.push(e);
+                }
+                _ => {}
+            }
+        }
+        failures
+    }
+
+    fn graph_spans(graph: &[GraphCell], width: usize) -> Vec<Span<'static>> {
+        let mut spans: Vec<Span> = graph
+            .iter()
+            .flat_map(|cell| {
+                let style = Style::default().fg(LANE_COLORS[cell.lane % LANE_COLORS.len()]);
+                [
+                    Span::styled(cell.symbol.to_string(), style),
+                    Span::styled(cell.connector.to_string(), style),
+                ]
+            })
+            .collect();
+        spans.push(Span::raw(" ".repeat(2 * width.saturating_sub(graph.len()))));
+        spans
+    }
+
+    fn decoration_spans(&self, id: Oid) -> Vec<Span<'static>> {
+        let names = match self.decorations.get(&id) {
+            Some(names) => names,
+            None => return vec![],
+        };
+        let mut spans = vec![Span::styled("(", Style::default().fg(Color::Yellow))];
+        for (index, name) in names.iter().enumerate() {
+            if index > 0 {
+                spans.push(Span::styled(", ", Style::default().fg(Color::Yellow)));
+            }
+            let color = if name == "HEAD" {
+                Color::Cyan
+            } else if name.starts_with("tag: ") {
+                Color::Yellow
+            } else {
+                Color::Green
+            };
+            spans.push(Span::styled(
+                name.to_string(),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ));
+        }
+        spans.push(Span::styled(") ", Style::default().fg(Color::Yellow)));
+        spans
+    }
+}
+
+/// Walk the history from a revision.
+pub fn walk<'repo>(
+    repo: &'repo Repository,
+    revision: &str,
+) -> Result<Revwalk<'repo>, GitBrowserError> {
+    let error = |e| GitBrowserError::git(ErrorKind::History, e).with_path(revision);
+    let commit = repo
+        .revparse_single(revision)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(error)?;
+    let mut revwalk = repo.revwalk().map_err(error)?;
+    // Topological order can only be worked out by reading the whole history
+    // first, so commits are taken newest first
+    revwalk.set_sorting(Sort::TIME).map_err(error)?;
+    revwalk.push(commit.id()).map_err(error)?;
+    Ok(revwalk)
+}
+
+impl<'repo> Drawable<'repo> for LogPage<'repo> {
+    fn draw(&self, f: &mut Frame, area: Rect, content_block: Block) -> Rect {
+        let viewport = content_block.inner(area);
+
+        let visible = viewport.height;
+        let (_page, _pages, page_start_index) =
+            pagination(self.len(), visible.into(), self.selected_index);
+        let page = self
+            .entries
+            .iter()
+            .enumerate()
+            .skip(page_start_index)
+            .take(visible.into());
+        let graph_width = page
+            .clone()
+            .map(|(_, entry)| entry.graph.len())
+            .max()
+            .unwrap_or_default();
+
+        let list_items: Vec<ListItem> = page
+            .map(|(index, entry)| {
+                let style = if index == self.selected_index {
+                    Style::default().fg(Color::Black).bg(Color::Cyan)
+                } else {
+                    Style::default().fg(Color::Gray)
+                };
+                let mut spans = LogPage::graph_spans(&entry.graph, graph_width);
+                spans.push(Span::styled(
+                    format!("{} ", &entry.id.to_string()[..7]),
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::DIM),
+                ));
+                spans.extend(self.decoration_spans(entry.id));
+                spans.push(Span::styled(entry.summary.to_string(), style));
+                spans.push(Span::styled(
+                    format!(" {} {}", entry.author, format_time(entry.time)),
+                    Style::default().add_modifier(Modifier::DIM),
+                ));
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+        let content = List::new(list_items).block(content_block);
+        f.render_widget(content, area);
+        viewport
+    }
+
+    fn title(&self) -> String {
+        self.revision.to_string()
+    }
+}
+
+impl<'repo> Navigable<'repo> for LogPage<'repo> {
+    fn home(&mut self, _page_size: u16) {
+        self.selected_index = 0;
+    }
+
+    fn end(&mut self, _page_size: u16) {
+        self.selected_index = self.len().saturating_sub(1);
+    }
+
+    fn pagedown(&mut self, page_size: u16) {
+        let h: usize = page_size.into();
+        let index = self.selected_index.saturating_add(h);
+        self.selected_index = index.min(self.len().saturating_sub(1));
+    }
+
+    fn pageup(&mut self, page_size: u16) {
+        let h: usize = page_size.into();
+        self.selected_index = self.selected_index.saturating_sub(h);
+    }
+
+    fn next_selection(&mut self) {
+        if self.selected_index + 1 < self.len() {
+            self.selected_index += 1;
+        } else if self.finished() {
+            self.selected_index = 0;
+        }
+    }
+
+    fn previous_selection(&mut self) {
+        if self.selected_index > 0 {
+            self.selected_index -= 1;
+        } else if self.finished() {
+            self.selected_index = self.len().saturating_sub(1);
+        }
+    }
+
+    fn select(&self) -> Result<Option<(Object<'repo>, String)>, GitBrowserError> {
+        let entry = match self.entries.get(self.selected_index) {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        match self.repo.find_object(entry.id, None) {
+            Ok(object) => Ok(Some((object, "".to_string()))),
+            Err(e) => Err(GitBrowserError::git(ErrorKind::CommitReference, e).with_oid(entry.id)),
+        }
+    }
+
+    fn selected_item(&self) -> String {
+        self.entries
+            .get(self.selected_index)
+            .map(|entry| entry.id.to_string())
+            .unwrap_or_default()
+    }
+
+    /// Read more of the history once the selection gets within half a
+    /// batch of the end of what has been read.
+    fn next_tick(&mut self, _block: bool) -> Result<(), GitBrowserError> {
+        if !self.finished() && self.selected_index + BATCH_SIZE / 2 >= self.len() {
+            self.load_more();
+        }
+        Ok(())
+    }
+}
@@ -13,6 +13,29 @@ pub enum NavigationAction {
     NextSelection,
     PreviousSelection,
     ExternalEditor,
+    ErrorLog,
+    JumpToTip,
+    Refresh,
+    SetSyntax,
+    Reflog,
+    Log,
+    Pickaxe,
+    FilterHistory,
+    CommitDetails,
+    NotesRef,
+    Pick,
+    ToggleColumns,
+    HistoryBack,
+    HistoryForward,
+    JumpList,
+    NewTab,
+    DuplicateTab,
+    CloseTab,
+    NextTab,
+    PreviousTab,
+    NextField,
+    Input(char),
+    DeleteChar,
     Exit,
     Tick,
     Invalid,
@@ -33,11 +56,41 @@ impl From<KeyEvent> for NavigationAction {
             (KeyCode::PageDown, 0) => NavigationAction::PageDown,
             (KeyCode::Up, 0) => NavigationAction::PreviousSelection,
             (KeyCode::Down, 0) => NavigationAction::NextSelection,
+            (KeyCode::F(5), 0) => NavigationAction::Refresh,
+            (KeyCode::Tab, 0) => NavigationAction::NextField,
+            (KeyCode::Backspace, 0) => NavigationAction::DeleteChar,
+            (KeyCode::Char(c), 0) => NavigationAction::Input(c),
+            (KeyCode::Char(c), modifiers) if modifiers == KeyModifiers::SHIFT.bits() => {
+                NavigationAction::Input(c)
+            }
             (keycode, modifiers) => {
                 if modifiers == KeyModifiers::CONTROL.bits() {
                     match keycode {
                         KeyCode::Char('g') => NavigationAction::Back,
                         KeyCode::Char('e') => NavigationAction::ExternalEditor,
+                        KeyCode::Char('l') => NavigationAction::ErrorLog,
+                        KeyCode::Char('u') => NavigationAction::JumpToTip,
+                        KeyCode::Char('s') => NavigationAction::SetSyntax,
+                        KeyCode::Char('r') => NavigationAction::Reflog,
+                        KeyCode::Char('o') => NavigationAction::Log,
+                        KeyCode::Char('f') => NavigationAction::Pickaxe,
+                        KeyCode::Char('k') => NavigationAction::FilterHistory,
+                        KeyCode::Char('d') => NavigationAction::CommitDetails,
+                        KeyCode::Char('n') => NavigationAction::NotesRef,
+                        KeyCode::Char('p') => NavigationAction::Pick,
+                        KeyCode::Char('b') => NavigationAction::ToggleColumns,
+                        KeyCode::Char('j') => NavigationAction::JumpList,
+                        KeyCode::Char('t') => NavigationAction::NewTab,
+                        KeyCode::Char('y') => NavigationAction::DuplicateTab,
+                        KeyCode::Char('w') => NavigationAction::CloseTab,
+                        KeyCode::Right => NavigationAction::NextTab,
+                        KeyCode::Left => NavigationAction::PreviousTab,
+                        _ => NavigationAction::Invalid,
+                    }
+                } else if modifiers == KeyModifiers::ALT.bits() {
+                    match keycode {
+                        KeyCode::Left => NavigationAction::HistoryBack,
+                        KeyCode::Right => NavigationAction::HistoryForward,
                         _ => NavigationAction::Invalid,
                     }
                 } else {
@@ -67,9 +120,32 @@ impl From<&NavigationAction> for ActionInfo {
             NavigationAction::NextSelection => ("Down", "Select the next item"),
             NavigationAction::PreviousSelection => ("Up", "Select the previous item"),
             NavigationAction::ExternalEditor => ("C-e", "Launch external pager for blob"),
+            NavigationAction::ErrorLog => ("C-l", "Error log"),
+            NavigationAction::JumpToTip => ("C-u", "Jump to new tip"),
+            NavigationAction::Refresh => ("F5", "Refresh"),
+            NavigationAction::SetSyntax => ("C-s", "Set syntax"),
+            NavigationAction::Reflog => ("C-r", "Reflog"),
+            NavigationAction::Log => ("C-o", "Log"),
+            NavigationAction::Pickaxe => ("C-f", "Pickaxe search"),
+            NavigationAction::FilterHistory => ("C-k", "Filter history"),
+            NavigationAction::CommitDetails => ("C-d", "Commit details"),
+            NavigationAction::NotesRef => ("C-n", "Notes ref"),
+            NavigationAction::Pick => ("C-p", "Pick"),
+            NavigationAction::ToggleColumns => ("C-b", "Columns"),
+            NavigationAction::HistoryBack => ("M-Left", "History back"),
+            NavigationAction::HistoryForward => ("M-Right", "History forward"),
+            NavigationAction::JumpList => ("C-j", "Jump list"),
+            NavigationAction::NewTab => ("C-t", "New tab"),
+            NavigationAction::DuplicateTab => ("C-y", "Duplicate tab"),
+            NavigationAction::CloseTab => ("C-w", "Close tab"),
+            NavigationAction::NextTab => ("C-Right", "Next tab"),
+            NavigationAction::PreviousTab => ("C-Left", "Previous tab"),
+            NavigationAction::NextField => ("Tab", "Next field"),
             // We never want to see this but have to define it
             NavigationAction::Invalid => ("invalid", "invalid"),
             NavigationAction::Tick => ("invalid", "invalid"),
+            NavigationAction::Input(_) => ("invalid", "invalid"),
+            NavigationAction::DeleteChar => ("invalid", "invalid"),
         };
         ActionInfo {
             key: key.to_string(),
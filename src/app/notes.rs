@@ -0,0 +1,176 @@
+use git2::{ErrorCode, Object, Oid, Repository};
+
+use ratatui::{
+    layout::Rect,
+    prelude::Modifier,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem},
+    Frame,
+};
+
+use crate::app::pagination::pagination;
+use crate::errors::{ErrorKind, GitBrowserError};
+use crate::traits::{Drawable, Navigable};
+
+/// Used when `core.notesRef` isn't set.
+pub const DEFAULT_NOTES_REF: &str = "refs/notes/commits";
+
+/// The notes ref git shows by default.
+pub fn default_notes_ref(repo: &Repository) -> String {
+    repo.note_default_ref()
+        .unwrap_or_else(|_| DEFAULT_NOTES_REF.to_string())
+}
+
+/// The note attached to an object in a notes ref, if there is one.
+pub fn find_note(
+    repo: &Repository,
+    notes_ref: &str,
+    id: Oid,
+) -> Result<Option<String>, GitBrowserError> {
+    match repo.find_note(Some(notes_ref), id) {
+        Ok(note) => Ok(Some(
+            String::from_utf8_lossy(note.message_bytes()).to_string(),
+        )),
+        // Either the object has no note or the notes ref doesn't exist
+        Err(e) if e.code() == ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(GitBrowserError::git(ErrorKind::Notes, e)
+            .with_oid(id)
+            .with_path(notes_ref)),
+    }
+}
+
+/// How git labels notes from a ref, e.g. `Notes (review):`.
+pub fn notes_label(notes_ref: &str) -> String {
+    if notes_ref == DEFAULT_NOTES_REF {
+        "Notes:".to_string()
+    } else {
+        let name = notes_ref.strip_prefix("refs/notes/").unwrap_or(notes_ref);
+        format!("Notes ({}):", name)
+    }
+}
+
+/// Lists the notes refs so that one can be chosen for display.
+pub struct NotesRefPicker {
+    refs: Vec<String>,
+    current: String,
+    selected_index: usize,
+}
+
+impl NotesRefPicker {
+    pub fn new(repo: &Repository, current: &str) -> Result<NotesRefPicker, GitBrowserError> {
+        let references = repo
+            .references_glob("refs/notes/*")
+            .map_err(|e| GitBrowserError::git(ErrorKind::Notes, e))?;
+        let mut refs: Vec<String> = references
+            .filter_map(|reference| reference.ok())
+            .filter_map(|reference| reference.name().map(|name| name.to_string()))
+            .collect();
+        // The current ref may not have been created yet
+        if !refs.iter().any(|name| name == current) {
+            refs.push(current.to_string());
+        }
+        refs.sort();
+        let selected_index = refs.iter().position(|name| name == current).unwrap_or(0);
+        Ok(NotesRefPicker {
+            refs,
+            current: current.to_string(),
+            selected_index,
+        })
+    }
+
+    pub fn selected(&self) -> Option<&str> {
+        self.refs.get(self.selected_index).map(|name| name.as_str())
+    }
+
+    fn len(&self) -> usize {
+        self.refs.len()
+    }
+}
+
+impl<'repo> Drawable<'repo> for NotesRefPicker {
+    fn draw(&self, f: &mut Frame, area: Rect, content_block: Block) -> Rect {
+        let viewport = content_block.inner(area);
+
+        let visible = viewport.height;
+        let (_page, _pages, page_start_index) =
+            pagination(self.len(), visible.into(), self.selected_index);
+
+        let list_items: Vec<ListItem> = self
+            .refs
+            .iter()
+            .enumerate()
+            .skip(page_start_index)
+            .take(visible.into())
+            .map(|(index, name)| {
+                let style = if index == self.selected_index {
+                    Style::default().fg(Color::Black).bg(Color::Cyan)
+                } else {
+                    Style::default().fg(Color::Gray)
+                };
+                let marker = if *name == self.current { "* " } else { "  " };
+                ListItem::new(Line::from(vec![
+                    Span::styled(marker, Style::default().add_modifier(Modifier::DIM)),
+                    Span::styled(name, style),
+                ]))
+            })
+            .collect();
+        let content = List::new(list_items).block(content_block);
+        f.render_widget(content, area);
+        viewport
+    }
+
+    fn title(&self) -> String {
+        "notes ref".to_string()
+    }
+}
+
+impl<'repo> Navigable<'repo> for NotesRefPicker {
+    fn home(&mut self, _page_size: u16) {
+        self.selected_index = 0;
+    }
+
+    fn end(&mut self, _page_size: u16) {
+        self.selected_index = self.len().saturating_sub(1);
+    }
+
+    fn pagedown(&mut self, page_size: u16) {
+        let h: usize = page_size.into();
+        let index = self.selected_index.saturating_add(h);
+        self.selected_index = index.min(self.len().saturating_sub(1));
+    }
+
+    fn pageup(&mut self, page_size: u16) {
+        let h: usize = page_size.into();
+        self.selected_index = self.selected_index.saturating_sub(h);
+    }
+
+    fn next_selection(&mut self) {
+        if self.selected_index + 1 < self.len() {
+            self.selected_index += 1;
+        } else {
+            self.selected_index = 0;
+        }
+    }
+
+    fn previous_selection(&mut self) {
+        if self.selected_index > 0 {
+            self.selected_index -= 1;
+        } else {
+            self.selected_index = self.len().saturating_sub(1);
+        }
+    }
+
+    fn select(&self) -> Result<Option<(Object<'repo>, String)>, GitBrowserError> {
+        // The app switches to the selected notes ref
+        Ok(None)
+    }
+
+    fn selected_item(&self) -> String {
+        self.selected().unwrap_or_default().to_string()
+    }
+
+    fn next_tick(&mut self, _block: bool) -> Result<(), GitBrowserError> {
+        Ok(())
+    }
+}
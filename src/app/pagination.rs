@@ -4,12 +4,12 @@ pub fn pagination(
     selected_index: usize,
 ) -> (usize, usize, usize) {
     let page_start_index = selected_index - (selected_index % visible_item_count);
-    let pages = if item_count % visible_item_count > 0 {
+    let pages = if !item_count.is_multiple_of(visible_item_count) {
         item_count / visible_item_count + 1
     } else {
         item_count / visible_item_count
     };
-    let page = if page_start_index % visible_item_count > 0 {
+    let page = if !page_start_index.is_multiple_of(visible_item_count) {
         page_start_index / visible_item_count + 1
     } else {
         page_start_index / visible_item_count
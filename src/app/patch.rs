@@ -0,0 +1,14 @@
+use git2::{Diff, DiffFormat};
+
+/// The text of a diff as `git diff` prints it.
+pub fn patch_text(diff: &Diff) -> Result<Vec<u8>, git2::Error> {
+    let mut patch = vec![];
+    diff.print(DiffFormat::Patch, |_delta, _hunk, line| {
+        if let origin @ ('+' | '-' | ' ') = line.origin() {
+            patch.push(origin as u8);
+        }
+        patch.extend_from_slice(line.content());
+        true
+    })?;
+    Ok(patch)
+}
@@ -0,0 +1,53 @@
+use std::str::FromStr;
+
+use git2::Oid;
+
+/// What is printed for the object chosen in `--pick` mode.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PickFormat {
+    /// `REV:PATH` for tree entries, or the name of a reference.
+    Revision,
+    Oid,
+    /// Text with `{rev}`, `{path}`, `{oid}` and `{ref}` replaced.
+    Template(String),
+}
+
+impl FromStr for PickFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<PickFormat, String> {
+        match value {
+            "rev" => Ok(PickFormat::Revision),
+            "oid" => Ok(PickFormat::Oid),
+            template if template.contains('{') => Ok(PickFormat::Template(template.to_string())),
+            _ => Err("expected rev, oid or a template such as '{rev}:{path}'".to_string()),
+        }
+    }
+}
+
+/// A tree entry or reference chosen in `--pick` mode.
+pub struct Pick {
+    /// The commit a tree entry is in, or the name of a reference.
+    pub rev: String,
+    /// The path of a tree entry from the root of the tree, or empty for a
+    /// reference.
+    pub path: String,
+    pub oid: Oid,
+    /// The reference the commit was reached through, if any.
+    pub reference: Option<String>,
+}
+
+impl Pick {
+    pub fn format(&self, format: &PickFormat) -> String {
+        match format {
+            PickFormat::Revision if self.path.is_empty() => self.rev.to_string(),
+            PickFormat::Revision => format!("{}:{}", self.rev, self.path),
+            PickFormat::Oid => self.oid.to_string(),
+            PickFormat::Template(template) => template
+                .replace("{rev}", &self.rev)
+                .replace("{path}", &self.path)
+                .replace("{oid}", &self.oid.to_string())
+                .replace("{ref}", self.reference.as_deref().unwrap_or_default()),
+        }
+    }
+}
@@ -0,0 +1,567 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use git2::{Blob, Commit, DiffFile, DiffOptions, FileMode, Object, Oid, Repository, Sort};
+
+use memchr::memmem::Finder;
+
+use ratatui::{
+    layout::Rect,
+    prelude::Modifier,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem},
+    Frame,
+};
+
+use syntect::parsing::Regex;
+
+use crate::app::form::{Form, FormField};
+use crate::app::pagination::pagination;
+use crate::app::patch::patch_text;
+use crate::app::signature::format_time;
+use crate::app::worker::{CancelToken, Mailbox, Task, TaskResult, WorkerHandle};
+use crate::errors::{ErrorKind, GitBrowserError};
+use crate::traits::{Drawable, Navigable};
+
+/// How many commits are searched between reports of progress.
+const PROGRESS_INTERVAL: usize = 500;
+
+/// Like git's `core.bigFileThreshold`, blobs larger than this are taken to
+/// be binary without reading them.
+const BIG_FILE_THRESHOLD: usize = 512 * 1024 * 1024;
+
+const PATTERN_FIELD: &str = "Pattern";
+const KIND_FIELD: &str = "Match";
+const KIND_CHOICES: &[&str] = &["string (-S)", "regex (-G)"];
+
+/// What a commit has to change to be found.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PickaxeKind {
+    /// The number of occurrences of a string in a file, like `git log -S`.
+    Occurrences,
+    /// An added or removed line matching a regex, like `git log -G`.
+    Regex,
+}
+
+impl PickaxeKind {
+    fn flag(&self) -> &'static str {
+        match *self {
+            PickaxeKind::Occurrences => "-S",
+            PickaxeKind::Regex => "-G",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PickaxeQuery {
+    pub start: Oid,
+    pub pattern: String,
+    pub kind: PickaxeKind,
+}
+
+impl PickaxeQuery {
+    /// A form for entering the pattern and how to match it.
+    pub fn form() -> Form {
+        Form::new(
+            "Pickaxe search",
+            vec![
+                FormField::text(PATTERN_FIELD, ""),
+                FormField::choice(KIND_FIELD, KIND_CHOICES, KIND_CHOICES[0]),
+            ],
+        )
+    }
+
+    pub fn from_form(start: Oid, form: &Form) -> Result<PickaxeQuery, GitBrowserError> {
+        let pattern = form.value(PATTERN_FIELD).to_string();
+        if pattern.is_empty() {
+            return Err(GitBrowserError::message(
+                ErrorKind::Pickaxe,
+                "Nothing to search for",
+            ));
+        }
+        let kind = if form.value(KIND_FIELD) == KIND_CHOICES[1] {
+            if let Some(e) = Regex::try_compile(&pattern) {
+                return Err(
+                    GitBrowserError::message(ErrorKind::Pickaxe, e.to_string()).with_path(pattern)
+                );
+            }
+            PickaxeKind::Regex
+        } else {
+            PickaxeKind::Occurrences
+        };
+        Ok(PickaxeQuery {
+            start,
+            pattern,
+            kind,
+        })
+    }
+}
+
+/// A commit found by a search, with the files whose changes matched.
+#[derive(Clone)]
+pub struct PickaxeMatch {
+    pub id: Oid,
+    pub summary: String,
+    /// When the commit was made, in seconds since the epoch.
+    pub time: i64,
+    pub paths: Vec<String>,
+}
+
+enum Matcher<'a> {
+    Occurrences(Box<Finder<'a>>),
+    Regex(Regex),
+}
+
+/// Walk the history from the start of the query, reporting matches and the
+/// number of commits searched so far as they are found.
+pub fn search(
+    repo: &Repository,
+    query: &PickaxeQuery,
+    cancelled: &AtomicBool,
+    mut report: impl FnMut(Vec<PickaxeMatch>, usize, bool),
+) -> Result<(), GitBrowserError> {
+    let error = |e| GitBrowserError::git(ErrorKind::Pickaxe, e).with_oid(query.start);
+    let matcher = match query.kind {
+        PickaxeKind::Occurrences => {
+            Matcher::Occurrences(Box::new(Finder::new(query.pattern.as_bytes())))
+        }
+        PickaxeKind::Regex => Matcher::Regex(Regex::new(query.pattern.to_string())),
+    };
+    let mut revwalk = repo.revwalk().map_err(error)?;
+    revwalk.set_sorting(Sort::TIME).map_err(error)?;
+    revwalk.push(query.start).map_err(error)?;
+
+    let mut matches = vec![];
+    let mut searched = 0;
+    for id in revwalk {
+        if cancelled.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        let id = id.map_err(error)?;
+        let commit = repo.find_commit(id).map_err(error)?;
+        let paths = match &matcher {
+            Matcher::Occurrences(finder) => changed_occurrences(repo, &commit, finder),
+            Matcher::Regex(regex) => changed_lines(repo, &commit, regex),
+        }
+        .map_err(|e| GitBrowserError::git(ErrorKind::Pickaxe, e).with_oid(id))?;
+        searched += 1;
+        if !paths.is_empty() {
+            matches.push(PickaxeMatch {
+                id,
+                summary: String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default())
+                    .to_string(),
+                time: commit.time().seconds(),
+                paths,
+            });
+        }
+        if !matches.is_empty() || searched % PROGRESS_INTERVAL == 0 {
+            report(std::mem::take(&mut matches), searched, false);
+        }
+    }
+    report(matches, searched, true);
+    Ok(())
+}
+
+/// The changes made by a commit to its first parent. Merges are skipped,
+/// as `git log` does without `-m`.
+fn commit_diff<'repo>(
+    repo: &'repo Repository,
+    commit: &Commit,
+    paths: &[String],
+) -> Result<Option<git2::Diff<'repo>>, git2::Error> {
+    if commit.parent_count() > 1 {
+        return Ok(None);
+    }
+    let parent_tree = match commit.parent_count() {
+        0 => None,
+        _ => Some(commit.parent(0)?.tree()?),
+    };
+    let mut options = DiffOptions::new();
+    for path in paths {
+        options.pathspec(path);
+    }
+    if !paths.is_empty() {
+        options.disable_pathspec_match(true);
+    }
+    repo.diff_tree_to_tree(
+        parent_tree.as_ref(),
+        Some(&commit.tree()?),
+        Some(&mut options),
+    )
+    .map(Some)
+}
+
+/// The files where the commit changed how many times the needle appears.
+/// Unlike `git log -S`, binary files aren't searched, as they can be large
+/// and matches in them are rarely what is being looked for.
+fn changed_occurrences(
+    repo: &Repository,
+    commit: &Commit,
+    needle: &Finder,
+) -> Result<Vec<String>, git2::Error> {
+    let diff = match commit_diff(repo, commit, &[])? {
+        Some(diff) => diff,
+        None => return Ok(vec![]),
+    };
+    let odb = repo.odb()?;
+    // Missing on one side, or a submodule rather than a file
+    let is_blob = |file: &DiffFile| !file.id().is_zero() && file.mode() != FileMode::Commit;
+    let big = |file: &DiffFile| -> Result<bool, git2::Error> {
+        Ok(is_blob(file) && odb.read_header(file.id())?.0 > BIG_FILE_THRESHOLD)
+    };
+    let blob = |file: DiffFile| -> Result<Option<Blob>, git2::Error> {
+        if !is_blob(&file) {
+            return Ok(None);
+        }
+        repo.find_blob(file.id()).map(Some)
+    };
+    let count = |blob: &Option<Blob>| {
+        blob.as_ref()
+            .map_or(0, |blob| needle.find_iter(blob.content()).count())
+    };
+    let mut paths = vec![];
+    for delta in diff.deltas() {
+        if big(&delta.old_file())? || big(&delta.new_file())? {
+            continue;
+        }
+        let old = blob(delta.old_file())?;
+        let new = blob(delta.new_file())?;
+        if [&old, &new]
+            .iter()
+            .any(|blob| blob.as_ref().is_some_and(|blob| blob.is_binary()))
+        {
+            continue;
+        }
+        // Most files don't contain the needle at all, which is quicker to
+        // rule out than to count
+        let found = |blob: &Option<Blob>| {
+            blob.as_ref()
+                .is_some_and(|blob| needle.find(blob.content()).is_some())
+        };
+        if !found(&old) && !found(&new) {
+            continue;
+        }
+        if count(&old) != count(&new) {
+            paths.extend(delta_path(&delta));
+        }
+    }
+    Ok(paths)
+}
+
+/// The files where the commit added or removed a line matching `regex`.
+fn changed_lines(
+    repo: &Repository,
+    commit: &Commit,
+    regex: &Regex,
+) -> Result<Vec<String>, git2::Error> {
+    let diff = match commit_diff(repo, commit, &[])? {
+        Some(diff) => diff,
+        None => return Ok(vec![]),
+    };
+    let mut paths: Vec<String> = vec![];
+    diff.foreach(
+        &mut |_delta, _progress| true,
+        None,
+        None,
+        Some(&mut |delta, _hunk, line| {
+            if matches!(line.origin(), '+' | '-')
+                && regex.is_match(&String::from_utf8_lossy(line.content()))
+            {
+                if let Some(path) = delta_path(&delta) {
+                    if paths.last() != Some(&path) {
+                        paths.push(path);
+                    }
+                }
+            }
+            true
+        }),
+    )?;
+    Ok(paths)
+}
+
+fn delta_path(delta: &git2::DiffDelta) -> Option<String> {
+    delta
+        .new_file()
+        .path()
+        .or(delta.old_file().path())
+        .map(|path| path.to_string_lossy().to_string())
+}
+
+/// A row of the results, which is either a commit or one of the files it
+/// changed.
+#[derive(Clone, Copy)]
+struct PickaxeRow {
+    commit: usize,
+    path: Option<usize>,
+}
+
+/// What the selected row of the results opens.
+pub enum PickaxeSelection<'repo> {
+    /// The patch of a commit, limited to the files that matched.
+    Diff(String, Vec<u8>),
+    /// A file as the commit left it, or as it was before if the commit
+    /// deleted it.
+    Blob(Commit<'repo>, String, Object<'repo>),
+}
+
+/// Lists the commits found by a search as they are found.
+pub struct PickaxePage<'repo> {
+    repo: &'repo Repository,
+    query: PickaxeQuery,
+    matches: Vec<PickaxeMatch>,
+    rows: Vec<PickaxeRow>,
+    searched: usize,
+    finished: bool,
+    failed: bool,
+    selected_index: usize,
+    mailbox: Mailbox,
+    // Stops the search when the page is closed
+    _cancel: CancelToken,
+}
+
+impl<'repo> PickaxePage<'repo> {
+    pub fn new(
+        repo: &'repo Repository,
+        query: PickaxeQuery,
+        workers: &WorkerHandle,
+    ) -> PickaxePage<'repo> {
+        let cancel = CancelToken::new();
+        let mailbox = Mailbox::new();
+        workers.submit_to(Task::Pickaxe(query.clone()), Some(&cancel), &mailbox);
+        PickaxePage {
+            repo,
+            query,
+            matches: vec![],
+            rows: vec![],
+            searched: 0,
+            finished: false,
+            failed: false,
+            selected_index: 0,
+            mailbox,
+            _cancel: cancel,
+        }
+    }
+
+    /// Add the commits the search has found since last time, returning why
+    /// the search failed if it did.
+    pub fn receive_results(&mut self) -> Vec<GitBrowserError> {
+        let mut failures = vec![];
+        let results: Vec<TaskResult> = self.mailbox.results().collect();
+        for result in results {
+            match result {
+                TaskResult::Pickaxe {
+                    matches,
+                    searched,
+                    finished,
+                } => self.receive_matches(matches, searched, finished),
+                TaskResult::Failed(e) => {
+                    // The search stops at the first error
+                    self.failed = true;
+                    failures.push(e);
+                }
+                _ => {}
+            }
+        }
+        failures
+    }
+
+    fn receive_matches(&mut self, matches: Vec<PickaxeMatch>, searched: usize, finished: bool) {
+        for found in matches {
+            let commit = self.matches.len();
+            self.rows.push(PickaxeRow { commit, path: None });
+            self.rows
+                .extend((0..found.paths.len()).map(|path| PickaxeRow {
+                    commit,
+                    path: Some(path),
+                }));
+            self.matches.push(found);
+        }
+        self.searched = searched;
+        self.finished = finished;
+    }
+
+    /// How far the search has got.
+    pub fn progress(&self) -> String {
+        let commits = if self.matches.len() == 1 {
+            "commit"
+        } else {
+            "commits"
+        };
+        let state = if self.failed {
+            "failed"
+        } else if self.finished {
+            "done"
+        } else {
+            "searching"
+        };
+        format!(
+            "{} {} found in {} searched, {}",
+            self.matches.len(),
+            commits,
+            self.searched,
+            state
+        )
+    }
+
+    fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn selection(&self) -> Result<Option<PickaxeSelection<'repo>>, GitBrowserError> {
+        let row = match self.rows.get(self.selected_index) {
+            Some(row) => *row,
+            None => return Ok(None),
+        };
+        let found = &self.matches[row.commit];
+        let error = |e| GitBrowserError::git(ErrorKind::Pickaxe, e).with_oid(found.id);
+        let commit = self.repo.find_commit(found.id).map_err(error)?;
+        match row.path {
+            None => {
+                let patch = match commit_diff(self.repo, &commit, &found.paths).map_err(error)? {
+                    Some(diff) => patch_text(&diff).map_err(error)?,
+                    None => vec![],
+                };
+                let name = format!("{}.diff", &found.id.to_string()[..7]);
+                Ok(Some(PickaxeSelection::Diff(name, patch)))
+            }
+            Some(path) => {
+                let path = &found.paths[path];
+                let entry = match commit.tree().and_then(|tree| tree.get_path(path.as_ref())) {
+                    Ok(entry) => entry,
+                    // Deleted by the commit, so show what was removed
+                    Err(_) => commit
+                        .parent(0)
+                        .and_then(|parent| parent.tree())
+                        .and_then(|tree| tree.get_path(path.as_ref()))
+                        .map_err(|e| error(e).with_path(path))?,
+                };
+                let object = entry
+                    .to_object(self.repo)
+                    .map_err(|e| error(e).with_path(path))?;
+                Ok(Some(PickaxeSelection::Blob(
+                    commit,
+                    path.to_string(),
+                    object,
+                )))
+            }
+        }
+    }
+}
+
+impl<'repo> Drawable<'repo> for PickaxePage<'repo> {
+    fn draw(&self, f: &mut Frame, area: Rect, content_block: Block) -> Rect {
+        let viewport = content_block.inner(area);
+
+        let visible = viewport.height;
+        let (_page, _pages, page_start_index) =
+            pagination(self.len(), visible.into(), self.selected_index);
+
+        let list_items: Vec<ListItem> = self
+            .rows
+            .iter()
+            .enumerate()
+            .skip(page_start_index)
+            .take(visible.into())
+            .map(|(index, row)| {
+                let style = if index == self.selected_index {
+                    Style::default().fg(Color::Black).bg(Color::Cyan)
+                } else {
+                    Style::default().fg(Color::Gray)
+                };
+                let found = &self.matches[row.commit];
+                let line = match row.path {
+                    None => Line::from(vec![
+                        Span::styled(
+                            format!("{} ", &found.id.to_string()[..7]),
+                            Style::default()
+                                .fg(Color::Yellow)
+                                .add_modifier(Modifier::DIM),
+                        ),
+                        Span::styled(
+                            format!("{:16} ", format_time(found.time)),
+                            Style::default().add_modifier(Modifier::DIM),
+                        ),
+                        Span::styled(&found.summary, style),
+                    ]),
+                    Some(path) => Line::from(vec![
+                        Span::raw("        "),
+                        Span::styled(&found.paths[path], style.fg(Color::Gray)),
+                    ]),
+                };
+                ListItem::new(line)
+            })
+            .collect();
+        let content = List::new(list_items).block(content_block);
+        f.render_widget(content, area);
+        viewport
+    }
+
+    fn title(&self) -> String {
+        format!(
+            "{}{} from {}",
+            self.query.kind.flag(),
+            self.query.pattern,
+            &self.query.start.to_string()[..7]
+        )
+    }
+}
+
+impl<'repo> Navigable<'repo> for PickaxePage<'repo> {
+    fn home(&mut self, _page_size: u16) {
+        self.selected_index = 0;
+    }
+
+    fn end(&mut self, _page_size: u16) {
+        self.selected_index = self.len().saturating_sub(1);
+    }
+
+    fn pagedown(&mut self, page_size: u16) {
+        let h: usize = page_size.into();
+        let index = self.selected_index.saturating_add(h);
+        self.selected_index = index.min(self.len().saturating_sub(1));
+    }
+
+    fn pageup(&mut self, page_size: u16) {
+        let h: usize = page_size.into();
+        self.selected_index = self.selected_index.saturating_sub(h);
+    }
+
+    fn next_selection(&mut self) {
+        if self.selected_index + 1 < self.len() {
+            self.selected_index += 1;
+        } else {
+            self.selected_index = 0;
+        }
+    }
+
+    fn previous_selection(&mut self) {
+        if self.selected_index > 0 {
+            self.selected_index -= 1;
+        } else {
+            self.selected_index = self.len().saturating_sub(1);
+        }
+    }
+
+    fn select(&self) -> Result<Option<(Object<'repo>, String)>, GitBrowserError> {
+        // Commits open their patch rather than their tree, so the app uses
+        // `selection`
+        Ok(None)
+    }
+
+    fn selected_item(&self) -> String {
+        self.rows
+            .get(self.selected_index)
+            .map(|row| {
+                let found = &self.matches[row.commit];
+                match row.path {
+                    None => found.id.to_string(),
+                    Some(path) => found.paths[path].to_string(),
+                }
+            })
+            .unwrap_or_default()
+    }
+
+    fn next_tick(&mut self, _block: bool) -> Result<(), GitBrowserError> {
+        Ok(())
+    }
+}
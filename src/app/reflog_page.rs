@@ -0,0 +1,211 @@
+use git2::{Object, Oid, Repository};
+
+use ratatui::{
+    layout::Rect,
+    prelude::Modifier,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem},
+    Frame,
+};
+
+use crate::app::pagination::pagination;
+use crate::app::signature::format_time;
+use crate::errors::{ErrorKind, GitBrowserError};
+use crate::traits::{Drawable, Navigable};
+
+/// One update of a reference, newest first.
+struct ReflogEntry {
+    old_id: Oid,
+    new_id: Oid,
+    message: String,
+    /// When the reference was updated, in seconds since the epoch.
+    time: i64,
+}
+
+/// Lists the updates recorded in the reflog of a reference.
+pub struct ReflogPage<'repo> {
+    repo: &'repo Repository,
+    reference: String,
+    entries: Vec<ReflogEntry>,
+    selected_index: usize,
+}
+
+impl<'repo> ReflogPage<'repo> {
+    pub fn new(
+        repo: &'repo Repository,
+        reference: String,
+    ) -> Result<ReflogPage<'repo>, GitBrowserError> {
+        let mut page = ReflogPage {
+            repo,
+            reference,
+            entries: vec![],
+            selected_index: 0,
+        };
+        page.reload()?;
+        Ok(page)
+    }
+
+    /// Read the reflog again, keeping the same entry selected. New entries
+    /// are added at the top, so the selection is kept from the bottom.
+    pub fn reload(&mut self) -> Result<(), GitBrowserError> {
+        let from_bottom = self.len().saturating_sub(self.selected_index);
+        let reflog = self
+            .repo
+            .reflog(&self.reference)
+            .map_err(|e| GitBrowserError::git(ErrorKind::Reflog, e).with_path(&self.reference))?;
+        self.entries = reflog
+            .iter()
+            .map(|entry| ReflogEntry {
+                old_id: entry.id_old(),
+                new_id: entry.id_new(),
+                message: entry.message().unwrap_or_default().to_string(),
+                time: entry.committer().when().seconds(),
+            })
+            .collect();
+        self.selected_index = match from_bottom {
+            0 => 0,
+            from_bottom => self.len().saturating_sub(from_bottom),
+        };
+        Ok(())
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// How git refers to an entry, e.g. `main@{2}`.
+    fn entry_name(&self, index: usize) -> String {
+        let short = self
+            .reference
+            .strip_prefix("refs/heads/")
+            .unwrap_or(&self.reference);
+        format!("{}@{{{}}}", short, index)
+    }
+}
+
+impl<'repo> Drawable<'repo> for ReflogPage<'repo> {
+    fn draw(&self, f: &mut Frame, area: Rect, content_block: Block) -> Rect {
+        let viewport = content_block.inner(area);
+
+        let visible = viewport.height;
+        let (_page, _pages, page_start_index) =
+            pagination(self.len(), visible.into(), self.selected_index);
+        let name_width = self.entry_name(self.len()).len() + 1;
+
+        let list_items: Vec<ListItem> = self
+            .entries
+            .iter()
+            .enumerate()
+            .skip(page_start_index)
+            .take(visible.into())
+            .map(|(index, entry)| {
+                let style = if index == self.selected_index {
+                    Style::default().fg(Color::Black).bg(Color::Cyan)
+                } else {
+                    Style::default().fg(Color::Gray)
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{:name_width$}", self.entry_name(index)),
+                        Style::default().add_modifier(Modifier::DIM),
+                    ),
+                    Span::styled(
+                        format!(
+                            "{}..{} ",
+                            &entry.old_id.to_string()[..7],
+                            &entry.new_id.to_string()[..7]
+                        ),
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::DIM),
+                    ),
+                    Span::styled(
+                        format!("{:16} ", format_time(entry.time)),
+                        Style::default().add_modifier(Modifier::DIM),
+                    ),
+                    Span::styled(&entry.message, style),
+                ]))
+            })
+            .collect();
+        let content = List::new(list_items).block(content_block);
+        f.render_widget(content, area);
+        viewport
+    }
+
+    fn title(&self) -> String {
+        self.reference.to_string()
+    }
+}
+
+impl<'repo> Navigable<'repo> for ReflogPage<'repo> {
+    fn home(&mut self, _page_size: u16) {
+        self.selected_index = 0;
+    }
+
+    fn end(&mut self, _page_size: u16) {
+        self.selected_index = self.len().saturating_sub(1);
+    }
+
+    fn pagedown(&mut self, page_size: u16) {
+        let h: usize = page_size.into();
+        let index = self.selected_index.saturating_add(h);
+        self.selected_index = index.min(self.len().saturating_sub(1));
+    }
+
+    fn pageup(&mut self, page_size: u16) {
+        let h: usize = page_size.into();
+        self.selected_index = self.selected_index.saturating_sub(h);
+    }
+
+    fn next_selection(&mut self) {
+        if self.selected_index + 1 < self.len() {
+            self.selected_index += 1;
+        } else {
+            self.selected_index = 0;
+        }
+    }
+
+    fn previous_selection(&mut self) {
+        if self.selected_index > 0 {
+            self.selected_index -= 1;
+        } else {
+            self.selected_index = self.len().saturating_sub(1);
+        }
+    }
+
+    fn select(&self) -> Result<Option<(Object<'repo>, String)>, GitBrowserError> {
+        let entry = match self.entries.get(self.selected_index) {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        let name = self.entry_name(self.selected_index);
+        if entry.new_id.is_zero() {
+            return Err(GitBrowserError::message(
+                ErrorKind::Reflog,
+                "The reference was deleted by this update",
+            )
+            .with_path(name));
+        }
+        // Commits that are only reachable from the reflog may have been
+        // pruned since
+        match self.repo.find_object(entry.new_id, None) {
+            Ok(object) => Ok(Some((object, "".to_string()))),
+            Err(e) => Err(GitBrowserError::git(ErrorKind::Reflog, e)
+                .with_oid(entry.new_id)
+                .with_path(name)),
+        }
+    }
+
+    fn selected_item(&self) -> String {
+        if self.entries.is_empty() {
+            "".to_string()
+        } else {
+            self.entry_name(self.selected_index)
+        }
+    }
+
+    fn next_tick(&mut self, _block: bool) -> Result<(), GitBrowserError> {
+        Ok(())
+    }
+}
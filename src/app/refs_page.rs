@@ -1,4 +1,6 @@
-use git2::{Object, Repository};
+use std::collections::HashMap;
+
+use git2::{Object, ObjectType, Oid, Repository};
 use ratatui::{
     layout::Rect,
     prelude::Modifier,
@@ -9,36 +11,163 @@ use ratatui::{
 };
 
 use crate::app::pagination::pagination;
-use crate::errors::GitBrowserError;
+use crate::app::workdir_page::PseudoRevision;
+use crate::app::worker::{Task, WorkerHandle};
+use crate::errors::{ErrorKind, GitBrowserError};
 use crate::traits::{Drawable, Navigable};
 
+const HEAD: &str = "HEAD";
+
 pub struct RefsPage<'repo> {
     repo: &'repo Repository,
+    refs: Vec<RefEntry>,
+    workers: WorkerHandle,
     selected_index: usize,
 }
 
+/// A reference name along with the object it ultimately points to.
+pub struct RefEntry {
+    pub name: String,
+    pub target: Option<Oid>,
+}
+
+impl RefEntry {
+    pub fn kind(&self) -> &'static str {
+        if let Some(revision) = self.pseudo_revision() {
+            match revision {
+                PseudoRevision::Index => "index",
+                PseudoRevision::Worktree => "worktree",
+            }
+        } else if self.name == HEAD {
+            "head"
+        } else if self.name.starts_with("refs/heads/") {
+            "branch"
+        } else if self.name.starts_with("refs/remotes/") {
+            "remote"
+        } else if self.name.starts_with("refs/tags/") {
+            "tag"
+        } else if self.name.starts_with("refs/notes/") {
+            "notes"
+        } else if self.name == "refs/stash" {
+            "stash"
+        } else {
+            "ref"
+        }
+    }
+
+    pub fn pseudo_revision(&self) -> Option<PseudoRevision> {
+        PseudoRevision::from_name(&self.name)
+    }
+
+    /// How the reference is shown next to the commit it points to in the
+    /// log, like `git log --decorate`.
+    fn decoration(&self) -> Option<String> {
+        match self.kind() {
+            "head" => Some(self.name.to_string()),
+            "branch" => Some(self.name["refs/heads/".len()..].to_string()),
+            "remote" => Some(self.name["refs/remotes/".len()..].to_string()),
+            "tag" => Some(format!("tag: {}", &self.name["refs/tags/".len()..])),
+            _ => None,
+        }
+    }
+}
+
 impl<'repo> RefsPage<'repo> {
-    pub fn new(repo: &'repo Repository) -> RefsPage<'repo> {
+    pub fn new(repo: &'repo Repository, workers: &WorkerHandle) -> RefsPage<'repo> {
+        workers.submit(Task::LoadRefs, None);
         RefsPage {
             repo,
+            refs: vec![],
+            workers: workers.clone(),
             selected_index: 0,
         }
     }
 
+    /// Reload the list of references from the repository in the
+    /// background.
+    pub fn refresh(&mut self) {
+        self.workers.submit(Task::LoadRefs, None);
+    }
+
+    /// Replace the list of references, keeping the same reference selected
+    /// if it still exists.
+    pub fn receive_refs(&mut self, refs: Vec<RefEntry>) {
+        let selected = self.selected_item();
+        self.refs = refs;
+        self.selected_index = match self
+            .refs
+            .binary_search_by(|entry| entry.name.cmp(&selected))
+        {
+            Ok(index) => index,
+            Err(_) => self.selected_index.min(self.len().saturating_sub(1)),
+        };
+    }
+
     fn len(&self) -> usize {
-        self.repo.references().map(|refs| refs.count()).unwrap_or(0)
+        self.refs.len()
+    }
+
+    fn items(&self) -> &[RefEntry] {
+        &self.refs
+    }
+
+    /// The names of the branches and tags pointing to each commit.
+    pub fn decorations(&self) -> HashMap<Oid, Vec<String>> {
+        let mut decorations: HashMap<Oid, Vec<String>> = HashMap::new();
+        for entry in self.items() {
+            if let (Some(target), Some(decoration)) = (entry.target, entry.decoration()) {
+                decorations.entry(target).or_default().push(decoration);
+            }
+        }
+        decorations
     }
 
-    fn items(&self) -> Vec<String> {
-        let mut refs = match self.repo.references() {
-            Ok(r) => r,
-            Err(_e) => return vec![],
+    /// The selected entry if it is the index or working tree rather than a
+    /// reference.
+    pub fn selected_pseudo_revision(&self) -> Option<PseudoRevision> {
+        self.items()
+            .get(self.selected_index)
+            .and_then(|entry| entry.pseudo_revision())
+    }
+}
+
+/// The references of the repository, with HEAD and the index and working
+/// tree. References that don't point to an object are listed without a
+/// target.
+pub fn load_refs(repo: &Repository) -> Result<Vec<RefEntry>, GitBrowserError> {
+    let error = |e| GitBrowserError::git(ErrorKind::Reference, e);
+    let mut entries = vec![];
+    for reference in repo.references().map_err(error)? {
+        let reference = reference.map_err(error)?;
+        // Names that aren't UTF-8 can't be looked up again by name
+        let name = match reference.name() {
+            Some(name) => name.to_string(),
+            None => continue,
         };
-        return refs
-            .names()
-            .map(|refname| refname.expect("Couldn't get refname").to_string())
-            .collect();
+        let target = reference
+            .peel(ObjectType::Any)
+            .ok()
+            .map(|object| object.id());
+        entries.push(RefEntry { name, target });
     }
+    if let Ok(head) = repo.find_reference(HEAD) {
+        entries.push(RefEntry {
+            name: HEAD.to_string(),
+            target: head.peel(ObjectType::Any).ok().map(|object| object.id()),
+        });
+    }
+    if !repo.is_bare() {
+        entries.extend(
+            [PseudoRevision::Index, PseudoRevision::Worktree].map(|revision| RefEntry {
+                name: revision.as_str().to_string(),
+                target: None,
+            }),
+        );
+    }
+    // HEAD and the upper case pseudo-revisions sort before anything under
+    // `refs/`
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
 }
 
 impl<'repo> Drawable<'repo> for RefsPage<'repo> {
@@ -65,12 +194,23 @@ impl<'repo> Drawable<'repo> for RefsPage<'repo> {
             } else {
                 Style::default().fg(Color::Gray)
             };
+            let target = match (item.target, item.pseudo_revision()) {
+                (Some(oid), _) => oid.to_string()[..7].to_string(),
+                (None, Some(_)) => "       ".to_string(),
+                (None, None) => "???????".to_string(),
+            };
             let line = Line::from(vec![
                 Span::styled(
-                    format!("{:10}", "ref"),
+                    format!("{:10}", item.kind()),
                     Style::default().add_modifier(Modifier::DIM),
                 ),
-                Span::styled(item, style),
+                Span::styled(
+                    format!("{} ", target),
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::DIM),
+                ),
+                Span::styled(&item.name, style),
             ]);
             list_items.push(ListItem::new(line));
         }
@@ -97,7 +237,7 @@ impl<'repo> Navigable<'repo> for RefsPage<'repo> {
     }
 
     fn end(&mut self, _page_size: u16) {
-        self.selected_index = if self.len() > 0 { self.len() - 1 } else { 0 };
+        self.selected_index = self.len().saturating_sub(1);
     }
 
     fn pagedown(&mut self, page_size: u16) {
@@ -112,7 +252,7 @@ impl<'repo> Navigable<'repo> for RefsPage<'repo> {
     }
 
     fn next_selection(&mut self) {
-        if self.selected_index < self.len() - 1 {
+        if self.selected_index + 1 < self.len() {
             self.selected_index += 1;
         } else {
             self.selected_index = 0;
@@ -123,27 +263,38 @@ impl<'repo> Navigable<'repo> for RefsPage<'repo> {
         if self.selected_index > 0 {
             self.selected_index -= 1;
         } else {
-            self.selected_index = self.len() - 1;
+            self.selected_index = self.len().saturating_sub(1);
         }
     }
 
-    fn select(&self) -> Option<(Object<'repo>, String)> {
-        let selected_ref = &self.items()[self.selected_index];
-        match self.repo.revparse_single(selected_ref) {
-            Ok(object) => Some((object, "".to_string())),
-            Err(e) => {
-                panic!("Couldn't parse ref {}", e);
+    fn select(&self) -> Result<Option<(Object<'repo>, String)>, GitBrowserError> {
+        let selected_ref = match self.items().get(self.selected_index) {
+            Some(selected_ref) => selected_ref,
+            None => return Ok(None),
+        };
+        let target = match selected_ref.target {
+            Some(target) => target,
+            None => {
+                return Err(GitBrowserError::message(
+                    ErrorKind::Reference,
+                    "Reference does not point to an object",
+                )
+                .with_path(&selected_ref.name))
             }
+        };
+        match self.repo.find_object(target, None) {
+            Ok(object) => Ok(Some((object, "".to_string()))),
+            Err(e) => Err(GitBrowserError::git(ErrorKind::Reference, e)
+                .with_oid(target)
+                .with_path(&selected_ref.name)),
         }
     }
 
     fn selected_item(&self) -> String {
-        let items = &self.items();
-        if items.is_empty() {
-            "".to_string()
-        } else {
-            items[self.selected_index].to_string()
-        }
+        self.items()
+            .get(self.selected_index)
+            .map(|entry| entry.name.to_string())
+            .unwrap_or_default()
     }
 
     fn next_tick(&mut self, _block: bool) -> Result<(), GitBrowserError> {
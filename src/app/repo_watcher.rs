@@ -0,0 +1,92 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+
+use git2::Repository;
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::app::git_dir::common_dir;
+use crate::errors::{ErrorKind, GitBrowserError};
+
+/// Watches the git directory for changes to `HEAD` and the index, and the
+/// common directory for changes to `packed-refs` and anything under `refs/`. They
+/// are the same directory unless the repository is a linked worktree.
+pub struct RepoWatcher {
+    // Kept alive for as long as we want to receive events
+    _watcher: RecommendedWatcher,
+    receiver: Receiver<notify::Result<Event>>,
+    git_dir: PathBuf,
+    common_dir: PathBuf,
+}
+
+impl RepoWatcher {
+    pub fn new(repo: &Repository) -> Result<RepoWatcher, GitBrowserError> {
+        // Events are reported relative to the watched path, so watch the
+        // canonical path to be able to match them reliably
+        let git_dir = &repo
+            .path()
+            .canonicalize()
+            .unwrap_or_else(|_| repo.path().to_path_buf());
+        let common_dir = &common_dir(repo);
+        let (sender, receiver) = channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            // The receiver only goes away when the app exits
+            let _ = sender.send(event);
+        })
+        .map_err(|e| watch_error(e, git_dir))?;
+
+        watcher
+            .watch(git_dir, RecursiveMode::NonRecursive)
+            .map_err(|e| watch_error(e, git_dir))?;
+        if common_dir != git_dir {
+            watcher
+                .watch(common_dir, RecursiveMode::NonRecursive)
+                .map_err(|e| watch_error(e, common_dir))?;
+        }
+        let refs_dir = common_dir.join("refs");
+        watcher
+            .watch(&refs_dir, RecursiveMode::Recursive)
+            .map_err(|e| watch_error(e, &refs_dir))?;
+
+        Ok(RepoWatcher {
+            _watcher: watcher,
+            receiver,
+            git_dir: git_dir.to_path_buf(),
+            common_dir: common_dir.to_path_buf(),
+        })
+    }
+
+    /// Drain all pending events, returning true if any of them touched
+    /// the refs or the index.
+    pub fn changed(&self) -> Result<bool, GitBrowserError> {
+        let mut changed = false;
+        for event in self.receiver.try_iter() {
+            let event = event.map_err(|e| watch_error(e, &self.git_dir))?;
+            if matches!(event.kind, EventKind::Access(_)) {
+                continue;
+            }
+            changed |= event.paths.iter().any(|path| self.is_watched_path(path));
+        }
+        Ok(changed)
+    }
+
+    fn is_watched_path(&self, path: &Path) -> bool {
+        // Lock files are renamed into place once git is done writing
+        if path.extension().is_some_and(|ext| ext == "lock") {
+            return false;
+        }
+        if let Ok(relative) = path.strip_prefix(&self.git_dir) {
+            if relative == Path::new("HEAD") || relative == Path::new("index") {
+                return true;
+            }
+        }
+        match path.strip_prefix(&self.common_dir) {
+            Ok(relative) => relative == Path::new("packed-refs") || relative.starts_with("refs"),
+            Err(_) => false,
+        }
+    }
+}
+
+fn watch_error(error: notify::Error, path: &Path) -> GitBrowserError {
+    GitBrowserError::message(ErrorKind::Watch, error.to_string()).with_path(path.to_string_lossy())
+}
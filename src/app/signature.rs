@@ -0,0 +1,187 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use chrono::{DateTime, Local};
+
+use git2::{Mailmap, ObjectType, Oid, Repository, Signature};
+
+use crate::errors::{ErrorKind, GitBrowserError};
+
+const MAILMAP_FILE: &str = ".mailmap";
+
+/// Where a mailmap is loaded from, to tell when it needs loading again.
+#[derive(Default, Eq, PartialEq)]
+struct MailmapSources {
+    /// The `.mailmap` at HEAD and the blob named by `mailmap.blob`.
+    blobs: Vec<Oid>,
+    /// The file named by `mailmap.file` and when it was last modified.
+    file: Option<(PathBuf, Option<SystemTime>)>,
+}
+
+impl MailmapSources {
+    fn find(repo: &Repository) -> Result<MailmapSources, GitBrowserError> {
+        let config = repo
+            .config()
+            .map_err(|e| GitBrowserError::git(ErrorKind::Mailmap, e))?;
+        let mut revisions = vec![format!("HEAD:{}", MAILMAP_FILE)];
+        if let Ok(revision) = config.get_string("mailmap.blob") {
+            revisions.push(revision);
+        }
+        // Like git, ignore blobs that don't exist
+        let blobs = revisions
+            .iter()
+            .filter_map(|revision| repo.revparse_single(revision).ok())
+            .filter(|object| object.kind() == Some(ObjectType::Blob))
+            .map(|object| object.id())
+            .collect();
+        let file = config.get_path("mailmap.file").ok().map(|path| {
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+            (path, modified)
+        });
+        Ok(MailmapSources { blobs, file })
+    }
+
+    /// The concatenated content of the sources, with later entries taking
+    /// precedence.
+    fn read(&self, repo: &Repository) -> Result<String, GitBrowserError> {
+        let mut content = String::new();
+        for id in &self.blobs {
+            let blob = repo.find_blob(*id).map_err(|e| {
+                GitBrowserError::git(ErrorKind::Mailmap, e)
+                    .with_oid(*id)
+                    .with_path(MAILMAP_FILE)
+            })?;
+            content.push_str(&String::from_utf8_lossy(blob.content()));
+            content.push('\n');
+        }
+        if let Some((path, _)) = &self.file {
+            let file = fs::read(path).map_err(|e| {
+                GitBrowserError::io(ErrorKind::Mailmap, e).with_path(path.to_string_lossy())
+            })?;
+            content.push_str(&String::from_utf8_lossy(&file));
+        }
+        Ok(content)
+    }
+}
+
+/// Formats the people in commits, using the repository's mailmap so that
+/// each person appears under one name however they have committed.
+pub struct SignatureFormatter {
+    mailmap: Mailmap,
+    sources: MailmapSources,
+}
+
+impl SignatureFormatter {
+    /// Load the `.mailmap` at HEAD, the blob named by `mailmap.blob` and
+    /// the file named by `mailmap.file`, with later ones taking precedence.
+    pub fn load(repo: &Repository) -> Result<SignatureFormatter, GitBrowserError> {
+        let mut formatter = SignatureFormatter::empty();
+        formatter.reload(repo)?;
+        Ok(formatter)
+    }
+
+    /// Load the mailmap again if any of where it comes from has changed.
+    pub fn reload(&mut self, repo: &Repository) -> Result<(), GitBrowserError> {
+        let sources = MailmapSources::find(repo)?;
+        if sources == self.sources {
+            return Ok(());
+        }
+        let content = sources.read(repo)?;
+        let mut mailmap =
+            Mailmap::new().map_err(|e| GitBrowserError::git(ErrorKind::Mailmap, e))?;
+        add_entries(&mut mailmap, &content)
+            .map_err(|e| GitBrowserError::git(ErrorKind::Mailmap, e))?;
+        self.mailmap = mailmap;
+        self.sources = sources;
+        Ok(())
+    }
+
+    /// A formatter that shows names as they were recorded.
+    pub fn empty() -> SignatureFormatter {
+        SignatureFormatter {
+            // Only fails when out of memory
+            mailmap: Mailmap::new().expect("Unable to create mailmap"),
+            sources: MailmapSources::default(),
+        }
+    }
+
+    /// The canonical name and email of a person.
+    pub fn name_and_email(&self, signature: &Signature) -> (String, String) {
+        let resolved = self.mailmap.resolve_signature(signature).ok();
+        let signature = resolved.as_ref().unwrap_or(signature);
+        (
+            String::from_utf8_lossy(signature.name_bytes()).to_string(),
+            String::from_utf8_lossy(signature.email_bytes()).to_string(),
+        )
+    }
+
+    /// The canonical name of a person.
+    pub fn name(&self, signature: &Signature) -> String {
+        self.name_and_email(signature).0
+    }
+
+    /// `Name <email> date`, like the headers of `git log`.
+    pub fn format(&self, signature: &Signature) -> String {
+        let (name, email) = self.name_and_email(signature);
+        format!(
+            "{} <{}> {}",
+            name,
+            email,
+            format_time(signature.when().seconds())
+        )
+    }
+}
+
+/// Add the entries of a mailmap file, which are lines of any of:
+///
+/// ```text
+/// Proper Name <commit@email>
+/// <proper@email> <commit@email>
+/// Proper Name <proper@email> <commit@email>
+/// Proper Name <proper@email> Commit Name <commit@email>
+/// ```
+///
+/// Only lines starting with `#` are comments, as names like `C#` are
+/// allowed by git, which is why `Mailmap::from_buffer` isn't used.
+fn add_entries(mailmap: &mut Mailmap, content: &str) -> Result<(), git2::Error> {
+    for line in content.lines().filter(|line| !line.starts_with('#')) {
+        let mut names = vec![];
+        let mut emails = vec![];
+        let mut rest = line;
+        // Anything after the second email is ignored
+        while emails.len() < 2 {
+            let (name, after) = match rest.split_once('<') {
+                Some(parts) => parts,
+                None => break,
+            };
+            let (email, after) = match after.split_once('>') {
+                Some(parts) => parts,
+                None => break,
+            };
+            names.push(Some(name.trim()).filter(|name| !name.is_empty()));
+            emails.push(email.trim());
+            rest = after;
+        }
+        match (names.as_slice(), emails.as_slice()) {
+            ([name], [email]) => mailmap.add_entry(*name, None, None, email)?,
+            ([name, replace_name], [real_email, replace_email]) => {
+                mailmap.add_entry(*name, Some(real_email), *replace_name, replace_email)?
+            }
+            // Not a valid entry, which git ignores
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Format a git timestamp, in seconds since the epoch, in local time.
+pub fn format_time(seconds: i64) -> String {
+    DateTime::from_timestamp(seconds, 0)
+        .map(|time| {
+            time.with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_default()
+}
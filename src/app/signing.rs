@@ -0,0 +1,734 @@
+use std::io::Write as _;
+use std::process::{Command, Stdio};
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+use git2::{Config, ErrorCode, Oid, Repository, Tag};
+
+use ratatui::{
+    prelude::Modifier,
+    style::{Color, Style},
+};
+
+use tempfile::NamedTempFile;
+
+use crate::errors::{ErrorKind, GitBrowserError};
+
+/// The first line of each kind of signature git can make.
+const SIGNATURE_HEADERS: [(&str, SigningMethod); 4] = [
+    ("-----BEGIN PGP SIGNATURE-----", SigningMethod::Gpg),
+    ("-----BEGIN PGP MESSAGE-----", SigningMethod::Gpg),
+    ("-----BEGIN SSH SIGNATURE-----", SigningMethod::Ssh),
+    ("-----BEGIN SIGNED MESSAGE-----", SigningMethod::X509),
+];
+
+/// The namespace git signs with, which `ssh-keygen -Y verify` checks.
+const SSH_NAMESPACE: &str = "git";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SigningMethod {
+    Gpg,
+    Ssh,
+    X509,
+}
+
+impl SigningMethod {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            SigningMethod::Gpg => "GPG",
+            SigningMethod::Ssh => "SSH",
+            SigningMethod::X509 => "X.509",
+        }
+    }
+
+    fn from_block(block: &str) -> Option<SigningMethod> {
+        SIGNATURE_HEADERS
+            .iter()
+            .find(|(header, _)| block.starts_with(header))
+            .map(|(_, method)| *method)
+    }
+}
+
+/// The outcome of checking a signature with `gpg` or `ssh-keygen`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Verification {
+    /// The signature is valid, made by the given signer. SSH signatures
+    /// made by keys that aren't in the allowed signers file have no signer.
+    Good(Option<String>),
+    /// The signature is valid but has expired, made by the given signer.
+    ExpiredSignature(String),
+    /// The signature is valid but the signer's key has expired.
+    ExpiredKey(String),
+    /// The signature is valid but the signer's key has been revoked.
+    RevokedKey(String),
+    Bad,
+    /// The key isn't in the keyring.
+    UnknownKey,
+    /// The program failed to check the signature, for the given reason.
+    Unverifiable(String),
+    /// The signature couldn't be checked, for the given reason.
+    Unavailable(String),
+}
+
+/// A signature on a commit or tag.
+pub struct SignatureInfo {
+    pub method: SigningMethod,
+    /// The OpenPGP fingerprint or key ID, or the SSH public key.
+    pub key_id: Option<String>,
+    /// The armored signature.
+    pub block: String,
+    pub signed_data: Vec<u8>,
+    pub verification: Option<Verification>,
+}
+
+pub enum SignatureStatus {
+    Unsigned,
+    Signed(SignatureInfo),
+}
+
+impl SignatureStatus {
+    fn from_parts(block: &[u8], signed_data: &[u8]) -> SignatureStatus {
+        let block = String::from_utf8_lossy(block).to_string();
+        let method = match SigningMethod::from_block(&block) {
+            Some(method) => method,
+            // Not something git would have made
+            None => return SignatureStatus::Unsigned,
+        };
+        let key_id = match method {
+            SigningMethod::Gpg => armor_body(&block).and_then(|body| openpgp_key_id(&body)),
+            SigningMethod::Ssh => armor_body(&block).and_then(|body| ssh_public_key(&body)),
+            SigningMethod::X509 => None,
+        };
+        SignatureStatus::Signed(SignatureInfo {
+            method,
+            key_id,
+            block,
+            signed_data: signed_data.to_vec(),
+            verification: None,
+        })
+    }
+
+    /// A short summary for the title bar.
+    pub fn badge(&self) -> (String, Style) {
+        let info = match self {
+            SignatureStatus::Unsigned => {
+                return (
+                    "unsigned".to_string(),
+                    Style::default().fg(Color::Gray).add_modifier(Modifier::DIM),
+                )
+            }
+            SignatureStatus::Signed(info) => info,
+        };
+        let key = info.short_key_id();
+        let method = info.method.as_str();
+        match &info.verification {
+            None => (
+                format!("{} {}", method, key),
+                Style::default().fg(Color::Cyan),
+            ),
+            Some(Verification::Good(Some(signer))) => (
+                format!("{} good signature from {}", method, signer),
+                Style::default().fg(Color::Green),
+            ),
+            Some(Verification::Good(None)) => (
+                format!("{} good signature {} with no principal", method, key),
+                Style::default().fg(Color::Green),
+            ),
+            Some(Verification::ExpiredSignature(signer)) => (
+                format!("{} expired signature from {}", method, signer),
+                Style::default().fg(Color::Yellow),
+            ),
+            Some(Verification::ExpiredKey(signer)) => (
+                format!("{} good signature from {} with expired key", method, signer),
+                Style::default().fg(Color::Yellow),
+            ),
+            Some(Verification::RevokedKey(signer)) => (
+                format!("{} good signature from {} with REVOKED key", method, signer),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ),
+            Some(Verification::Bad) => (
+                format!("{} BAD signature {}", method, key),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ),
+            Some(Verification::UnknownKey) => (
+                format!("{} unknown key {}", method, key),
+                Style::default().fg(Color::Yellow),
+            ),
+            Some(Verification::Unverifiable(_)) => (
+                format!("{} {} unverifiable", method, key),
+                Style::default().fg(Color::Yellow),
+            ),
+            Some(Verification::Unavailable(_)) => (
+                format!("{} {} unverified", method, key),
+                Style::default().fg(Color::Yellow),
+            ),
+        }
+    }
+}
+
+impl SignatureInfo {
+    /// Enough of the key to recognise it.
+    fn short_key_id(&self) -> String {
+        match (&self.key_id, self.method) {
+            // The long key ID is the end of the fingerprint
+            (Some(key_id), SigningMethod::Gpg) => {
+                key_id[key_id.len().saturating_sub(16)..].to_string()
+            }
+            // Just the key type
+            (Some(key_id), _) => key_id.split(' ').next().unwrap_or_default().to_string(),
+            (None, _) => "".to_string(),
+        }
+    }
+}
+
+pub fn commit_signature(repo: &Repository, id: Oid) -> Result<SignatureStatus, GitBrowserError> {
+    match repo.extract_signature(&id, None) {
+        Ok((block, signed_data)) => Ok(SignatureStatus::from_parts(&block, &signed_data)),
+        Err(e) if e.code() == ErrorCode::NotFound => Ok(SignatureStatus::Unsigned),
+        Err(e) => Err(GitBrowserError::git(ErrorKind::Signature, e).with_oid(id)),
+    }
+}
+
+/// Tag signatures are appended to the tag message, so they are split off
+/// the raw tag like git does rather than with `extract_signature`, which
+/// only handles commits.
+pub fn tag_signature(repo: &Repository, tag: &Tag) -> Result<SignatureStatus, GitBrowserError> {
+    let error = |e| GitBrowserError::git(ErrorKind::Signature, e).with_oid(tag.id());
+    let odb = repo.odb().map_err(error)?;
+    let object = odb.read(tag.id()).map_err(error)?;
+    let data = object.data();
+    Ok(match signature_start(data) {
+        Some(start) => SignatureStatus::from_parts(&data[start..], &data[..start]),
+        None => SignatureStatus::Unsigned,
+    })
+}
+
+/// Where the signature starts in a raw tag. Like git, this is the last line
+/// starting a signature block, as the message may quote others.
+fn signature_start(data: &[u8]) -> Option<usize> {
+    let mut start = 0;
+    let mut signature = None;
+    for line in data.split_inclusive(|&b| b == b'\n') {
+        if SIGNATURE_HEADERS
+            .iter()
+            .any(|(header, _)| line.starts_with(header.as_bytes()))
+        {
+            signature = Some(start);
+        }
+        start += line.len();
+    }
+    signature
+}
+
+/// The base64 encoded body of an armored block, without headers or the
+/// checksum.
+fn armor_body(block: &str) -> Option<Vec<u8>> {
+    let mut lines = block.lines().skip(1);
+    let mut body = String::new();
+    // OpenPGP armor may have headers, which end with a blank line
+    let has_headers = block.lines().nth(1)?.contains(": ");
+    if has_headers {
+        lines.by_ref().find(|line| line.trim().is_empty())?;
+    }
+    for line in lines {
+        let line = line.trim();
+        if line.starts_with("-----END") || line.starts_with('=') {
+            break;
+        }
+        body.push_str(line);
+    }
+    STANDARD.decode(body).ok()
+}
+
+/// The issuer fingerprint, or failing that the issuer key ID, of an
+/// OpenPGP signature packet.
+fn openpgp_key_id(packet: &[u8]) -> Option<String> {
+    let (&header, rest) = packet.split_first()?;
+    if header & 0x80 == 0 {
+        return None;
+    }
+    let body = if header & 0x40 != 0 {
+        // New format, where the length has variable size
+        if header & 0x3f != 2 {
+            return None;
+        }
+        match *rest.first()? {
+            0..=191 => rest.get(1..)?,
+            192..=223 => rest.get(2..)?,
+            255 => rest.get(5..)?,
+            _ => return None,
+        }
+    } else {
+        if (header >> 2) & 0x0f != 2 {
+            return None;
+        }
+        match header & 0x03 {
+            0 => rest.get(1..)?,
+            1 => rest.get(2..)?,
+            2 => rest.get(4..)?,
+            _ => rest,
+        }
+    };
+
+    match *body.first()? {
+        // The key ID is at a fixed position
+        3 => Some(hex(body.get(7..15)?)),
+        version @ (4 | 6) => {
+            let length_size = if version == 4 { 2 } else { 4 };
+            let mut position = 4;
+            let mut key_id = None;
+            // Hashed then unhashed subpackets
+            for _ in 0..2 {
+                let length = be_number(body.get(position..position + length_size)?);
+                position += length_size;
+                let subpackets = body.get(position..position + length)?;
+                position += length;
+                for (kind, data) in Subpackets(subpackets) {
+                    match kind {
+                        // Issuer fingerprint, after a version byte
+                        33 => return data.get(1..).map(hex),
+                        16 => key_id = Some(hex(data)),
+                        _ => {}
+                    }
+                }
+            }
+            key_id
+        }
+        _ => None,
+    }
+}
+
+/// Iterates over the type and data of OpenPGP signature subpackets.
+struct Subpackets<'a>(&'a [u8]);
+
+impl<'a> Iterator for Subpackets<'a> {
+    type Item = (u8, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let data = self.0;
+        let (length, offset) = match *data.first()? {
+            first @ 0..=191 => (usize::from(first), 1),
+            first @ 192..=254 => (
+                ((usize::from(first) - 192) << 8) + usize::from(*data.get(1)?) + 192,
+                2,
+            ),
+            255 => (be_number(data.get(1..5)?), 5),
+        };
+        let subpacket = data.get(offset..offset + length)?;
+        self.0 = &data[offset + length..];
+        let (&kind, data) = subpacket.split_first()?;
+        // The top bit marks the subpacket as critical
+        Some((kind & 0x7f, data))
+    }
+}
+
+/// The public key of an SSH signature, as it would appear in an allowed
+/// signers file.
+fn ssh_public_key(blob: &[u8]) -> Option<String> {
+    let rest = blob.strip_prefix(b"SSHSIG")?;
+    // Skip the version
+    let (public_key, _) = ssh_string(rest.get(4..)?)?;
+    let (key_type, _) = ssh_string(public_key)?;
+    Some(format!(
+        "{} {}",
+        String::from_utf8_lossy(key_type),
+        STANDARD.encode(public_key)
+    ))
+}
+
+/// Split a length prefixed string off the front of SSH wire format data.
+fn ssh_string(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let length = be_number(data.get(0..4)?);
+    let end = 4usize.checked_add(length)?;
+    Some((data.get(4..end)?, data.get(end..)?))
+}
+
+fn be_number(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .fold(0, |number, &byte| (number << 8) | usize::from(byte))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+/// Check a signature with the program git would use for it.
+pub fn verify(
+    config: &Config,
+    method: SigningMethod,
+    block: &str,
+    signed_data: &[u8],
+) -> Verification {
+    let result = match method {
+        SigningMethod::Gpg => {
+            let program = config
+                .get_string("gpg.openpgp.program")
+                .or_else(|_| config.get_string("gpg.program"))
+                .unwrap_or_else(|_| "gpg".to_string());
+            verify_gpg(&program, block, signed_data)
+        }
+        SigningMethod::X509 => {
+            let program = config
+                .get_string("gpg.x509.program")
+                .unwrap_or_else(|_| "gpgsm".to_string());
+            verify_gpg(&program, block, signed_data)
+        }
+        SigningMethod::Ssh => verify_ssh(config, block, signed_data),
+    };
+    result.unwrap_or_else(|e| {
+        let program = e.context().path.clone().unwrap_or_default();
+        Verification::Unavailable(format!("{}: {}", program, e.cause()))
+    })
+}
+
+fn verify_gpg(
+    program: &str,
+    block: &str,
+    signed_data: &[u8],
+) -> Result<Verification, GitBrowserError> {
+    let signature = signature_file(block)?;
+    let path = signature.path().to_string_lossy().to_string();
+    let (_, status) = run(
+        program,
+        &["--status-fd=1", "--verify", &path, "-"],
+        signed_data,
+    )?;
+    Ok(gpg_verification(&status).unwrap_or_else(|| {
+        Verification::Unavailable(format!("{} did not report a result", program))
+    }))
+}
+
+/// What the status lines printed by `gpg --status-fd` say of a signature.
+fn gpg_verification(status: &str) -> Option<Verification> {
+    let lines: Vec<Vec<&str>> = status
+        .lines()
+        .filter_map(|line| line.strip_prefix("[GNUPG:] "))
+        .map(|line| line.split(' ').collect())
+        .collect();
+    // A missing key is reported as an error first
+    if lines.iter().any(|fields| fields[0] == "NO_PUBKEY") {
+        return Some(Verification::UnknownKey);
+    }
+    for fields in &lines {
+        // The key ID comes before the signer
+        let signer = || fields.get(2..).unwrap_or_default().join(" ");
+        let verification = match fields[0] {
+            "GOODSIG" => Verification::Good(Some(signer())),
+            "EXPSIG" => Verification::ExpiredSignature(signer()),
+            "EXPKEYSIG" => Verification::ExpiredKey(signer()),
+            "REVKEYSIG" => Verification::RevokedKey(signer()),
+            "BADSIG" => Verification::Bad,
+            "ERRSIG" => Verification::Unverifiable(match fields.get(6) {
+                Some(&"4") => "unsupported algorithm".to_string(),
+                Some(&"9") => "missing key".to_string(),
+                Some(code) => format!("error code {}", code),
+                None => "unknown error".to_string(),
+            }),
+            _ => continue,
+        };
+        return Some(verification);
+    }
+    None
+}
+
+fn verify_ssh(
+    config: &Config,
+    block: &str,
+    signed_data: &[u8],
+) -> Result<Verification, GitBrowserError> {
+    let program = config
+        .get_string("gpg.ssh.program")
+        .unwrap_or_else(|_| "ssh-keygen".to_string());
+    let allowed_signers = match config.get_path("gpg.ssh.allowedSignersFile") {
+        Ok(path) => path.to_string_lossy().to_string(),
+        Err(_) => {
+            return Ok(Verification::Unavailable(
+                "gpg.ssh.allowedSignersFile is not set".to_string(),
+            ))
+        }
+    };
+    let signature = signature_file(block)?;
+    let path = signature.path().to_string_lossy().to_string();
+
+    let principal = match run(
+        &program,
+        &["-Y", "find-principals", "-f", &allowed_signers, "-s", &path],
+        &[],
+    ) {
+        Ok((true, principals)) => principals.lines().next().map(|p| p.to_string()),
+        _ => None,
+    };
+    let verified = match &principal {
+        Some(principal) => run(
+            &program,
+            &[
+                "-Y",
+                "verify",
+                "-f",
+                &allowed_signers,
+                "-I",
+                principal,
+                "-n",
+                SSH_NAMESPACE,
+                "-s",
+                &path,
+            ],
+            signed_data,
+        )?,
+        // Like git, still check that the signature matches the key it was
+        // made with
+        None => run(
+            &program,
+            &["-Y", "check-novalidate", "-n", SSH_NAMESPACE, "-s", &path],
+            signed_data,
+        )?,
+    };
+    Ok(match verified {
+        (true, _) => Verification::Good(principal),
+        (false, _) => Verification::Bad,
+    })
+}
+
+fn signature_file(block: &str) -> Result<NamedTempFile, GitBrowserError> {
+    let mut file =
+        NamedTempFile::new().map_err(|e| GitBrowserError::io(ErrorKind::TemporaryFile, e))?;
+    file.write_all(block.as_bytes())
+        .map_err(|e| GitBrowserError::io(ErrorKind::TemporaryFile, e))?;
+    Ok(file)
+}
+
+/// Run a program with some input, returning whether it succeeded and what
+/// it printed.
+fn run(program: &str, args: &[&str], input: &[u8]) -> Result<(bool, String), GitBrowserError> {
+    let error = |e| GitBrowserError::io(ErrorKind::Subprocess, e).with_path(program);
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(error)?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Programs may exit without reading everything, such as when they
+        // don't know the key
+        let _ = stdin.write_all(input);
+    }
+    let output = child.wait_with_output().map_err(error)?;
+    Ok((
+        output.status.success(),
+        String::from_utf8_lossy(&output.stdout).to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GPG_BLOCK: &str = "-----BEGIN PGP SIGNATURE-----
+
+iHUEABYIAB0WIQSBMpAjdXcg4upAe5IKqtcz5nQNkgUCatVBbQAKCRAKqtcz5nQN
+kl4rAP9Vq+LGQA9cSq6bCdRaNZm8qpPVIPN46CQD1iza3cIB3QD/egigGS6YQV94
+ezRESQjcnh1cE5kmsUG65RakfVqdGAo=
+=+YEf
+-----END PGP SIGNATURE-----
+";
+
+    const SSH_BLOCK: &str = "-----BEGIN SSH SIGNATURE-----
+U1NIU0lHAAAAAQAAADMAAAALc3NoLWVkMjU1MTkAAAAg1PK48mVScbTkGPowwf26LUo+ZD
+peXcfX/QP4s6IlFvcAAAADZ2l0AAAAAAAAAAZzaGE1MTIAAABTAAAAC3NzaC1lZDI1NTE5
+AAAAQET70oT1pK9tRYmbe2ku5XE1KJ5Ot1sv/EDLIdn8V50k+u+9DqdFFf8lpaWp6wzaUv
+grXAnQ684nk6WnmxL5bAw=
+-----END SSH SIGNATURE-----
+";
+
+    const GPG_FINGERPRINT: &str = "81329023757720E2EA407B920AAAD733E6740D92";
+
+    const SSH_KEY: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAINTyuPJlUnG05Bj6MMH9ui1KPmQ6Xl3H1/0D+LOiJRb3";
+
+    #[test]
+    fn openpgp_key_id_of_v4_signature() {
+        let packet = armor_body(GPG_BLOCK).unwrap();
+        assert_eq!(openpgp_key_id(&packet).as_deref(), Some(GPG_FINGERPRINT));
+    }
+
+    #[test]
+    fn openpgp_key_id_from_issuer_subpacket() {
+        let packet = armor_body(GPG_BLOCK).unwrap();
+        // Replace the hashed issuer fingerprint with an unknown subpacket
+        // to fall back on the unhashed issuer key ID
+        let mut packet = packet.clone();
+        let fingerprint = packet
+            .windows(2)
+            .position(|window| window == [0x16, 0x21])
+            .unwrap();
+        packet[fingerprint + 1] = 0x64;
+        assert_eq!(
+            openpgp_key_id(&packet).as_deref(),
+            Some(&GPG_FINGERPRINT[GPG_FINGERPRINT.len() - 16..])
+        );
+    }
+
+    #[test]
+    fn openpgp_key_id_of_truncated_signature() {
+        let packet = armor_body(GPG_BLOCK).unwrap();
+        // The hashed subpackets holding the fingerprint end 37 bytes in,
+        // after the two byte packet header, four byte signature header, two
+        // byte length and 29 bytes of subpackets
+        for end in 0..37 {
+            assert_eq!(openpgp_key_id(&packet[..end]), None, "{} bytes", end);
+        }
+        for end in 37..packet.len() {
+            // Whatever comes after isn't needed
+            assert_eq!(
+                openpgp_key_id(&packet[..end]).as_deref(),
+                Some(GPG_FINGERPRINT),
+                "{} bytes",
+                end
+            );
+        }
+    }
+
+    #[test]
+    fn openpgp_key_id_of_garbage() {
+        assert_eq!(openpgp_key_id(b""), None);
+        assert_eq!(openpgp_key_id(b"not a packet"), None);
+        assert_eq!(openpgp_key_id(&[0xff; 64]), None);
+        assert_eq!(openpgp_key_id(&[0x88, 0xff, 0x04, 0x00]), None);
+        let packet = armor_body(SSH_BLOCK).unwrap();
+        assert_eq!(openpgp_key_id(&packet), None);
+    }
+
+    #[test]
+    fn subpackets_stop_at_truncation() {
+        let subpackets: Vec<(u8, &[u8])> = Subpackets(&[2, 5, 1, 3, 16, 1]).collect();
+        assert_eq!(subpackets, vec![(5, &[1][..])]);
+        assert_eq!(Subpackets(&[0xff, 0xff, 0xff, 0xff, 0xff]).count(), 0);
+        assert_eq!(Subpackets(&[0xc0]).count(), 0);
+        // The critical bit isn't part of the type
+        let subpackets: Vec<(u8, &[u8])> = Subpackets(&[2, 0x90, 7]).collect();
+        assert_eq!(subpackets, vec![(16, &[7][..])]);
+    }
+
+    #[test]
+    fn ssh_public_key_of_signature() {
+        let blob = armor_body(SSH_BLOCK).unwrap();
+        assert_eq!(ssh_public_key(&blob).as_deref(), Some(SSH_KEY));
+    }
+
+    #[test]
+    fn ssh_public_key_of_truncated_signature() {
+        let blob = armor_body(SSH_BLOCK).unwrap();
+        // The magic, version, key length and 51 byte key
+        let key_end = 6 + 4 + 4 + 51;
+        for end in 0..key_end {
+            assert_eq!(ssh_public_key(&blob[..end]), None, "{} bytes", end);
+        }
+        assert_eq!(ssh_public_key(&blob[..key_end]).as_deref(), Some(SSH_KEY));
+    }
+
+    #[test]
+    fn ssh_public_key_of_garbage() {
+        assert_eq!(ssh_public_key(b""), None);
+        assert_eq!(ssh_public_key(b"SSHSIG\0\0\0\x01\xff\xff\xff\xff"), None);
+        let packet = armor_body(GPG_BLOCK).unwrap();
+        assert_eq!(ssh_public_key(&packet), None);
+    }
+
+    #[test]
+    fn armor_body_of_garbage() {
+        assert_eq!(armor_body(""), None);
+        assert_eq!(
+            armor_body("-----BEGIN PGP SIGNATURE-----\n\n*not base64*\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn gpg_verification_of_status_lines() {
+        let status = |line: &str| {
+            gpg_verification(&format!(
+                "[GNUPG:] NEWSIG\n{}\n[GNUPG:] TRUST_UNDEFINED 0 pgp\n",
+                line
+            ))
+        };
+        assert_eq!(
+            status("[GNUPG:] GOODSIG 0AAAD733E6740D92 A U Thor <a@example.com>"),
+            Some(Verification::Good(Some(
+                "A U Thor <a@example.com>".to_string()
+            )))
+        );
+        assert_eq!(
+            status("[GNUPG:] EXPSIG 0AAAD733E6740D92 A U Thor <a@example.com>"),
+            Some(Verification::ExpiredSignature(
+                "A U Thor <a@example.com>".to_string()
+            ))
+        );
+        assert_eq!(
+            status("[GNUPG:] EXPKEYSIG 0AAAD733E6740D92 A U Thor <a@example.com>"),
+            Some(Verification::ExpiredKey(
+                "A U Thor <a@example.com>".to_string()
+            ))
+        );
+        assert_eq!(
+            status("[GNUPG:] REVKEYSIG 0AAAD733E6740D92 A U Thor <a@example.com>"),
+            Some(Verification::RevokedKey(
+                "A U Thor <a@example.com>".to_string()
+            ))
+        );
+        assert_eq!(
+            status("[GNUPG:] BADSIG 0AAAD733E6740D92 A U Thor <a@example.com>"),
+            Some(Verification::Bad)
+        );
+        assert_eq!(
+            status("[GNUPG:] ERRSIG 0AAAD733E6740D92 22 10 00 1700000000 4 -"),
+            Some(Verification::Unverifiable(
+                "unsupported algorithm".to_string()
+            ))
+        );
+        assert_eq!(status("[GNUPG:] PLAINTEXT 74 0"), None);
+        assert_eq!(gpg_verification(""), None);
+    }
+
+    #[test]
+    fn gpg_verification_of_missing_key() {
+        let status = "[GNUPG:] NEWSIG\n\
+            [GNUPG:] ERRSIG 0AAAD733E6740D92 22 10 00 1700000000 9 -\n\
+            [GNUPG:] NO_PUBKEY 0AAAD733E6740D92\n";
+        assert_eq!(gpg_verification(status), Some(Verification::UnknownKey));
+    }
+
+    #[test]
+    fn signature_start_of_tag() {
+        let tag = format!("object 0\ntype commit\ntag v1\n\nRelease\n{}", GPG_BLOCK);
+        assert_eq!(
+            signature_start(tag.as_bytes()),
+            Some(tag.find("-----").unwrap())
+        );
+        assert_eq!(
+            signature_start(b"object 0\ntype commit\ntag v1\n\nRelease\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn signature_start_after_quoted_block() {
+        let message = format!("object 0\ntype commit\ntag v1\n\nQuoting:\n{}\n", SSH_BLOCK);
+        let tag = format!("{}{}", message, GPG_BLOCK);
+        assert_eq!(signature_start(tag.as_bytes()), Some(message.len()));
+    }
+
+    #[test]
+    fn signature_method_from_block() {
+        assert_eq!(
+            SigningMethod::from_block(GPG_BLOCK),
+            Some(SigningMethod::Gpg)
+        );
+        assert_eq!(
+            SigningMethod::from_block(SSH_BLOCK),
+            Some(SigningMethod::Ssh)
+        );
+        assert_eq!(SigningMethod::from_block("garbage"), None);
+    }
+}
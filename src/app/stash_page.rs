@@ -0,0 +1,496 @@
+use git2::{Commit, Object, Oid, Repository};
+
+use ratatui::{
+    layout::Rect,
+    prelude::Modifier,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem},
+    Frame,
+};
+
+use crate::app::pagination::pagination;
+use crate::app::patch::patch_text;
+use crate::app::signature::format_time;
+use crate::app::worker::{CancelToken, Mailbox, Task, TaskResult, WorkerHandle};
+use crate::errors::{ErrorKind, GitBrowserError};
+use crate::traits::{Drawable, Navigable};
+
+/// One entry of the stash.
+#[derive(Clone)]
+pub struct StashEntry {
+    pub index: usize,
+    pub message: String,
+    pub id: Oid,
+    /// When the stash was made, in seconds since the epoch.
+    pub time: i64,
+}
+
+impl StashEntry {
+    /// How git refers to the entry, e.g. `stash@{0}`.
+    pub fn name(&self) -> String {
+        format!("stash@{{{}}}", self.index)
+    }
+}
+
+pub fn load_stashes(repo: &mut Repository) -> Result<Vec<StashEntry>, GitBrowserError> {
+    let mut stashes = vec![];
+    repo.stash_foreach(|index, message, id| {
+        stashes.push((index, message.to_string(), *id));
+        true
+    })
+    .map_err(|e| GitBrowserError::git(ErrorKind::Stash, e))?;
+    stashes
+        .into_iter()
+        .map(|(index, message, id)| {
+            let commit = repo.find_commit(id).map_err(|e| {
+                GitBrowserError::git(ErrorKind::Stash, e)
+                    .with_oid(id)
+                    .with_path(format!("stash@{{{}}}", index))
+            })?;
+            Ok(StashEntry {
+                index,
+                message,
+                id,
+                time: commit.time().seconds(),
+            })
+        })
+        .collect()
+}
+
+/// Lists the entries of the stash.
+pub struct StashPage<'repo> {
+    repo: &'repo Repository,
+    stashes: Vec<StashEntry>,
+    workers: WorkerHandle,
+    mailbox: Mailbox,
+    selected_index: usize,
+}
+
+impl<'repo> StashPage<'repo> {
+    pub fn new(repo: &'repo Repository, workers: &WorkerHandle) -> StashPage<'repo> {
+        let mailbox = Mailbox::new();
+        workers.submit_to(Task::LoadStashes, None, &mailbox);
+        StashPage {
+            repo,
+            stashes: vec![],
+            workers: workers.clone(),
+            mailbox,
+            selected_index: 0,
+        }
+    }
+
+    /// Reload the stash in the background.
+    pub fn refresh(&mut self) {
+        self.workers
+            .submit_to(Task::LoadStashes, None, &self.mailbox);
+    }
+
+    /// Take the stashes that have been loaded since last time, returning
+    /// what failed to load.
+    pub fn receive_results(&mut self) -> Vec<GitBrowserError> {
+        let mut failures = vec![];
+        let results: Vec<TaskResult> = self.mailbox.results().collect();
+        for result in results {
+            match result {
+                TaskResult::Stashes(stashes) => self.receive_stashes(stashes),
+                TaskResult::Failed(e) => failures.push(e),
+                _ => {}
+            }
+        }
+        failures
+    }
+
+    /// Replace the list of stashes, keeping the same stash selected if it
+    /// still exists. Indexes shift as stashes are pushed and dropped, so
+    /// stashes are matched by id.
+    fn receive_stashes(&mut self, stashes: Vec<StashEntry>) {
+        let selected = self.stashes.get(self.selected_index).map(|s| s.id);
+        self.stashes = stashes;
+        self.selected_index = self
+            .stashes
+            .iter()
+            .position(|stash| Some(stash.id) == selected)
+            .unwrap_or(self.selected_index.min(self.len().saturating_sub(1)));
+    }
+
+    fn len(&self) -> usize {
+        self.stashes.len()
+    }
+
+    pub fn selected(&self) -> Option<&StashEntry> {
+        self.stashes.get(self.selected_index)
+    }
+}
+
+impl<'repo> Drawable<'repo> for StashPage<'repo> {
+    fn draw(&self, f: &mut Frame, area: Rect, content_block: Block) -> Rect {
+        let viewport = content_block.inner(area);
+
+        let visible = viewport.height;
+        let (_page, _pages, page_start_index) =
+            pagination(self.len(), visible.into(), self.selected_index);
+
+        let list_items: Vec<ListItem> = self
+            .stashes
+            .iter()
+            .enumerate()
+            .skip(page_start_index)
+            .take(visible.into())
+            .map(|(index, stash)| {
+                let style = if index == self.selected_index {
+                    Style::default().fg(Color::Black).bg(Color::Cyan)
+                } else {
+                    Style::default().fg(Color::Gray)
+                };
+                let time = format_time(stash.time);
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{:11}", stash.name()),
+                        Style::default().add_modifier(Modifier::DIM),
+                    ),
+                    Span::styled(
+                        format!("{:16} ", time),
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::DIM),
+                    ),
+                    Span::styled(&stash.message, style),
+                ]))
+            })
+            .collect();
+        let content = List::new(list_items).block(content_block);
+        f.render_widget(content, area);
+        viewport
+    }
+
+    fn title(&self) -> String {
+        "stash".to_string()
+    }
+}
+
+impl<'repo> Navigable<'repo> for StashPage<'repo> {
+    fn home(&mut self, _page_size: u16) {
+        self.selected_index = 0;
+    }
+
+    fn end(&mut self, _page_size: u16) {
+        self.selected_index = self.len().saturating_sub(1);
+    }
+
+    fn pagedown(&mut self, page_size: u16) {
+        let h: usize = page_size.into();
+        let index = self.selected_index.saturating_add(h);
+        self.selected_index = index.min(self.len().saturating_sub(1));
+    }
+
+    fn pageup(&mut self, page_size: u16) {
+        let h: usize = page_size.into();
+        self.selected_index = self.selected_index.saturating_sub(h);
+    }
+
+    fn next_selection(&mut self) {
+        if self.selected_index + 1 < self.len() {
+            self.selected_index += 1;
+        } else {
+            self.selected_index = 0;
+        }
+    }
+
+    fn previous_selection(&mut self) {
+        if self.selected_index > 0 {
+            self.selected_index -= 1;
+        } else {
+            self.selected_index = self.len().saturating_sub(1);
+        }
+    }
+
+    fn select(&self) -> Result<Option<(Object<'repo>, String)>, GitBrowserError> {
+        let stash = match self.selected() {
+            Some(stash) => stash,
+            None => return Ok(None),
+        };
+        match self.repo.find_object(stash.id, None) {
+            Ok(object) => Ok(Some((object, stash.name()))),
+            Err(e) => Err(GitBrowserError::git(ErrorKind::Stash, e)
+                .with_oid(stash.id)
+                .with_path(stash.name())),
+        }
+    }
+
+    fn selected_item(&self) -> String {
+        self.selected()
+            .map(|stash| stash.name())
+            .unwrap_or_default()
+    }
+
+    fn next_tick(&mut self, _block: bool) -> Result<(), GitBrowserError> {
+        Ok(())
+    }
+}
+
+/// Something saved in a stash entry.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StashPart {
+    /// The tracked files as they were in the working tree.
+    Worktree,
+    Index,
+    /// Only present when the stash was made with `--include-untracked`.
+    Untracked,
+    /// The changes to the working tree since the commit the stash was made
+    /// on.
+    Diff,
+}
+
+impl StashPart {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            StashPart::Worktree => "worktree",
+            StashPart::Index => "index",
+            StashPart::Untracked => "untracked",
+            StashPart::Diff => "diff",
+        }
+    }
+
+    /// The suffix that selects the commit holding this part, relative to
+    /// the stash commit.
+    fn suffix(&self) -> &'static str {
+        match *self {
+            StashPart::Index => "^2",
+            StashPart::Untracked => "^3",
+            StashPart::Worktree | StashPart::Diff => "",
+        }
+    }
+}
+
+/// A patch of the changes to the working tree saved in a stash entry,
+/// relative to the commit the stash was made on.
+pub fn stash_diff(repo: &Repository, id: Oid, name: &str) -> Result<Vec<u8>, GitBrowserError> {
+    let error = |e| {
+        GitBrowserError::git(ErrorKind::Diff, e)
+            .with_oid(id)
+            .with_path(name)
+    };
+    let commit = repo.find_commit(id).map_err(error)?;
+    let base = commit.parent(0).and_then(|c| c.tree()).map_err(error)?;
+    let tree = commit.tree().map_err(error)?;
+    let diff = repo
+        .diff_tree_to_tree(Some(&base), Some(&tree), None)
+        .map_err(error)?;
+    patch_text(&diff).map_err(error)
+}
+
+/// Lists what was saved in one stash entry.
+pub struct StashEntryPage<'repo> {
+    name: String,
+    commit: Commit<'repo>,
+    parts: Vec<StashPart>,
+    workers: WorkerHandle,
+    mailbox: Mailbox,
+    // Set while the diff is being computed, cancelling it if the page is
+    // closed first
+    loading_diff: Option<CancelToken>,
+    // The diff once it has arrived, until it is opened
+    diff: Option<(String, Vec<u8>)>,
+    selected_index: usize,
+}
+
+impl<'repo> StashEntryPage<'repo> {
+    pub fn new(
+        object: Object<'repo>,
+        name: String,
+        workers: &WorkerHandle,
+    ) -> Result<StashEntryPage<'repo>, GitBrowserError> {
+        let commit = object.peel_to_commit().map_err(|e| {
+            GitBrowserError::git(ErrorKind::Stash, e)
+                .with_oid(object.id())
+                .with_path(&name)
+        })?;
+        let mut parts = vec![StashPart::Worktree, StashPart::Index];
+        if commit.parent_count() > 2 {
+            parts.push(StashPart::Untracked);
+        }
+        parts.push(StashPart::Diff);
+        Ok(StashEntryPage {
+            name,
+            commit,
+            parts,
+            workers: workers.clone(),
+            mailbox: Mailbox::new(),
+            loading_diff: None,
+            diff: None,
+            selected_index: 0,
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.parts.len()
+    }
+
+    pub fn selected_part(&self) -> Option<StashPart> {
+        self.parts.get(self.selected_index).copied()
+    }
+
+    /// How a part is shown, e.g. `stash@{0}^2`.
+    fn label(&self, part: StashPart) -> String {
+        format!("{}{}", self.name, part.suffix())
+    }
+
+    /// The revision of the commit holding a part. Stashes are renumbered as
+    /// they are pushed and dropped, so this names the stash commit by id
+    /// rather than by its position in the stash.
+    pub fn revision(&self, part: StashPart) -> String {
+        format!("{}{}", self.commit.id(), part.suffix())
+    }
+
+    /// The commit holding a part of the stash.
+    pub fn part_commit(&self, part: StashPart) -> Result<Commit<'repo>, GitBrowserError> {
+        let commit = match part {
+            StashPart::Worktree | StashPart::Diff => return Ok(self.commit.clone()),
+            StashPart::Index => self.commit.parent(1),
+            StashPart::Untracked => self.commit.parent(2),
+        };
+        commit.map_err(|e| {
+            GitBrowserError::git(ErrorKind::Stash, e)
+                .with_oid(self.commit.id())
+                .with_path(self.label(part))
+        })
+    }
+
+    /// Compute the diff of the stash in the background, to be opened when
+    /// it arrives.
+    pub fn load_diff(&mut self) {
+        let cancel = CancelToken::new();
+        self.workers.submit_to(
+            Task::StashDiff {
+                id: self.commit.id(),
+                name: self.name.to_string(),
+            },
+            Some(&cancel),
+            &self.mailbox,
+        );
+        self.loading_diff = Some(cancel);
+    }
+
+    /// Keep the diff if it has arrived, unless the page stopped waiting for
+    /// it, returning what failed to load.
+    pub fn receive_results(&mut self) -> Vec<GitBrowserError> {
+        let mut failures = vec![];
+        let results: Vec<TaskResult> = self.mailbox.results().collect();
+        for result in results {
+            match result {
+                TaskResult::StashDiff { name, diff } if self.loading_diff.is_some() => {
+                    self.loading_diff = None;
+                    self.diff = Some((name, diff));
+                }
+                TaskResult::Failed(e) => {
+                    self.loading_diff = None;
+                    failures.push(e);
+                }
+                _ => {}
+            }
+        }
+        failures
+    }
+
+    /// The name and text of the diff that has arrived, if any.
+    pub fn take_diff(&mut self) -> Option<(String, Vec<u8>)> {
+        self.diff.take()
+    }
+}
+
+impl<'repo> Drawable<'repo> for StashEntryPage<'repo> {
+    fn draw(&self, f: &mut Frame, area: Rect, content_block: Block) -> Rect {
+        let viewport = content_block.inner(area);
+
+        let list_items: Vec<ListItem> = self
+            .parts
+            .iter()
+            .enumerate()
+            .map(|(index, part)| {
+                let style = if index == self.selected_index {
+                    Style::default().fg(Color::Black).bg(Color::Cyan)
+                } else {
+                    Style::default().fg(Color::Gray)
+                };
+                let (kind, fg, modifier) = match part {
+                    StashPart::Diff => ("diff", Color::Gray, Modifier::empty()),
+                    _ => ("tree", Color::Blue, Modifier::BOLD),
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{:10}", kind),
+                        Style::default().add_modifier(Modifier::DIM),
+                    ),
+                    Span::styled(
+                        format!("{:14}", self.label(*part)),
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::DIM),
+                    ),
+                    Span::styled(part.as_str(), style.fg(fg).add_modifier(modifier)),
+                ]))
+            })
+            .collect();
+        let content = List::new(list_items).block(content_block);
+        f.render_widget(content, area);
+        viewport
+    }
+
+    fn title(&self) -> String {
+        if self.loading_diff.is_some() {
+            format!("{} (loading diff)", self.name)
+        } else {
+            self.name.to_string()
+        }
+    }
+}
+
+impl<'repo> Navigable<'repo> for StashEntryPage<'repo> {
+    fn home(&mut self, _page_size: u16) {
+        self.selected_index = 0;
+    }
+
+    fn end(&mut self, _page_size: u16) {
+        self.selected_index = self.len().saturating_sub(1);
+    }
+
+    fn pagedown(&mut self, _page_size: u16) {
+        self.end(0);
+    }
+
+    fn pageup(&mut self, _page_size: u16) {
+        self.home(0);
+    }
+
+    fn next_selection(&mut self) {
+        if self.selected_index + 1 < self.len() {
+            self.selected_index += 1;
+        } else {
+            self.selected_index = 0;
+        }
+    }
+
+    fn previous_selection(&mut self) {
+        if self.selected_index > 0 {
+            self.selected_index -= 1;
+        } else {
+            self.selected_index = self.len().saturating_sub(1);
+        }
+    }
+
+    fn select(&self) -> Result<Option<(Object<'repo>, String)>, GitBrowserError> {
+        // Parts aren't all objects, so the app uses `selected_part`
+        Ok(None)
+    }
+
+    fn selected_item(&self) -> String {
+        self.selected_part()
+            .map(|part| part.as_str().to_string())
+            .unwrap_or_default()
+    }
+
+    fn next_tick(&mut self, _block: bool) -> Result<(), GitBrowserError> {
+        Ok(())
+    }
+}
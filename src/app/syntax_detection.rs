@@ -0,0 +1,215 @@
+use std::path::Path;
+
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+
+/// Modelines are only looked for this many lines from the start or end of a
+/// file, as Vim does by default.
+const MODELINE_LINES: usize = 5;
+
+/// Language names used by Vim, Emacs, linguist and interpreters that don't
+/// match the name or an extension of a syntax.
+const ALIASES: &[(&str, &str)] = &[
+    ("ash", "sh"),
+    ("bash", "sh"),
+    ("dash", "sh"),
+    ("golang", "go"),
+    ("ksh", "sh"),
+    ("node", "js"),
+    ("nodejs", "js"),
+    ("plain text", "txt"),
+    ("shell", "sh"),
+    ("shell-script", "sh"),
+    ("text", "txt"),
+    ("viml", "vim"),
+    ("vim script", "vim"),
+    ("zsh", "sh"),
+];
+
+/// Choose the syntax for a file, trying in order:
+///
+/// 1. the `linguist-language` attribute from `.gitattributes`
+/// 2. a Vim or Emacs modeline
+/// 3. the full file name, e.g. `Makefile` or `.bashrc`
+/// 4. the file extension
+/// 5. the interpreter in a shebang line
+/// 6. other first line patterns known to the syntaxes, e.g. `<?xml`
+pub fn detect_syntax<'s>(
+    syntax_set: &'s SyntaxSet,
+    name: &str,
+    content: &str,
+    linguist_language: Option<&str>,
+) -> Option<&'s SyntaxReference> {
+    let first_line = content.lines().next().unwrap_or_default();
+    linguist_language
+        .and_then(|language| find_language(syntax_set, language))
+        .or_else(|| modeline(content).and_then(|language| find_language(syntax_set, &language)))
+        .or_else(|| syntax_set.find_syntax_by_extension(name))
+        .or_else(|| {
+            name.strip_prefix('.')
+                .and_then(|name| syntax_set.find_syntax_by_extension(name))
+        })
+        .or_else(|| {
+            Path::new(name)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .and_then(|ext| syntax_set.find_syntax_by_extension(ext))
+        })
+        .or_else(|| {
+            interpreter(first_line).and_then(|language| find_language(syntax_set, language))
+        })
+        .or_else(|| syntax_set.find_syntax_by_first_line(first_line))
+}
+
+/// Find a syntax by a language name as used in modelines and
+/// `.gitattributes`.
+fn find_language<'s>(syntax_set: &'s SyntaxSet, language: &str) -> Option<&'s SyntaxReference> {
+    let language = language.trim().to_lowercase();
+    let language = language.strip_suffix("-mode").unwrap_or(&language);
+    let alias = ALIASES
+        .iter()
+        .find(|(name, _)| *name == language)
+        .map(|(_, alias)| *alias);
+    syntax_set
+        .find_syntax_by_token(language)
+        .or_else(|| alias.and_then(|alias| syntax_set.find_syntax_by_token(alias)))
+}
+
+/// The language named by an interpreter in a shebang line, such as `python`
+/// in `#!/usr/bin/env python3`.
+fn interpreter(first_line: &str) -> Option<&str> {
+    let mut words = first_line.strip_prefix("#!")?.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        // Skip options like `env -S`
+        program = words.find(|word| !word.starts_with('-'))?;
+    }
+    // Drop versions like `python3.12`
+    Some(program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.'))
+}
+
+/// The language set by a Vim or Emacs modeline, if there is one.
+fn modeline(content: &str) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let head = &lines[..lines.len().min(MODELINE_LINES)];
+    let tail = &lines[lines.len().saturating_sub(MODELINE_LINES)..];
+    head.iter()
+        .take(2)
+        .find_map(|line| emacs_modeline(line))
+        .or_else(|| head.iter().chain(tail).find_map(|line| vim_modeline(line)))
+}
+
+/// `-*- mode: python -*-` or `-*- python -*-`
+fn emacs_modeline(line: &str) -> Option<String> {
+    let (_, rest) = line.split_once("-*-")?;
+    let (variables, _) = rest.split_once("-*-")?;
+    if !variables.contains(':') {
+        return Some(variables.trim().to_string());
+    }
+    variables.split(';').find_map(|variable| {
+        let (key, value) = variable.split_once(':')?;
+        key.trim()
+            .eq_ignore_ascii_case("mode")
+            .then(|| value.trim().to_string())
+    })
+}
+
+/// `vim: set ft=python :` or `vi: syntax=python`
+fn vim_modeline(line: &str) -> Option<String> {
+    let start = ["vim:", "vi:", "ex:", "Vim:"].iter().find_map(|marker| {
+        line.match_indices(marker)
+            // The marker must start the line or follow whitespace
+            .find(|(index, _)| *index == 0 || line[..*index].ends_with(char::is_whitespace))
+            .map(|(index, _)| index + marker.len())
+    })?;
+    line[start..]
+        .split(|c: char| c == ':' || c.is_whitespace())
+        .find_map(|option| {
+            let (key, value) = option.split_once('=')?;
+            matches!(key, "ft" | "filetype" | "syn" | "syntax").then(|| value.to_string())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(name: &str, content: &str) -> Option<String> {
+        let syntax_set = two_face::syntax::extra_newlines();
+        detect_syntax(&syntax_set, name, content, None).map(|syntax| syntax.name.clone())
+    }
+
+    #[test]
+    fn interpreter_of_shebangs() {
+        assert_eq!(interpreter("#!/bin/sh"), Some("sh"));
+        assert_eq!(interpreter("#!/usr/bin/env python3"), Some("python"));
+        assert_eq!(interpreter("#!/usr/bin/env -S python3.12"), Some("python"));
+        assert_eq!(interpreter("#! /usr/bin/perl -w"), Some("perl"));
+        assert_eq!(interpreter("#!/usr/bin/env -S"), None);
+        assert_eq!(interpreter("# not a shebang"), None);
+    }
+
+    #[test]
+    fn modelines() {
+        assert_eq!(
+            modeline("# -*- mode: python -*-\nprint()\n"),
+            Some("python".to_string())
+        );
+        assert_eq!(
+            modeline("/* -*- coding: utf-8; mode: c -*- */\n"),
+            Some("c".to_string())
+        );
+        assert_eq!(modeline("-*- ruby -*-\n"), Some("ruby".to_string()));
+        assert_eq!(
+            modeline("echo hi\n# vim: set ft=sh :\n"),
+            Some("sh".to_string())
+        );
+        assert_eq!(modeline("// vi: syntax=rust\n"), Some("rust".to_string()));
+        assert_eq!(modeline("let novim: set ft=sh :\n"), None);
+        assert_eq!(modeline("no modeline here\n"), None);
+    }
+
+    #[test]
+    fn modelines_only_near_the_start_or_end() {
+        let middle = format!("{}# vim: ft=sh\n{}", "x\n".repeat(5), "x\n".repeat(5));
+        assert_eq!(modeline(&middle), None);
+        let end = format!("{}# vim: ft=sh\n", "x\n".repeat(10));
+        assert_eq!(modeline(&end), Some("sh".to_string()));
+    }
+
+    #[test]
+    fn detect_syntax_by_file_name() {
+        assert_eq!(detect("Makefile", "all:\n").as_deref(), Some("Makefile"));
+        assert_eq!(
+            detect(".bashrc", "alias ll='ls -l'\n").as_deref(),
+            Some("Bourne Again Shell (bash)")
+        );
+        assert_eq!(
+            detect("CMakeLists.txt", "project(foo)\n").as_deref(),
+            Some("CMake")
+        );
+        assert_eq!(detect("main.rs", "fn main() {}\n").as_deref(), Some("Rust"));
+    }
+
+    #[test]
+    fn detect_syntax_by_content() {
+        assert_eq!(
+            detect("script", "#!/usr/bin/env -S python3.12\nprint()\n").as_deref(),
+            Some("Python")
+        );
+        assert_eq!(
+            detect("build", "# -*- mode: python -*-\n").as_deref(),
+            Some("Python")
+        );
+        assert_eq!(
+            detect("notes.txt", "echo hi\n# vim: set ft=sh :\n").as_deref(),
+            Some("Bourne Again Shell (bash)")
+        );
+    }
+
+    #[test]
+    fn detect_syntax_prefers_linguist_language() {
+        let syntax_set = two_face::syntax::extra_newlines();
+        let syntax = detect_syntax(&syntax_set, "main.rs", "", Some("Python"));
+        assert_eq!(syntax.map(|syntax| syntax.name.as_str()), Some("Python"));
+    }
+}
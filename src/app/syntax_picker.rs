@@ -0,0 +1,139 @@
+use git2::Object;
+
+use ratatui::{
+    layout::Rect,
+    prelude::Modifier,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem},
+    Frame,
+};
+
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+
+use crate::app::pagination::pagination;
+use crate::errors::GitBrowserError;
+use crate::traits::{Drawable, Navigable};
+
+/// Lists every syntax so that one can be chosen for the blob being viewed.
+pub struct SyntaxPicker<'syntax> {
+    syntaxes: Vec<&'syntax SyntaxReference>,
+    selected_index: usize,
+}
+
+impl<'syntax> SyntaxPicker<'syntax> {
+    pub fn new(syntax_set: &'syntax SyntaxSet, current: Option<&str>) -> SyntaxPicker<'syntax> {
+        let mut syntaxes: Vec<&SyntaxReference> = syntax_set
+            .syntaxes()
+            .iter()
+            .filter(|syntax| !syntax.hidden)
+            .collect();
+        syntaxes.sort_by_key(|syntax| syntax.name.to_lowercase());
+        let selected_index = current
+            .and_then(|current| syntaxes.iter().position(|syntax| syntax.name == current))
+            .unwrap_or(0);
+        SyntaxPicker {
+            syntaxes,
+            selected_index,
+        }
+    }
+
+    pub fn selected(&self) -> Option<&'syntax SyntaxReference> {
+        self.syntaxes.get(self.selected_index).copied()
+    }
+
+    fn len(&self) -> usize {
+        self.syntaxes.len()
+    }
+}
+
+impl<'repo, 'syntax> Drawable<'repo> for SyntaxPicker<'syntax> {
+    fn draw(&self, f: &mut Frame, area: Rect, content_block: Block) -> Rect {
+        let viewport = content_block.inner(area);
+
+        let visible = viewport.height;
+        let (_page, _pages, page_start_index) =
+            pagination(self.len(), visible.into(), self.selected_index);
+
+        let list_items: Vec<ListItem> = self
+            .syntaxes
+            .iter()
+            .enumerate()
+            .skip(page_start_index)
+            .take(visible.into())
+            .map(|(index, syntax)| {
+                let style = if index == self.selected_index {
+                    Style::default().fg(Color::Black).bg(Color::Cyan)
+                } else {
+                    Style::default().fg(Color::Gray)
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:30}", syntax.name), style),
+                    Span::styled(
+                        format!(" {}", syntax.file_extensions.join(" ")),
+                        Style::default().add_modifier(Modifier::DIM),
+                    ),
+                ]))
+            })
+            .collect();
+        let content = List::new(list_items).block(content_block);
+        f.render_widget(content, area);
+        viewport
+    }
+
+    fn title(&self) -> String {
+        "set syntax".to_string()
+    }
+}
+
+impl<'repo, 'syntax> Navigable<'repo> for SyntaxPicker<'syntax> {
+    fn home(&mut self, _page_size: u16) {
+        self.selected_index = 0;
+    }
+
+    fn end(&mut self, _page_size: u16) {
+        self.selected_index = self.len().saturating_sub(1);
+    }
+
+    fn pagedown(&mut self, page_size: u16) {
+        let h: usize = page_size.into();
+        let index = self.selected_index.saturating_add(h);
+        self.selected_index = index.min(self.len().saturating_sub(1));
+    }
+
+    fn pageup(&mut self, page_size: u16) {
+        let h: usize = page_size.into();
+        self.selected_index = self.selected_index.saturating_sub(h);
+    }
+
+    fn next_selection(&mut self) {
+        if self.selected_index + 1 < self.len() {
+            self.selected_index += 1;
+        } else {
+            self.selected_index = 0;
+        }
+    }
+
+    fn previous_selection(&mut self) {
+        if self.selected_index > 0 {
+            self.selected_index -= 1;
+        } else {
+            self.selected_index = self.len().saturating_sub(1);
+        }
+    }
+
+    fn select(&self) -> Result<Option<(Object<'repo>, String)>, GitBrowserError> {
+        // The app applies the selected syntax to the blob pager
+        Ok(None)
+    }
+
+    fn selected_item(&self) -> String {
+        self.selected()
+            .map(|syntax| syntax.name.to_string())
+            .unwrap_or_default()
+    }
+
+    fn next_tick(&mut self, _block: bool) -> Result<(), GitBrowserError> {
+        Ok(())
+    }
+}
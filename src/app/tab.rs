@@ -0,0 +1,66 @@
+use git2::{Commit, Oid};
+
+use crate::app::{
+    blob_pager::BlobPager,
+    columns::Preview,
+    commit_page::CommitPage,
+    form::Form,
+    history::{History, JumpList},
+    log_page::LogPage,
+    notes::NotesRefPicker,
+    pickaxe::PickaxePage,
+    reflog_page::ReflogPage,
+    signing::SignatureStatus,
+    stash_page::{StashEntryPage, StashPage},
+    syntax_picker::SyntaxPicker,
+    tree_page::TreePage,
+    workdir_page::WorkdirPage,
+    worker::Mailbox,
+    AppMode,
+};
+use crate::errors::GitBrowserError;
+
+/// A tab that isn't being shown. The app keeps the state of the current tab
+/// in its own fields, and switches tabs by swapping that state with a
+/// parked one.
+#[derive(Default)]
+pub struct Tab<'repo, 'syntax> {
+    /// Shown in the tab bar.
+    pub label: String,
+    pub commit: Option<Commit<'repo>>,
+    pub note: Option<String>,
+    pub commit_signature: Option<SignatureStatus>,
+    pub tag_signature: Option<(String, Oid, SignatureStatus)>,
+    pub reference: Option<String>,
+    pub moved_to: Option<Oid>,
+    pub mailbox: Mailbox,
+    pub tree_pages: Vec<TreePage<'repo>>,
+    pub workdir_pages: Vec<WorkdirPage<'repo>>,
+    pub stash_page: Option<StashPage<'repo>>,
+    pub stash_entry_page: Option<StashEntryPage<'repo>>,
+    pub reflog_page: Option<ReflogPage<'repo>>,
+    pub log_page: Option<LogPage<'repo>>,
+    pub pickaxe_form: Option<(Oid, Form)>,
+    pub pickaxe_page: Option<PickaxePage<'repo>>,
+    pub filter_form: Option<Form>,
+    pub blob_pager: Option<BlobPager<'syntax>>,
+    pub preview: Option<Preview<'syntax>>,
+    pub syntax_picker: Option<SyntaxPicker<'syntax>>,
+    pub commit_page: Option<CommitPage>,
+    pub notes_picker: Option<NotesRefPicker>,
+    pub jump_list: Option<JumpList>,
+    pub mode_history: Vec<AppMode>,
+    pub history: History,
+    pub active_error: Option<GitBrowserError>,
+}
+
+impl<'repo, 'syntax> Tab<'repo, 'syntax> {
+    /// A tab starting at the list of references.
+    pub fn new() -> Tab<'repo, 'syntax> {
+        Tab {
+            label: "refs".to_string(),
+            mode_history: vec![AppMode::BrowseRefs],
+            ..Tab::default()
+        }
+    }
+}
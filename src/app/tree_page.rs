@@ -1,19 +1,29 @@
-use git2::{Object, Repository};
+use git2::{Object, Repository, Tree, TreeEntry};
 
 use ratatui::{
     layout::Rect,
+    prelude::Modifier,
+    style::{Color, Style},
     text::{Line, Span},
     widgets::{Block, List, ListItem},
     Frame,
 };
 
+use crate::app::entry_metadata::EntryMetadata;
 use crate::app::pagination::pagination;
-use crate::errors::GitBrowserError;
+use crate::app::worker::{CancelToken, Mailbox, Task, TaskResult, WorkerHandle};
+use crate::errors::{ErrorKind, GitBrowserError};
 use crate::traits::{Display, Drawable, Navigable};
 
+/// The number of entries whose metadata is loaded by each background task.
+const METADATA_BATCH: usize = 64;
+
 pub struct TreePage<'repo> {
     repo: &'repo Repository,
-    tree_object: Object<'repo>,
+    tree: Tree<'repo>,
+    metadata: Vec<Option<EntryMetadata>>,
+    mailbox: Mailbox,
+    _cancel: CancelToken,
     selected_index: usize,
     name: String,
 }
@@ -23,56 +33,141 @@ impl<'repo> TreePage<'repo> {
         repo: &'repo Repository,
         tree_object: Object<'repo>,
         name: String,
-    ) -> TreePage<'repo> {
-        TreePage {
+        workers: &WorkerHandle,
+    ) -> Result<TreePage<'repo>, GitBrowserError> {
+        let tree = tree_object.peel_to_tree().map_err(|e| {
+            GitBrowserError::git(ErrorKind::TreeReference, e)
+                .with_oid(tree_object.id())
+                .with_path(&name)
+        })?;
+
+        // The workers take the most recently submitted task first, so submit
+        // the batches from the end to load the top of the tree first
+        let cancel = CancelToken::new();
+        let mailbox = Mailbox::new();
+        let batches: Vec<usize> = (0..tree.len()).step_by(METADATA_BATCH).collect();
+        for start in batches.into_iter().rev() {
+            workers.submit_to(
+                Task::EntryMetadata {
+                    tree: tree.id(),
+                    entries: start..start + METADATA_BATCH,
+                },
+                Some(&cancel),
+                &mailbox,
+            );
+        }
+
+        Ok(TreePage {
             selected_index: 0,
             repo,
-            tree_object,
+            metadata: vec![None; tree.len()],
+            mailbox,
+            _cancel: cancel,
+            tree,
             name,
+        })
+    }
+
+    pub fn tree(&self) -> &Tree<'repo> {
+        &self.tree
+    }
+
+    /// Take the metadata that has been loaded since last time, returning
+    /// what failed to load.
+    pub fn receive_results(&mut self) -> Vec<GitBrowserError> {
+        let mut failures = vec![];
+        for result in self.mailbox.results() {
+            match result {
+                TaskResult::EntryMetadata(metadata) => {
+                    for (index, entry_metadata) in metadata {
+                        if let Some(slot) = self.metadata.get_mut(index) {
+                            *slot = Some(entry_metadata);
+                        }
+                    }
+                }
+                TaskResult::Failed(e) => failures.push(e),
+                _ => {}
+            }
         }
+        failures
     }
 
     fn len(&self) -> usize {
-        match self.tree_object.peel_to_tree() {
-            Ok(tree) => tree.len(),
-            Err(_) => 0,
+        self.tree.len()
+    }
+
+    pub fn selected_entry(&self) -> Option<TreeEntry<'_>> {
+        self.tree.get(self.selected_index)
+    }
+
+    /// The metadata of the selected entry, once it has been loaded.
+    pub fn selected_metadata(&self) -> Option<&EntryMetadata> {
+        self.metadata
+            .get(self.selected_index)
+            .and_then(|metadata| metadata.as_ref())
+    }
+
+    /// Draw only the names of the entries, for the narrow columns of the
+    /// column layout.
+    pub fn draw_names(&self, f: &mut Frame, area: Rect, content_block: Block) -> Rect {
+        self.draw_entries(f, area, content_block, false)
+    }
+
+    fn draw_entries(
+        &self,
+        f: &mut Frame,
+        area: Rect,
+        content_block: Block,
+        with_details: bool,
+    ) -> Rect {
+        let viewport = content_block.inner(area);
+        let mut list_items = Vec::<ListItem>::new();
+        let iter = self.tree.iter();
+
+        let visible = viewport.height;
+        let (_page, _pages, page_start_index) =
+            pagination(self.len(), visible.into(), self.selected_index);
+
+        let display_items = iter.skip(page_start_index).take(visible.into());
+
+        for (pos, entry) in display_items.enumerate() {
+            let selected = pos + page_start_index == self.selected_index;
+            let metadata = self.metadata[pos + page_start_index].as_ref();
+            if let Some((kind, kind_style)) = entry.display_kind(metadata) {
+                let (value, style) = entry.display_name(selected);
+                let mut spans = vec![];
+                if with_details {
+                    let size = metadata.map(|m| m.display_size()).unwrap_or_default();
+                    spans.push(Span::styled(format!("{:10}", kind), kind_style));
+                    spans.push(Span::styled(
+                        format!("{:>7} ", size),
+                        Style::default().fg(Color::Gray).add_modifier(Modifier::DIM),
+                    ));
+                }
+                spans.push(Span::styled(value, style));
+                list_items.push(ListItem::new(Line::from(spans)));
+            }
+        }
+        let content = List::new(list_items).block(content_block);
+        f.render_widget(content, area);
+        viewport
+    }
+
+    /// Select the entry with the given name, if it exists.
+    pub fn select_name(&mut self, name: &str) {
+        if let Some(index) = self
+            .tree
+            .iter()
+            .position(|entry| entry.name_bytes() == name.as_bytes())
+        {
+            self.selected_index = index;
         }
     }
 }
 
 impl<'repo> Drawable<'repo> for TreePage<'repo> {
     fn draw(&self, f: &mut Frame, area: Rect, content_block: Block) -> Rect {
-        match self.tree_object.peel_to_tree() {
-            Ok(tree) => {
-                let viewport = content_block.inner(area);
-                let mut list_items = Vec::<ListItem>::new();
-                let iter = tree.iter();
-
-                let visible = viewport.height;
-                let (_page, _pages, page_start_index) =
-                    pagination(tree.len(), visible.into(), self.selected_index);
-
-                let display_items = iter.skip(page_start_index).take(visible.into());
-
-                for (pos, entry) in display_items.enumerate() {
-                    let selected = pos + page_start_index == self.selected_index;
-                    if let Some((kind, kind_style)) = entry.display_kind(self.repo) {
-                        let (value, style) = entry.display_name(selected);
-                        let line = Line::from(vec![
-                            Span::styled(format!("{:10}", kind), kind_style),
-                            Span::styled(value, style),
-                        ]);
-                        list_items.push(ListItem::new(line));
-                    }
-                }
-                let content = List::new(list_items).block(content_block);
-                f.render_widget(content, area);
-                viewport
-            }
-            Err(e) => {
-                panic!("failed to peel tree {}", e);
-            }
-        }
+        self.draw_entries(f, area, content_block, true)
     }
 
     fn title(&self) -> String {
@@ -91,12 +186,8 @@ impl<'repo> Navigable<'repo> for TreePage<'repo> {
 
     fn pagedown(&mut self, page_size: u16) {
         let h: usize = page_size.into();
-        let selected_index = self.selected_index + h;
-        self.selected_index = if selected_index > self.len() {
-            self.len() - 1
-        } else {
-            selected_index
-        }
+        let index = self.selected_index.saturating_add(h);
+        self.selected_index = index.min(self.len().saturating_sub(1));
     }
 
     fn pageup(&mut self, page_size: u16) {
@@ -109,7 +200,7 @@ impl<'repo> Navigable<'repo> for TreePage<'repo> {
     }
 
     fn next_selection(&mut self) {
-        if self.selected_index < self.len() - 1 {
+        if self.selected_index + 1 < self.len() {
             self.selected_index += 1;
         } else {
             self.selected_index = 0;
@@ -120,53 +211,32 @@ impl<'repo> Navigable<'repo> for TreePage<'repo> {
         if self.selected_index > 0 {
             self.selected_index -= 1;
         } else {
-            self.selected_index = self.len() - 1;
+            self.selected_index = self.len().saturating_sub(1);
         }
     }
 
-    fn select(&self) -> Option<(Object<'repo>, String)> {
-        match self.tree_object.peel_to_tree() {
-            Ok(tree) => {
-                if let Some(entry) = tree.get(self.selected_index) {
-                    match entry.to_object(self.repo) {
-                        Ok(object) => {
-                            if let Some(name) = entry.name() {
-                                Some((object, name.into()))
-                            } else {
-                                panic!("Failed to get tree entry name");
-                            }
-                        }
-                        Err(e) => {
-                            panic!("Failed to get object from entry {}", e);
-                        }
-                    }
-                } else {
-                    panic!("no tree entry?!?");
-                }
-            }
-            Err(e) => {
-                panic!("no tree?!? {}", e);
-            }
-        }
+    fn select(&self) -> Result<Option<(Object<'repo>, String)>, GitBrowserError> {
+        let entry = match self.tree.get(self.selected_index) {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        let name = entry.name().ok_or_else(|| {
+            GitBrowserError::message(ErrorKind::TreeEntry, "Tree entry name is not valid UTF-8")
+                .with_oid(entry.id())
+        })?;
+        let object = entry.to_object(self.repo).map_err(|e| {
+            GitBrowserError::git(ErrorKind::TreeEntry, e)
+                .with_oid(entry.id())
+                .with_path(name)
+        })?;
+        Ok(Some((object, name.into())))
     }
 
     fn selected_item(&self) -> String {
-        match self.tree_object.peel_to_tree() {
-            Ok(tree) => {
-                if let Some(entry) = tree.get(self.selected_index) {
-                    if let Some(name) = entry.name() {
-                        name.to_string()
-                    } else {
-                        panic!("Failed to get tree entry name");
-                    }
-                } else {
-                    panic!("no tree entry?!?");
-                }
-            }
-            Err(e) => {
-                panic!("no tree?!? {}", e);
-            }
-        }
+        self.tree
+            .get(self.selected_index)
+            .map(|entry| String::from_utf8_lossy(entry.name_bytes()).to_string())
+            .unwrap_or_default()
     }
 
     fn next_tick(&mut self, _block: bool) -> Result<(), GitBrowserError> {
@@ -0,0 +1,477 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use git2::{Object, Oid, Repository, Status};
+
+use ratatui::{
+    layout::Rect,
+    prelude::Modifier,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem},
+    Frame,
+};
+
+use crate::app::blob_pager::BlobContent;
+use crate::app::entry_metadata::format_size;
+use crate::app::pagination::pagination;
+use crate::app::worker::{CancelToken, Mailbox, Task, TaskResult, WorkerHandle};
+use crate::errors::{ErrorKind, GitBrowserError};
+use crate::traits::{Drawable, Navigable};
+
+const GITLINK_MODE: u32 = 0o160000;
+const SYMLINK_MODE: u32 = 0o120000;
+
+/// Something to browse that isn't a commit.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PseudoRevision {
+    Index,
+    Worktree,
+}
+
+impl PseudoRevision {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            PseudoRevision::Index => "INDEX",
+            PseudoRevision::Worktree => "WORKTREE",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<PseudoRevision> {
+        match name {
+            "INDEX" => Some(PseudoRevision::Index),
+            "WORKTREE" => Some(PseudoRevision::Worktree),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum EntryKind {
+    Tree,
+    Blob,
+    Symlink,
+    Submodule,
+}
+
+struct WorkdirEntry {
+    name: String,
+    kind: EntryKind,
+    size: Option<u64>,
+    // Only known for entries in the index
+    oid: Option<Oid>,
+    // The combined status of everything under a directory
+    status: Status,
+}
+
+impl WorkdirEntry {
+    fn display_kind(&self) -> &'static str {
+        match self.kind {
+            EntryKind::Tree => "tree",
+            EntryKind::Blob => "blob",
+            EntryKind::Symlink => "link",
+            EntryKind::Submodule => "submodule",
+        }
+    }
+
+    /// Status markers in the style of `git status --short`, with what is
+    /// staged first and what is modified in the working tree second.
+    fn display_status(&self) -> Vec<Span<'static>> {
+        let status = self.status;
+        if status.is_empty() {
+            return vec![Span::raw("   ")];
+        }
+        if status.contains(Status::CONFLICTED) {
+            return vec![Span::styled("UU ", Style::default().fg(Color::Red))];
+        }
+        if status == Status::WT_NEW {
+            return vec![Span::styled("?? ", Style::default().fg(Color::Red))];
+        }
+        let staged = if status.contains(Status::INDEX_NEW) {
+            "A"
+        } else if status.contains(Status::INDEX_MODIFIED) {
+            "M"
+        } else if status.contains(Status::INDEX_DELETED) {
+            "D"
+        } else if status.contains(Status::INDEX_RENAMED) {
+            "R"
+        } else if status.contains(Status::INDEX_TYPECHANGE) {
+            "T"
+        } else {
+            " "
+        };
+        let modified = if status.contains(Status::WT_MODIFIED) {
+            "M"
+        } else if status.contains(Status::WT_DELETED) {
+            "D"
+        } else if status.contains(Status::WT_RENAMED) {
+            "R"
+        } else if status.contains(Status::WT_TYPECHANGE) {
+            "T"
+        } else if status.contains(Status::WT_NEW) {
+            "?"
+        } else {
+            " "
+        };
+        vec![
+            Span::styled(staged, Style::default().fg(Color::Green)),
+            Span::styled(format!("{} ", modified), Style::default().fg(Color::Red)),
+        ]
+    }
+}
+
+/// What was selected on a `WorkdirPage`.
+pub enum WorkdirSelection {
+    Directory(String),
+    File(String, BlobContent),
+}
+
+/// Lists a directory of the index or the working tree.
+pub struct WorkdirPage<'repo> {
+    repo: &'repo Repository,
+    revision: PseudoRevision,
+    // Relative to the top of the working tree, and empty at the top
+    path: String,
+    entries: Vec<WorkdirEntry>,
+    mailbox: Mailbox,
+    _cancel: CancelToken,
+    selected_index: usize,
+    name: String,
+}
+
+impl<'repo> WorkdirPage<'repo> {
+    pub fn new(
+        repo: &'repo Repository,
+        revision: PseudoRevision,
+        path: String,
+        name: String,
+        workers: &WorkerHandle,
+    ) -> Result<WorkdirPage<'repo>, GitBrowserError> {
+        let mut page = WorkdirPage {
+            repo,
+            revision,
+            path,
+            entries: vec![],
+            mailbox: Mailbox::new(),
+            _cancel: CancelToken::new(),
+            selected_index: 0,
+            name,
+        };
+        page.reload(workers)?;
+        Ok(page)
+    }
+
+    pub fn revision(&self) -> PseudoRevision {
+        self.revision
+    }
+
+    /// List the directory again, keeping the same entry selected if it
+    /// still exists.
+    pub fn reload(&mut self, workers: &WorkerHandle) -> Result<(), GitBrowserError> {
+        let selected = self.selected_item();
+        self.entries = match self.revision {
+            PseudoRevision::Index => self.index_entries()?,
+            PseudoRevision::Worktree => self.worktree_entries()?,
+        };
+        self.selected_index = self
+            .entries
+            .iter()
+            .position(|entry| entry.name == selected)
+            .unwrap_or(0);
+
+        // Statuses from before the reload are out of date
+        self._cancel = CancelToken::new();
+        self.mailbox = Mailbox::new();
+        workers.submit_to(
+            Task::Statuses {
+                path: self.path.to_string(),
+            },
+            Some(&self._cancel),
+            &self.mailbox,
+        );
+        Ok(())
+    }
+
+    /// Take the statuses that have been loaded since last time, returning
+    /// what failed to load.
+    pub fn receive_results(&mut self) -> Vec<GitBrowserError> {
+        let mut failures = vec![];
+        let results: Vec<TaskResult> = self.mailbox.results().collect();
+        for result in results {
+            match result {
+                TaskResult::Statuses(statuses) => self.receive_statuses(statuses),
+                TaskResult::Failed(e) => failures.push(e),
+                _ => {}
+            }
+        }
+        failures
+    }
+
+    /// Apply statuses of paths under this directory to its entries.
+    fn receive_statuses(&mut self, statuses: Vec<(String, Status)>) {
+        let prefix = if self.path.is_empty() {
+            "".to_string()
+        } else {
+            format!("{}/", self.path)
+        };
+        let mut combined: BTreeMap<&str, Status> = BTreeMap::new();
+        for (path, status) in &statuses {
+            if let Some(relative) = path.strip_prefix(&prefix) {
+                let name = relative.split('/').next().unwrap_or(relative);
+                *combined.entry(name).or_insert(Status::empty()) |= *status;
+            }
+        }
+        for entry in &mut self.entries {
+            entry.status = combined
+                .get(entry.name.as_str())
+                .copied()
+                .unwrap_or(Status::empty());
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn entry_path(&self, name: &str) -> String {
+        if self.path.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", self.path, name)
+        }
+    }
+
+    fn index_entries(&self) -> Result<Vec<WorkdirEntry>, GitBrowserError> {
+        let error = |e| GitBrowserError::git(ErrorKind::Index, e);
+        let mut index = self.repo.index().map_err(error)?;
+        // The repository keeps the index it loaded first, which is stale once
+        // something else has staged changes
+        index.read(false).map_err(error)?;
+        let prefix = if self.path.is_empty() {
+            "".to_string()
+        } else {
+            format!("{}/", self.path)
+        };
+        let mut entries: BTreeMap<String, WorkdirEntry> = BTreeMap::new();
+        for entry in index.iter() {
+            let path = String::from_utf8_lossy(&entry.path);
+            let relative = match path.strip_prefix(&prefix) {
+                Some(relative) => relative,
+                None => continue,
+            };
+            let (name, kind) = match relative.split_once('/') {
+                Some((directory, _)) => (directory, EntryKind::Tree),
+                None if entry.mode == GITLINK_MODE => (relative, EntryKind::Submodule),
+                None if entry.mode == SYMLINK_MODE => (relative, EntryKind::Symlink),
+                None => (relative, EntryKind::Blob),
+            };
+            // Conflicted paths have an entry for each side
+            entries
+                .entry(name.to_string())
+                .or_insert_with(|| WorkdirEntry {
+                    name: name.to_string(),
+                    kind,
+                    size: (kind != EntryKind::Tree).then_some(entry.file_size.into()),
+                    oid: (kind != EntryKind::Tree).then_some(entry.id),
+                    status: Status::empty(),
+                });
+        }
+        Ok(entries.into_values().collect())
+    }
+
+    fn worktree_entries(&self) -> Result<Vec<WorkdirEntry>, GitBrowserError> {
+        let directory = self.worktree_path(&self.path)?;
+        let error =
+            |e| GitBrowserError::io(ErrorKind::Worktree, e).with_path(directory.to_string_lossy());
+        let mut entries = vec![];
+        for dir_entry in fs::read_dir(&directory).map_err(error)? {
+            let dir_entry = dir_entry.map_err(error)?;
+            let name = dir_entry.file_name().to_string_lossy().to_string();
+            if name == ".git" {
+                continue;
+            }
+            let file_type = dir_entry.file_type().map_err(error)?;
+            let kind = if file_type.is_symlink() {
+                EntryKind::Symlink
+            } else if file_type.is_dir() {
+                if dir_entry.path().join(".git").exists() {
+                    EntryKind::Submodule
+                } else {
+                    EntryKind::Tree
+                }
+            } else {
+                EntryKind::Blob
+            };
+            let size = match kind {
+                EntryKind::Blob => dir_entry.metadata().ok().map(|m| m.len()),
+                _ => None,
+            };
+            entries.push(WorkdirEntry {
+                name,
+                kind,
+                size,
+                oid: None,
+                status: Status::empty(),
+            });
+        }
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(entries)
+    }
+
+    fn worktree_path(&self, path: &str) -> Result<PathBuf, GitBrowserError> {
+        let workdir = self.repo.workdir().ok_or_else(|| {
+            GitBrowserError::message(ErrorKind::Worktree, "Repository has no working tree")
+        })?;
+        Ok(workdir.join(path))
+    }
+
+    /// The selected entry, with its content if it is a file.
+    pub fn selection(&self) -> Result<Option<WorkdirSelection>, GitBrowserError> {
+        let entry = match self.entries.get(self.selected_index) {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        let path = self.entry_path(&entry.name);
+        let content = match (entry.kind, self.revision, entry.oid) {
+            (EntryKind::Tree, _, _) => {
+                return Ok(Some(WorkdirSelection::Directory(entry.name.to_string())))
+            }
+            (EntryKind::Submodule, _, _) => return Ok(None),
+            (_, PseudoRevision::Index, Some(oid)) => {
+                let blob = self.repo.find_blob(oid).map_err(|e| {
+                    GitBrowserError::git(ErrorKind::BlobReference, e)
+                        .with_oid(oid)
+                        .with_path(&path)
+                })?;
+                BlobContent::new(self.repo, oid, blob.content())?
+            }
+            (_, PseudoRevision::Index, None) => return Ok(None),
+            (kind, PseudoRevision::Worktree, _) => {
+                let file = self.worktree_path(&path)?;
+                let error = |e| {
+                    GitBrowserError::io(ErrorKind::Worktree, e).with_path(file.to_string_lossy())
+                };
+                // Like git, show the target of a link rather than following it
+                let data = match kind {
+                    EntryKind::Symlink => fs::read_link(&file)
+                        .map_err(error)?
+                        .to_string_lossy()
+                        .as_bytes()
+                        .to_vec(),
+                    _ => fs::read(&file).map_err(error)?,
+                };
+                BlobContent::from_file(self.repo, &data)?
+            }
+        };
+        Ok(Some(WorkdirSelection::File(
+            entry.name.to_string(),
+            content,
+        )))
+    }
+}
+
+impl<'repo> Drawable<'repo> for WorkdirPage<'repo> {
+    fn draw(&self, f: &mut Frame, area: Rect, content_block: Block) -> Rect {
+        let viewport = content_block.inner(area);
+
+        let visible = viewport.height;
+        let (_page, _pages, page_start_index) =
+            pagination(self.len(), visible.into(), self.selected_index);
+
+        let list_items: Vec<ListItem> = self
+            .entries
+            .iter()
+            .enumerate()
+            .skip(page_start_index)
+            .take(visible.into())
+            .map(|(index, entry)| {
+                let (name, fg, modifier) = match entry.kind {
+                    EntryKind::Tree => (format!("{}/", entry.name), Color::Blue, Modifier::BOLD),
+                    _ => (entry.name.to_string(), Color::Gray, Modifier::empty()),
+                };
+                let bg = if index == self.selected_index {
+                    Color::Cyan
+                } else {
+                    Color::Reset
+                };
+                let mut spans = vec![
+                    Span::styled(
+                        format!("{:10}", entry.display_kind()),
+                        Style::default().fg(Color::Gray).add_modifier(Modifier::DIM),
+                    ),
+                    Span::styled(
+                        format!("{:>7} ", entry.size.map(format_size).unwrap_or_default()),
+                        Style::default().fg(Color::Gray).add_modifier(Modifier::DIM),
+                    ),
+                ];
+                spans.extend(entry.display_status());
+                spans.push(Span::styled(
+                    name,
+                    Style::default().fg(fg).bg(bg).add_modifier(modifier),
+                ));
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+        let content = List::new(list_items).block(content_block);
+        f.render_widget(content, area);
+        viewport
+    }
+
+    fn title(&self) -> String {
+        self.name.to_string()
+    }
+}
+
+impl<'repo> Navigable<'repo> for WorkdirPage<'repo> {
+    fn home(&mut self, _page_size: u16) {
+        self.selected_index = 0;
+    }
+
+    fn end(&mut self, _page_size: u16) {
+        self.selected_index = self.len().saturating_sub(1);
+    }
+
+    fn pagedown(&mut self, page_size: u16) {
+        let h: usize = page_size.into();
+        let index = self.selected_index.saturating_add(h);
+        self.selected_index = index.min(self.len().saturating_sub(1));
+    }
+
+    fn pageup(&mut self, page_size: u16) {
+        let h: usize = page_size.into();
+        self.selected_index = self.selected_index.saturating_sub(h);
+    }
+
+    fn next_selection(&mut self) {
+        if self.selected_index + 1 < self.len() {
+            self.selected_index += 1;
+        } else {
+            self.selected_index = 0;
+        }
+    }
+
+    fn previous_selection(&mut self) {
+        if self.selected_index > 0 {
+            self.selected_index -= 1;
+        } else {
+            self.selected_index = self.len().saturating_sub(1);
+        }
+    }
+
+    fn select(&self) -> Result<Option<(Object<'repo>, String)>, GitBrowserError> {
+        // Entries aren't necessarily objects, so the app uses `selection`
+        Ok(None)
+    }
+
+    fn selected_item(&self) -> String {
+        self.entries
+            .get(self.selected_index)
+            .map(|entry| entry.name.to_string())
+            .unwrap_or_default()
+    }
+
+    fn next_tick(&mut self, _block: bool) -> Result<(), GitBrowserError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,539 @@
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, Scope};
+
+use git2::{Oid, Repository, Status, StatusOptions};
+
+use syntect::highlighting::{Highlighter, Theme};
+use syntect::parsing::SyntaxSet;
+
+use crate::app::entry_metadata::EntryMetadata;
+use crate::app::highlight::{Checkpoints, HighlightedLine, SourceLines};
+use crate::app::history_filter::HistoryFilter;
+use crate::app::log_page::{HistoryReader, LogEntry};
+use crate::app::pickaxe::{self, PickaxeMatch, PickaxeQuery};
+use crate::app::refs_page::{load_refs, RefEntry};
+use crate::app::signature::SignatureFormatter;
+use crate::app::signing::{self, SigningMethod, Verification};
+use crate::app::stash_page::{load_stashes, stash_diff, StashEntry};
+use crate::errors::{ErrorKind, GitBrowserError};
+
+/// The most worker threads to start, regardless of available parallelism.
+const MAX_WORKERS: usize = 4;
+
+/// Expensive work that is done away from the UI thread.
+pub enum Task {
+    EntryMetadata {
+        tree: Oid,
+        entries: Range<usize>,
+    },
+    /// Highlight ranges of lines in a blob as they are requested, parsing
+    /// ahead in between requests. This runs on its own thread until the
+    /// sender of the requests is dropped.
+    Highlight {
+        blob: Oid,
+        content: Arc<[u8]>,
+        syntax: String,
+        requests: Receiver<Range<usize>>,
+    },
+    /// Read the history of a revision a batch at a time as batches are
+    /// requested. This runs on its own thread until the sender of the
+    /// requests is dropped or the end of the history is reached.
+    Log {
+        revision: String,
+        filter: HistoryFilter,
+        requests: Receiver<()>,
+    },
+    LoadRefs,
+    LoadStashes,
+    /// Format the changes saved in a stash entry as a patch.
+    StashDiff {
+        id: Oid,
+        name: String,
+    },
+    /// Search the history for commits changing a string or regex,
+    /// reporting matches as they are found until cancelled. Searching long
+    /// histories takes a while, so this runs on its own thread.
+    Pickaxe(PickaxeQuery),
+    /// Check a commit or tag signature with `gpg` or `ssh-keygen`.
+    VerifySignature {
+        id: Oid,
+        method: SigningMethod,
+        block: String,
+        signed_data: Vec<u8>,
+    },
+    /// The status of every file under a directory of the working tree.
+    Statuses {
+        path: String,
+    },
+}
+
+pub enum TaskResult {
+    EntryMetadata(Vec<(usize, EntryMetadata)>),
+    Highlight {
+        syntax: String,
+        start: usize,
+        lines: Vec<HighlightedLine>,
+    },
+    Refs(Vec<RefEntry>),
+    Log {
+        entries: Vec<LogEntry>,
+        finished: bool,
+    },
+    Pickaxe {
+        matches: Vec<PickaxeMatch>,
+        searched: usize,
+        finished: bool,
+    },
+    Stashes(Vec<StashEntry>),
+    StashDiff {
+        name: String,
+        diff: Vec<u8>,
+    },
+    Verification {
+        id: Oid,
+        verification: Verification,
+    },
+    Statuses(Vec<(String, Status)>),
+    Failed(GitBrowserError),
+}
+
+impl Task {
+    /// Whether the task runs for as long as it is wanted, or for long enough
+    /// that it would hold on to a worker of the pool.
+    fn is_session(&self) -> bool {
+        matches!(
+            self,
+            Task::Highlight { .. } | Task::Log { .. } | Task::Pickaxe(_)
+        )
+    }
+}
+
+struct Job {
+    task: Task,
+    cancelled: Option<Arc<AtomicBool>>,
+    // Where the results go, when not to the pool's results
+    results: Option<Sender<TaskResult>>,
+}
+
+struct Queue {
+    // Jobs are taken from the end so that the most recent requests, which
+    // are usually for whatever is on screen, are served first
+    jobs: Mutex<Vec<Job>>,
+    available: Condvar,
+    closed: AtomicBool,
+    pending: AtomicUsize,
+}
+
+/// Cancels the jobs submitted with it when dropped.
+#[derive(Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+}
+
+impl Drop for CancelToken {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Where the results of the tasks submitted with it arrive, so that each
+/// page takes its own results rather than the app handing them out.
+pub struct Mailbox {
+    sender: Sender<TaskResult>,
+    receiver: Receiver<TaskResult>,
+}
+
+impl Default for Mailbox {
+    fn default() -> Mailbox {
+        let (sender, receiver) = channel();
+        Mailbox { sender, receiver }
+    }
+}
+
+impl Mailbox {
+    pub fn new() -> Mailbox {
+        Mailbox::default()
+    }
+
+    /// Results that have arrived, without blocking.
+    pub fn results(&self) -> impl Iterator<Item = TaskResult> + '_ {
+        self.receiver.try_iter()
+    }
+}
+
+/// Used by pages to submit work to the pool.
+#[derive(Clone)]
+pub struct WorkerHandle {
+    queue: Arc<Queue>,
+    // Sessions are given threads of their own, so that they can't keep
+    // the pool from other work
+    sessions: Sender<Job>,
+}
+
+impl WorkerHandle {
+    /// Submit a task whose results go to the pool's results.
+    pub fn submit(&self, task: Task, cancel: Option<&CancelToken>) {
+        self.push(task, cancel, None);
+    }
+
+    /// Submit a task whose results go to a mailbox. Errors still go to the
+    /// pool's results.
+    pub fn submit_to(&self, task: Task, cancel: Option<&CancelToken>, mailbox: &Mailbox) {
+        self.push(task, cancel, Some(mailbox.sender.clone()));
+    }
+
+    fn push(&self, task: Task, cancel: Option<&CancelToken>, results: Option<Sender<TaskResult>>) {
+        self.queue.pending.fetch_add(1, Ordering::Relaxed);
+        let job = Job {
+            task,
+            cancelled: cancel.map(|token| token.0.clone()),
+            results,
+        };
+        if job.task.is_session() {
+            if self.sessions.send(job).is_err() {
+                // The pool is shutting down
+                self.queue.pending.fetch_sub(1, Ordering::Relaxed);
+            }
+            return;
+        }
+        self.queue
+            .jobs
+            .lock()
+            .expect("worker queue poisoned")
+            .push(job);
+        self.queue.available.notify_one();
+    }
+
+    /// The number of submitted jobs that have not finished yet.
+    pub fn pending(&self) -> usize {
+        self.queue.pending.load(Ordering::Relaxed)
+    }
+}
+
+pub struct WorkerPool {
+    handle: WorkerHandle,
+    results: Receiver<TaskResult>,
+}
+
+impl WorkerPool {
+    pub fn new<'scope, 'env>(
+        scope: &'scope Scope<'scope, 'env>,
+        repo_path: &Path,
+        syntax_set: &'env SyntaxSet,
+        theme: &'env Theme,
+    ) -> WorkerPool {
+        let queue = Arc::new(Queue {
+            jobs: Mutex::new(vec![]),
+            available: Condvar::new(),
+            closed: AtomicBool::new(false),
+            pending: AtomicUsize::new(0),
+        });
+        let (sender, results) = channel();
+        let workers = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .min(MAX_WORKERS);
+        let worker = Worker {
+            queue: queue.clone(),
+            results: sender,
+            repo_path: repo_path.to_path_buf(),
+            syntax_set,
+            theme,
+        };
+        for _ in 0..workers {
+            let worker = worker.clone();
+            scope.spawn(move || worker.run());
+        }
+
+        // Start a thread for each session until every handle is dropped
+        let (sessions, session_jobs) = channel::<Job>();
+        scope.spawn(move || {
+            for job in session_jobs {
+                let worker = worker.clone();
+                scope.spawn(move || {
+                    let mut repo = worker.open_repository();
+                    worker.run_job(&mut repo, job);
+                });
+            }
+        });
+
+        WorkerPool {
+            handle: WorkerHandle { queue, sessions },
+            results,
+        }
+    }
+
+    pub fn handle(&self) -> &WorkerHandle {
+        &self.handle
+    }
+
+    pub fn pending(&self) -> usize {
+        self.handle.pending()
+    }
+
+    /// Results of finished work, without blocking.
+    pub fn results(&self) -> impl Iterator<Item = TaskResult> + '_ {
+        self.results.try_iter()
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        let queue = &self.handle.queue;
+        // Workers check the flag with the lock held before waiting, so
+        // holding it here means none of them can miss the wakeup
+        let _jobs = queue.jobs.lock();
+        queue.closed.store(true, Ordering::Relaxed);
+        queue.available.notify_all();
+    }
+}
+
+#[derive(Clone)]
+struct Worker<'env> {
+    queue: Arc<Queue>,
+    results: Sender<TaskResult>,
+    repo_path: PathBuf,
+    syntax_set: &'env SyntaxSet,
+    theme: &'env Theme,
+}
+
+impl<'env> Worker<'env> {
+    fn run(self) {
+        let mut repo = self.open_repository();
+        while let Some(job) = self.next_job() {
+            self.run_job(&mut repo, job);
+        }
+    }
+
+    /// Repositories can't be shared between threads, so each thread opens
+    /// its own.
+    fn open_repository(&self) -> Result<Repository, GitBrowserError> {
+        Repository::open(&self.repo_path).map_err(|e| {
+            GitBrowserError::git(ErrorKind::OpenRepository, e)
+                .with_path(self.repo_path.to_string_lossy())
+        })
+    }
+
+    fn run_job(&self, repo: &mut Result<Repository, GitBrowserError>, job: Job) {
+        let cancelled = job.cancelled.unwrap_or_default();
+        let results = job.results.unwrap_or_else(|| self.results.clone());
+        if !cancelled.load(Ordering::Relaxed) {
+            let result = match repo {
+                Ok(repo) => self.perform(repo, job.task, &cancelled, &results),
+                Err(e) => Err(GitBrowserError::message(e.kind(), e.cause())),
+            };
+            if let Err(e) = result {
+                // Failures go to whoever asked, who may be waiting on them
+                let _ = results.send(TaskResult::Failed(e));
+            }
+        }
+        self.queue.pending.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    fn next_job(&self) -> Option<Job> {
+        let mut jobs = self.queue.jobs.lock().ok()?;
+        loop {
+            if self.queue.closed.load(Ordering::Relaxed) {
+                return None;
+            }
+            if let Some(job) = jobs.pop() {
+                return Some(job);
+            }
+            jobs = self.queue.available.wait(jobs).ok()?;
+        }
+    }
+
+    fn perform(
+        &self,
+        repo: &mut Repository,
+        task: Task,
+        cancelled: &AtomicBool,
+        results: &Sender<TaskResult>,
+    ) -> Result<(), GitBrowserError> {
+        let send = |result| {
+            // The receiver goes away when the page that asked is closed
+            let _ = results.send(result);
+        };
+        match task {
+            Task::EntryMetadata {
+                tree: tree_id,
+                entries,
+            } => {
+                let tree = repo.find_tree(tree_id).map_err(|e| {
+                    GitBrowserError::git(ErrorKind::TreeReference, e).with_oid(tree_id)
+                })?;
+                let mut metadata = vec![];
+                let mut error = None;
+                for index in entries {
+                    if let Some(entry) = tree.get(index) {
+                        match EntryMetadata::load(repo, &entry) {
+                            Ok(m) => metadata.push((index, m)),
+                            Err(e) => {
+                                // Don't retry entries that failed to load
+                                metadata.push((index, EntryMetadata::default()));
+                                error = Some(e);
+                            }
+                        }
+                    }
+                }
+                send(TaskResult::EntryMetadata(metadata));
+                match error {
+                    Some(e) => Err(e),
+                    None => Ok(()),
+                }
+            }
+            Task::Highlight {
+                blob: blob_id,
+                content,
+                syntax,
+                requests,
+            } => {
+                let content = blob_text(&content, blob_id)?;
+                let syntax_name = syntax;
+                let syntax = self.find_syntax(&syntax_name, blob_id)?;
+                let highlighter = Highlighter::new(self.theme);
+                let mut checkpoints =
+                    Checkpoints::new(SourceLines::new(content), syntax, self.syntax_set);
+                while !cancelled.load(Ordering::Relaxed) {
+                    let request = match requests.try_recv() {
+                        Ok(request) => request,
+                        Err(TryRecvError::Disconnected) => break,
+                        Err(TryRecvError::Empty) if !checkpoints.is_complete() => {
+                            // Keep parsing ahead so that later jumps are quick
+                            checkpoints.step().map_err(|e| e.with_oid(blob_id))?;
+                            continue;
+                        }
+                        Err(TryRecvError::Empty) => {
+                            // Nothing to do until the next request, so don't
+                            // count as pending work while waiting
+                            self.queue.pending.fetch_sub(1, Ordering::Relaxed);
+                            let request = requests.recv();
+                            self.queue.pending.fetch_add(1, Ordering::Relaxed);
+                            match request {
+                                Ok(request) => request,
+                                Err(_) => break,
+                            }
+                        }
+                    };
+                    // Only the most recent request is still on screen
+                    let request = requests.try_iter().last().unwrap_or(request);
+                    let lines = checkpoints
+                        .highlight(request.clone(), &highlighter)
+                        .map_err(|e| e.with_oid(blob_id))?;
+                    send(TaskResult::Highlight {
+                        syntax: syntax_name.to_string(),
+                        start: request.start,
+                        lines,
+                    });
+                }
+                Ok(())
+            }
+            Task::Pickaxe(query) => {
+                pickaxe::search(repo, &query, cancelled, |matches, searched, finished| {
+                    send(TaskResult::Pickaxe {
+                        matches,
+                        searched,
+                        finished,
+                    })
+                })
+            }
+            Task::Log {
+                revision,
+                filter,
+                requests,
+            } => {
+                // A broken mailmap has already been reported by the app, so
+                // names are shown as recorded
+                let people =
+                    SignatureFormatter::load(repo).unwrap_or_else(|_| SignatureFormatter::empty());
+                let mut reader = HistoryReader::new(repo, revision, people, filter)?;
+                loop {
+                    // Nothing to do until the next request, so don't count as
+                    // pending work while waiting
+                    self.queue.pending.fetch_sub(1, Ordering::Relaxed);
+                    let request = requests.recv();
+                    self.queue.pending.fetch_add(1, Ordering::Relaxed);
+                    if request.is_err() || cancelled.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let (entries, finished) = reader.read_batch(cancelled)?;
+                    send(TaskResult::Log { entries, finished });
+                    if finished {
+                        break;
+                    }
+                }
+                Ok(())
+            }
+            Task::LoadRefs => {
+                send(TaskResult::Refs(load_refs(repo)?));
+                Ok(())
+            }
+            Task::LoadStashes => {
+                send(TaskResult::Stashes(load_stashes(repo)?));
+                Ok(())
+            }
+            Task::StashDiff { id, name } => {
+                let diff = stash_diff(repo, id, &name)?;
+                send(TaskResult::StashDiff { name, diff });
+                Ok(())
+            }
+            Task::VerifySignature {
+                id,
+                method,
+                block,
+                signed_data,
+            } => {
+                let config = repo
+                    .config()
+                    .map_err(|e| GitBrowserError::git(ErrorKind::Signature, e).with_oid(id))?;
+                let verification = signing::verify(&config, method, &block, &signed_data);
+                send(TaskResult::Verification { id, verification });
+                Ok(())
+            }
+            Task::Statuses { path } => {
+                let mut options = StatusOptions::new();
+                options
+                    .include_untracked(true)
+                    .recurse_untracked_dirs(true)
+                    .renames_head_to_index(true);
+                if !path.is_empty() {
+                    // Directory names are paths, not patterns
+                    options.pathspec(&path).disable_pathspec_match(true);
+                }
+                let statuses = repo
+                    .statuses(Some(&mut options))
+                    .map_err(|e| GitBrowserError::git(ErrorKind::Status, e).with_path(&path))?;
+                let statuses = statuses
+                    .iter()
+                    .filter_map(|entry| Some((entry.path()?.to_string(), entry.status())))
+                    .collect();
+                send(TaskResult::Statuses(statuses));
+                Ok(())
+            }
+        }
+    }
+
+    fn find_syntax(
+        &self,
+        name: &str,
+        blob: Oid,
+    ) -> Result<&'env syntect::parsing::SyntaxReference, GitBrowserError> {
+        self.syntax_set.find_syntax_by_name(name).ok_or_else(|| {
+            GitBrowserError::message(ErrorKind::Highlight, format!("Unknown syntax {}", name))
+                .with_oid(blob)
+        })
+    }
+}
+
+fn blob_text(content: &[u8], oid: Oid) -> Result<&str, GitBrowserError> {
+    std::str::from_utf8(content)
+        .map_err(|e| GitBrowserError::message(ErrorKind::Encoding, e.to_string()).with_oid(oid))
+}
@@ -0,0 +1,307 @@
+use std::io::{self, Write};
+
+use chrono::{DateTime, FixedOffset};
+
+use clap::ValueEnum;
+
+use git2::{Object, ObjectType, Repository, Signature, Tree, TreeEntry};
+
+use serde::Serialize;
+
+use syntect::easy::HighlightLines;
+use syntect::highlighting::Theme;
+use syntect::parsing::SyntaxSet;
+use syntect::util::{as_24_bit_terminal_escaped, LinesWithEndings};
+
+use gitbrowser_rs::errors::{ErrorKind, GitBrowserError};
+use gitbrowser_rs::scripting::{
+    attribute, detect_syntax, load_refs, looks_binary, walk, AttributeValue, BlobContent, Display,
+    EntryMetadata, SignatureFormatter,
+};
+
+/// How listings are printed.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// Columns like those shown in the TUI
+    #[default]
+    Text,
+    /// A JSON array, for scripts
+    Json,
+}
+
+#[derive(Serialize)]
+struct EntryRecord {
+    name: String,
+    path: String,
+    mode: String,
+    #[serde(rename = "type")]
+    kind: Option<&'static str>,
+    id: String,
+    size: Option<usize>,
+    /// `None` if the blob was too large to check.
+    binary: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct RefRecord {
+    name: String,
+    kind: &'static str,
+    /// The object the reference ultimately points to.
+    target: Option<String>,
+}
+
+#[derive(Serialize)]
+struct PersonRecord {
+    name: String,
+    email: String,
+    time: String,
+}
+
+#[derive(Serialize)]
+struct CommitRecord {
+    id: String,
+    tree: String,
+    parents: Vec<String>,
+    author: PersonRecord,
+    committer: PersonRecord,
+    summary: String,
+    message: String,
+}
+
+/// List a tree given as `REV[:PATH]`, or the single entry if the path is a
+/// blob, with the same kind and size shown in the TUI.
+pub fn ls(repo: &Repository, spec: &str, format: OutputFormat) -> Result<(), GitBrowserError> {
+    let object = resolve(repo, spec)?;
+    let (root, path) = root_and_path(repo, spec);
+    let mut entries = vec![];
+    if object.kind() == Some(ObjectType::Blob) {
+        let entry = root
+            .ok_or_else(|| {
+                GitBrowserError::message(ErrorKind::TreeEntry, "Blobs are listed as REV:PATH")
+                    .with_path(spec)
+            })?
+            .get_path(path.as_ref())
+            .map_err(|e| GitBrowserError::git(ErrorKind::TreeEntry, e).with_path(spec))?;
+        let metadata = EntryMetadata::load(repo, &entry)?;
+        entries.push((entry, metadata, path));
+    } else {
+        let tree = object.peel_to_tree().map_err(|e| {
+            GitBrowserError::git(ErrorKind::TreeReference, e)
+                .with_oid(object.id())
+                .with_path(spec)
+        })?;
+        for entry in tree.iter() {
+            let metadata = EntryMetadata::load(repo, &entry)?;
+            let name = String::from_utf8_lossy(entry.name_bytes()).to_string();
+            let entry_path = match path.as_str() {
+                "" => name,
+                path => format!("{}/{}", path, name),
+            };
+            entries.push((entry.to_owned(), metadata, entry_path));
+        }
+    }
+
+    let listing = match format {
+        OutputFormat::Text => entries
+            .iter()
+            .map(|(entry, metadata, _path)| entry_line(entry, metadata))
+            .collect(),
+        OutputFormat::Json => json(
+            &entries
+                .iter()
+                .map(|(entry, metadata, path)| EntryRecord {
+                    name: String::from_utf8_lossy(entry.name_bytes()).to_string(),
+                    path: path.to_string(),
+                    mode: format!("{:06o}", entry.filemode()),
+                    kind: entry.kind().map(|kind| kind.str()),
+                    id: entry.id().to_string(),
+                    size: metadata.size,
+                    binary: metadata.binary,
+                })
+                .collect::<Vec<_>>(),
+        )?,
+    };
+    output(io::stdout().lock().write_all(listing.as_bytes()))
+}
+
+/// List the references along with the objects they point to, as in the
+/// refs page.
+pub fn refs(repo: &Repository, format: OutputFormat) -> Result<(), GitBrowserError> {
+    let records: Vec<RefRecord> = load_refs(repo)?
+        .into_iter()
+        // The index and working tree are only pages of the TUI
+        .filter(|entry| entry.pseudo_revision().is_none())
+        .map(|entry| RefRecord {
+            kind: entry.kind(),
+            target: entry.target.map(|target| target.to_string()),
+            name: entry.name,
+        })
+        .collect();
+
+    let listing = match format {
+        OutputFormat::Text => records
+            .iter()
+            .map(|record| {
+                format!(
+                    "{:40} {:7} {}\n",
+                    record.target.as_deref().unwrap_or("-"),
+                    record.kind,
+                    record.name
+                )
+            })
+            .collect(),
+        OutputFormat::Json => json(&records)?,
+    };
+    output(io::stdout().lock().write_all(listing.as_bytes()))
+}
+
+/// List the history of a revision newest first, with people named through
+/// the mailmap as in the log page.
+pub fn log(
+    repo: &Repository,
+    revision: &str,
+    max_count: Option<usize>,
+    format: OutputFormat,
+) -> Result<(), GitBrowserError> {
+    let people = SignatureFormatter::load(repo)?;
+    let mut records = vec![];
+    for id in walk(repo, revision)?.take(max_count.unwrap_or(usize::MAX)) {
+        let id = id.map_err(|e| GitBrowserError::git(ErrorKind::History, e).with_path(revision))?;
+        let commit = repo
+            .find_commit(id)
+            .map_err(|e| GitBrowserError::git(ErrorKind::CommitReference, e).with_oid(id))?;
+        records.push(CommitRecord {
+            id: id.to_string(),
+            tree: commit.tree_id().to_string(),
+            parents: commit
+                .parent_ids()
+                .map(|parent| parent.to_string())
+                .collect(),
+            author: person(&people, &commit.author()),
+            committer: person(&people, &commit.committer()),
+            summary: String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default())
+                .to_string(),
+            message: String::from_utf8_lossy(commit.message_bytes()).to_string(),
+        });
+    }
+
+    let listing = match format {
+        OutputFormat::Text => records
+            .iter()
+            .map(|record| format!("{} {}\n", record.id, record.summary))
+            .collect(),
+        OutputFormat::Json => json(&records)?,
+    };
+    output(io::stdout().lock().write_all(listing.as_bytes()))
+}
+
+/// Print a blob given as `REV:PATH`, resolving Git LFS pointers. Text can
+/// be highlighted with ANSI escapes using the syntax the TUI would pick.
+pub fn cat(
+    repo: &Repository,
+    spec: &str,
+    highlight: Option<(&SyntaxSet, &Theme)>,
+) -> Result<(), GitBrowserError> {
+    let object = resolve(repo, spec)?;
+    let blob = object.peel_to_blob().map_err(|e| {
+        GitBrowserError::git(ErrorKind::BlobReference, e)
+            .with_oid(object.id())
+            .with_path(spec)
+    })?;
+    // Unlike the pager, print all of an LFS object
+    let content = BlobContent::with_limit(repo, blob.id(), blob.content(), u64::MAX)?;
+
+    let text = match highlight {
+        Some(_) if looks_binary(&content.data) => None,
+        Some(_) => std::str::from_utf8(&content.data).ok(),
+        None => None,
+    };
+    match (highlight, text) {
+        (Some((syntax_set, theme)), Some(text)) => {
+            let (root, path) = root_and_path(repo, spec);
+            let language =
+                root.and_then(
+                    |root| match attribute(repo, &root, &path, "linguist-language") {
+                        Ok(Some(AttributeValue::Value(language))) => Some(language),
+                        _ => None,
+                    },
+                );
+            let name = path.rsplit('/').next().unwrap_or_default();
+            let syntax = detect_syntax(syntax_set, name, text, language.as_deref())
+                .unwrap_or_else(|| syntax_set.find_syntax_plain_text());
+            let mut highlighter = HighlightLines::new(syntax, theme);
+            let mut highlighted = String::new();
+            for line in LinesWithEndings::from(text) {
+                let ranges = highlighter.highlight_line(line, syntax_set).map_err(|e| {
+                    GitBrowserError::message(ErrorKind::Highlight, e.to_string())
+                        .with_oid(content.id)
+                        .with_path(spec)
+                })?;
+                highlighted.push_str(&as_24_bit_terminal_escaped(&ranges, false));
+            }
+            // Don't leave the terminal coloured after the last line
+            highlighted.push_str("\x1b[0m");
+            output(io::stdout().lock().write_all(highlighted.as_bytes()))
+        }
+        _ => output(io::stdout().lock().write_all(&content.data)),
+    }
+}
+
+fn resolve<'repo>(repo: &'repo Repository, spec: &str) -> Result<Object<'repo>, GitBrowserError> {
+    repo.revparse_single(spec)
+        .map_err(|e| GitBrowserError::git(ErrorKind::Revision, e).with_path(spec))
+}
+
+/// The tree of the revision in a `REV:PATH` spec along with the path, as
+/// the attributes and name of a blob come from where it is in the tree.
+fn root_and_path<'repo>(repo: &'repo Repository, spec: &str) -> (Option<Tree<'repo>>, String) {
+    match spec.split_once(':') {
+        Some((revision, path)) => {
+            let root = repo
+                .revparse_single(revision)
+                .and_then(|object| object.peel_to_tree())
+                .ok();
+            (root, path.trim_matches('/').to_string())
+        }
+        None => (None, "".to_string()),
+    }
+}
+
+fn entry_line(entry: &TreeEntry, metadata: &EntryMetadata) -> String {
+    let (kind, _style) = entry
+        .display_kind(Some(metadata))
+        .unwrap_or_else(|| ("".to_string(), Default::default()));
+    let (name, _style) = entry.display_name(false);
+    format!(
+        "{:06o} {:10}{:>7} {}\n",
+        entry.filemode(),
+        kind,
+        metadata.display_size(),
+        name
+    )
+}
+
+/// A person in a commit, with the time in their own time zone.
+fn person(people: &SignatureFormatter, signature: &Signature) -> PersonRecord {
+    let (name, email) = people.name_and_email(signature);
+    let when = signature.when();
+    let time = FixedOffset::east_opt(when.offset_minutes() * 60)
+        .zip(DateTime::from_timestamp(when.seconds(), 0))
+        .map(|(offset, time)| time.with_timezone(&offset).to_rfc3339())
+        .unwrap_or_default();
+    PersonRecord { name, email, time }
+}
+
+fn json<T: Serialize>(records: &T) -> Result<String, GitBrowserError> {
+    serde_json::to_string_pretty(records)
+        .map(|json| json + "\n")
+        .map_err(|e| GitBrowserError::message(ErrorKind::Output, e.to_string()))
+}
+
+/// Stop quietly when the output is closed early, e.g. when piped to `head`.
+fn output(result: io::Result<()>) -> Result<(), GitBrowserError> {
+    match result {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result.map_err(|e| GitBrowserError::io(ErrorKind::Output, e)),
+    }
+}
@@ -1,77 +1,180 @@
 use std::error::Error;
 use std::fmt;
-use std::panic;
+use std::io;
 
-use color_eyre::{config::HookBuilder, eyre};
+use git2::Oid;
 
-use crate::tui;
+#[derive(Debug)]
+pub enum GitBrowserError {
+    Git(ErrorKind, git2::Error, ErrorContext),
+    Io(ErrorKind, io::Error, ErrorContext),
+    Message(ErrorKind, String, ErrorContext),
+}
 
-pub fn install_hooks() -> color_eyre::Result<()> {
-    let (panic_hook, eyre_hook) = HookBuilder::default().into_hooks();
+/// Extra information about the object that an error relates to.
+#[derive(Clone, Debug, Default)]
+pub struct ErrorContext {
+    pub oid: Option<Oid>,
+    pub path: Option<String>,
+}
 
-    let panic_hook = panic_hook.into_panic_hook();
-    panic::set_hook(Box::new(move |panic_info| {
-        tui::restore().unwrap();
-        panic_hook(panic_info);
-    }));
+impl GitBrowserError {
+    pub fn git(kind: ErrorKind, error: git2::Error) -> Self {
+        GitBrowserError::Git(kind, error, ErrorContext::default())
+    }
 
-    let eyre_hook = eyre_hook.into_eyre_hook();
-    eyre::set_hook(Box::new(
-        move |error: &(dyn std::error::Error + 'static)| {
-            tui::restore().unwrap();
-            eyre_hook(error)
-        },
-    ))?;
+    pub fn io(kind: ErrorKind, error: io::Error) -> Self {
+        GitBrowserError::Io(kind, error, ErrorContext::default())
+    }
 
-    Ok(())
-}
+    pub fn message(kind: ErrorKind, message: impl Into<String>) -> Self {
+        GitBrowserError::Message(kind, message.into(), ErrorContext::default())
+    }
 
-#[derive(Clone, Copy, Debug)]
-pub enum GitBrowserError {
-    Error(ErrorKind),
-}
+    pub fn with_oid(mut self, oid: Oid) -> Self {
+        self.context_mut().oid = Some(oid);
+        self
+    }
 
-impl GitBrowserError {
-    pub fn as_str(&self) -> &str {
-        match &self {
-            GitBrowserError::Error(err) => err.as_str(),
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.context_mut().path = Some(path.into());
+        self
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            GitBrowserError::Git(kind, _, _)
+            | GitBrowserError::Io(kind, _, _)
+            | GitBrowserError::Message(kind, _, _) => *kind,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        self.kind().as_str()
+    }
+
+    pub fn context(&self) -> &ErrorContext {
+        match self {
+            GitBrowserError::Git(_, _, context)
+            | GitBrowserError::Io(_, _, context)
+            | GitBrowserError::Message(_, _, context) => context,
         }
     }
+
+    fn context_mut(&mut self) -> &mut ErrorContext {
+        match self {
+            GitBrowserError::Git(_, _, context)
+            | GitBrowserError::Io(_, _, context)
+            | GitBrowserError::Message(_, _, context) => context,
+        }
+    }
+
+    /// The underlying cause of the error.
+    pub fn cause(&self) -> String {
+        match self {
+            GitBrowserError::Git(_, error, _) => error.message().to_string(),
+            GitBrowserError::Io(_, error, _) => error.to_string(),
+            GitBrowserError::Message(_, message, _) => message.to_string(),
+        }
+    }
+
+    /// Lines of detail to display below the error summary.
+    pub fn details(&self) -> Vec<String> {
+        let mut details = vec![self.cause()];
+        let context = self.context();
+        if let Some(oid) = context.oid {
+            details.push(format!("object: {}", oid));
+        }
+        if let Some(path) = &context.path {
+            details.push(format!("path: {}", path));
+        }
+        details
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum ErrorKind {
+    Attributes,
     BinaryFile,
     BlobReference,
+    CommitReference,
+    Diff,
+    Encoding,
+    Highlight,
+    History,
+    Index,
+    Lfs,
+    Mailmap,
+    Notes,
+    OpenRepository,
+    Output,
+    Pickaxe,
+    Reference,
+    Reflog,
+    Revision,
+    Signature,
+    Stash,
+    Status,
     Subprocess,
     TemporaryFile,
     TerminalInit,
+    TreeEntry,
+    TreeReference,
+    Watch,
+    Worktree,
 }
 
 impl ErrorKind {
-    pub fn as_str(&self) -> &str {
+    pub fn as_str(&self) -> &'static str {
         match *self {
+            ErrorKind::Attributes => "Unable to read .gitattributes",
             ErrorKind::BinaryFile => "Unable to load and display binary files",
             ErrorKind::BlobReference => "Unable to load blob from repository",
+            ErrorKind::CommitReference => "Unable to load commit from repository",
+            ErrorKind::Diff => "Unable to compute diff",
+            ErrorKind::Encoding => "Unable to decode file contents",
+            ErrorKind::Highlight => "Failed to highlight file contents",
+            ErrorKind::History => "Unable to walk the history",
+            ErrorKind::Index => "Unable to read the index",
+            ErrorKind::Lfs => "Unable to load Git LFS object",
+            ErrorKind::Mailmap => "Unable to read .mailmap",
+            ErrorKind::Notes => "Unable to read notes",
+            ErrorKind::OpenRepository => "Failed to open repository",
+            ErrorKind::Output => "Unable to write output",
+            ErrorKind::Pickaxe => "Unable to search the history",
+            ErrorKind::Reference => "Unable to resolve reference",
+            ErrorKind::Reflog => "Unable to read reflog",
+            ErrorKind::Revision => "Unable to resolve revision",
+            ErrorKind::Signature => "Unable to read signature",
+            ErrorKind::Stash => "Unable to read stashes",
+            ErrorKind::Status => "Unable to get the status of files",
             ErrorKind::Subprocess => "Failed to execute subprocess",
             ErrorKind::TemporaryFile => "Failed to write temporary file",
             ErrorKind::TerminalInit => "Failed to reinitialize terminal",
+            ErrorKind::TreeEntry => "Unable to load tree entry",
+            ErrorKind::TreeReference => "Unable to load tree from repository",
+            ErrorKind::Watch => "Failed to watch repository for changes",
+            ErrorKind::Worktree => "Unable to read the working tree",
         }
     }
 }
 
 impl fmt::Display for GitBrowserError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            GitBrowserError::Error(err) => write!(f, "{:?}", err.as_str()),
+        write!(f, "{}", self.as_str())?;
+        for detail in self.details() {
+            write!(f, "\n  {}", detail)?;
         }
+        Ok(())
     }
 }
 
 impl Error for GitBrowserError {
-    fn description(&self) -> &str {
-        match &self {
-            GitBrowserError::Error(err) => err.as_str(),
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GitBrowserError::Git(_, error, _) => Some(error),
+            GitBrowserError::Io(_, error, _) => Some(error),
+            _ => None,
         }
     }
 }
@@ -0,0 +1,20 @@
+//! Browse git repositories at arbitrary revisions without checking out the
+//! working directory.
+//!
+//! [`app::App`] holds a browsing session and can be embedded in another
+//! ratatui application: turn key events into [`app::NavigationAction`]s and
+//! pass them to [`app::App::navigate`], send
+//! [`app::NavigationAction::Tick`] when idle so that background work is
+//! picked up, and draw it into any area of the frame with [`ui::draw`]. The
+//! app never touches the terminal itself, so blobs it is asked to open in an
+//! editor are handed out by [`app::App::take_external_editor`] to be opened
+//! while the terminal is suspended.
+//!
+//! [`scripting`] has how the browser reads repositories, for tools that
+//! print what it would show.
+
+pub mod app;
+pub mod errors;
+pub mod scripting;
+pub(crate) mod traits;
+pub mod ui;
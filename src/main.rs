@@ -1,22 +1,22 @@
 use std::env;
+use std::thread;
 use std::time::Duration;
 
 use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
-use ratatui::{backend::Backend, Terminal};
 
-mod app;
-mod errors;
-mod traits;
+mod cli;
 mod tui;
-mod ui;
-use crate::{
-    app::{navigation::NavigationAction, App},
+use crate::cli::OutputFormat;
+use crate::tui::Tui;
+use gitbrowser_rs::{
+    app::{App, NavigationAction, PickFormat, WorkerPool},
+    errors::{ErrorKind, GitBrowserError},
     ui::ui,
 };
 
 use color_eyre::Result;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use git2::{Object, Repository};
 
 #[derive(Parser, Debug)]
@@ -30,10 +30,72 @@ struct Args {
 
     #[arg(short, long)]
     pager: Option<String>,
+
+    /// Verify commit and tag signatures with gpg or ssh-keygen
+    #[arg(long)]
+    verify_signatures: bool,
+
+    /// Show trees in columns with their parent directory and a preview of
+    /// the selected entry
+    #[arg(long)]
+    columns: bool,
+
+    /// Choose a file, directory or reference with C-p and print it: rev for
+    /// REV:PATH, oid, or a template using {rev}, {path}, {oid} and {ref}.
+    /// The TUI is drawn on the terminal rather than stdout, so that what is
+    /// printed can be captured
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "rev"
+    )]
+    pick: Option<PickFormat>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Commands for scripting, which print to stdout instead of starting the TUI.
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List the entries of a tree, given as REV[:PATH]
+    Ls {
+        spec: String,
+
+        #[arg(long, value_enum, default_value_t)]
+        format: OutputFormat,
+    },
+    /// List the references and the objects they point to
+    Refs {
+        #[arg(long, value_enum, default_value_t)]
+        format: OutputFormat,
+    },
+    /// List the commits in the history of a revision, newest first
+    Log {
+        #[arg(default_value = "HEAD")]
+        revision: String,
+
+        /// Stop after this many commits
+        #[arg(short = 'n', long)]
+        max_count: Option<usize>,
+
+        #[arg(long, value_enum, default_value_t)]
+        format: OutputFormat,
+    },
+    /// Print the content of a blob, given as REV:PATH
+    Cat {
+        spec: String,
+
+        /// Highlight the syntax with ANSI colours
+        #[arg(long)]
+        highlight: bool,
+    },
 }
 
 fn main() -> Result<()> {
-    errors::install_hooks()?;
+    tui::install_hooks()?;
 
     let args = Args::parse();
     let repo_path = match args.repository {
@@ -41,44 +103,89 @@ fn main() -> Result<()> {
         _ => ".".to_string(),
     };
 
-    let repo = match Repository::open(repo_path) {
-        Ok(repo) => repo,
-        Err(e) => panic!("failed to open: {}", e),
-    };
+    let repo = Repository::open(&repo_path)
+        .map_err(|e| GitBrowserError::git(ErrorKind::OpenRepository, e).with_path(&repo_path))?;
+
+    let syntax_set = two_face::syntax::extra_newlines();
+    let theme_set = two_face::theme::extra();
+    let theme = theme_set
+        .get(two_face::theme::EmbeddedThemeName::Nord)
+        .clone();
 
-    let commit: Option<Object> = match args.commit_id {
-        Some(commit_id) => match repo.revparse_single(&commit_id) {
-            Ok(object) => Some(object),
-            Err(e) => panic!("Failed to get commit {}", e),
-        },
+    match &args.command {
+        Some(Command::Ls { spec, format }) => return Ok(cli::ls(&repo, spec, *format)?),
+        Some(Command::Refs { format }) => return Ok(cli::refs(&repo, *format)?),
+        Some(Command::Log {
+            revision,
+            max_count,
+            format,
+        }) => return Ok(cli::log(&repo, revision, *max_count, *format)?),
+        Some(Command::Cat { spec, highlight }) => {
+            let highlight = highlight.then_some((&syntax_set, &theme));
+            return Ok(cli::cat(&repo, spec, highlight)?);
+        }
+        None => {}
+    }
+
+    let commit: Option<Object> = match &args.commit_id {
+        Some(commit_id) => Some(
+            repo.revparse_single(commit_id)
+                .map_err(|e| GitBrowserError::git(ErrorKind::Revision, e).with_path(commit_id))?,
+        ),
         _ => None,
     };
+    // Remember which reference the commit came from so that we can notice
+    // when it moves
+    let reference = args.commit_id.as_ref().and_then(|commit_id| {
+        repo.resolve_reference_from_short_name(commit_id)
+            .ok()
+            .and_then(|reference| reference.name().map(|name| name.to_string()))
+    });
 
     let pager = match args.pager {
         Some(pager) => pager,
         None => {
             if let Some(pager) = env::var_os("PAGER") {
-                pager.into_string().expect("Unable to decode PAGER env var")
+                pager.into_string().map_err(|pager| {
+                    GitBrowserError::message(
+                        ErrorKind::Encoding,
+                        format!("Unable to decode PAGER env var {:?}", pager),
+                    )
+                })?
             } else {
                 "less".to_string()
             }
         }
     };
 
-    let syntax_set = two_face::syntax::extra_newlines();
-    let theme_set = two_face::theme::extra();
-    let theme = theme_set
-        .get(two_face::theme::EmbeddedThemeName::Nord)
-        .clone();
-
-    let mut terminal = tui::init()?;
-    let mut app = App::new(&repo, commit, pager, &syntax_set, &theme);
-    run_app(&mut terminal, &mut app)?;
-    tui::restore()?;
-    Ok(())
+    thread::scope(|scope| {
+        let workers = WorkerPool::new(scope, repo.path(), &syntax_set, &theme);
+        let mut app = App::new(&repo, commit, pager, &syntax_set, &theme, workers)?;
+        if let Some(reference) = reference {
+            app.set_reference(reference);
+        }
+        app.set_verify_signatures(args.verify_signatures);
+        app.set_columns(args.columns);
+        let screen = match args.pick {
+            Some(format) => {
+                app.set_pick(format);
+                tui::Screen::Tty
+            }
+            None => tui::Screen::Stdout,
+        };
+        let mut terminal = tui::init(screen)?;
+        let picked = run_app(&mut terminal, &mut app)?;
+        tui::restore()?;
+        if let Some(picked) = picked {
+            println!("{}", picked);
+        }
+        Ok(())
+    })
 }
 
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<bool> {
+/// Run the TUI until it is exited, returning what was chosen in `--pick`
+/// mode.
+fn run_app(terminal: &mut Tui, app: &mut App) -> Result<Option<String>> {
     let mut redraw = false;
     loop {
         if redraw {
@@ -90,8 +197,10 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<bool
             redraw = match app.navigate(&NavigationAction::Tick) {
                 Ok(redraw) => redraw.0,
                 Err(error) => {
-                    app.error(error);
-                    true
+                    // Background loading failures are logged rather than
+                    // interrupting the user with a popup
+                    app.warn(error);
+                    false
                 }
             };
             continue;
@@ -105,7 +214,7 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<bool
                 continue;
             }
             if key.code == KeyCode::Char('x') && key.modifiers == KeyModifiers::CONTROL {
-                return Ok(true);
+                return Ok(None);
             }
             let navigation_action = NavigationAction::from(key);
             redraw = match app.navigate(&navigation_action) {
@@ -114,6 +223,19 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<bool
                     app.error(error);
                     true
                 }
+            };
+            if let Some(editor) = app.take_external_editor() {
+                tui::restore()?;
+                eprintln!("Opening {} with {} ...", editor.name(), editor.editor());
+                let result = editor.open(tui::screen().stdio());
+                tui::resume()?;
+                if let Err(error) = result {
+                    app.error(error);
+                }
+                redraw = true;
+            }
+            if let Some(picked) = app.picked() {
+                return Ok(Some(picked));
             }
         }
     }
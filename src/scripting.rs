@@ -0,0 +1,12 @@
+//! How the browser reads repositories, for tools that print what it would
+//! show rather than drawing it, like the `ls`, `refs`, `log` and `cat`
+//! commands.
+
+pub use crate::app::attributes::{attribute, AttributeValue};
+pub use crate::app::blob_pager::BlobContent;
+pub use crate::app::entry_metadata::{looks_binary, EntryMetadata};
+pub use crate::app::log_page::walk;
+pub use crate::app::refs_page::{load_refs, RefEntry};
+pub use crate::app::signature::SignatureFormatter;
+pub use crate::app::syntax_detection::detect_syntax;
+pub use crate::traits::Display;
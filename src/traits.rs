@@ -1,6 +1,6 @@
 use color_eyre::Result;
 
-use git2::{Object, ObjectType, Repository, TreeEntry};
+use git2::{Object, ObjectType, TreeEntry};
 
 use ratatui::{
     layout::Rect,
@@ -10,10 +10,11 @@ use ratatui::{
     Frame,
 };
 
+use crate::app::entry_metadata::EntryMetadata;
 use crate::errors::GitBrowserError;
 
 pub trait Display {
-    fn display_kind(&self, repo: &Repository) -> Option<(String, Style)>;
+    fn display_kind(&self, metadata: Option<&EntryMetadata>) -> Option<(String, Style)>;
 
     fn display_name(&self, selected: bool) -> (String, Style);
 }
@@ -25,7 +26,7 @@ pub trait Navigable<'repo> {
     fn pageup(&mut self, page_size: u16);
     fn next_selection(&mut self);
     fn previous_selection(&mut self);
-    fn select(&self) -> Option<(Object<'repo>, String)>;
+    fn select(&self) -> Result<Option<(Object<'repo>, String)>, GitBrowserError>;
     fn selected_item(&self) -> String;
     fn next_tick(&mut self, block: bool) -> Result<(), GitBrowserError>;
 }
@@ -37,22 +38,21 @@ pub trait Drawable<'repo> {
 }
 
 impl<'tree> Display for TreeEntry<'tree> {
-    fn display_kind(&self, repo: &Repository) -> Option<(String, Style)> {
+    fn display_kind(&self, metadata: Option<&EntryMetadata>) -> Option<(String, Style)> {
         if let Some(kind) = self.kind() {
             let value = match kind {
                 ObjectType::Tree => "tree",
-                ObjectType::Blob => {
-                    let object = self.to_object(repo).ok()?;
-                    let blob = object.peel_to_blob().ok()?;
-                    if blob.is_binary() {
+                ObjectType::Blob => match metadata.map(|m| m.binary) {
+                    Some(Some(true)) => {
                         return Some((
                             "binary".to_string(),
                             Style::default().fg(Color::Red).add_modifier(Modifier::DIM),
                         ));
-                    } else {
-                        "blob"
                     }
-                }
+                    Some(_) => "blob",
+                    // Not loaded yet
+                    None => "...",
+                },
                 _ => "unknown",
             };
             return Some((
@@ -1,4 +1,10 @@
-use std::io::{self, stdout, Stdout};
+use std::fs::{File, OpenOptions};
+use std::io::{self, stderr, stdout, Write};
+use std::panic;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use color_eyre::{config::HookBuilder, eyre};
 
 use crossterm::{
     execute,
@@ -7,18 +13,104 @@ use crossterm::{
 use ratatui::prelude::{CrosstermBackend, Terminal};
 
 /// A type alias for the terminal type used in this application
-pub type Tui = Terminal<CrosstermBackend<Stdout>>;
+pub type Tui = Terminal<CrosstermBackend<Box<dyn Write>>>;
+
+/// Where the TUI is drawn.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Screen {
+    Stdout,
+    /// The controlling terminal, leaving stdout free for output that is
+    /// captured, such as in `f=$(gitbrowser --pick)`. Falls back on stderr
+    /// where there is no `/dev/tty`.
+    Tty,
+}
+
+impl Screen {
+    fn output(&self) -> io::Result<Box<dyn Write>> {
+        Ok(match self {
+            Screen::Stdout => Box::new(stdout()),
+            Screen::Tty => match open_tty() {
+                Ok(tty) => Box::new(tty),
+                Err(_) => Box::new(stderr()),
+            },
+        })
+    }
+
+    /// Where programs run from the TUI, such as the editor, should draw.
+    pub fn stdio(&self) -> Stdio {
+        match self {
+            Screen::Stdout => Stdio::inherit(),
+            Screen::Tty => match open_tty() {
+                Ok(tty) => Stdio::from(tty),
+                Err(_) => Stdio::from(stderr()),
+            },
+        }
+    }
+}
+
+fn open_tty() -> io::Result<File> {
+    OpenOptions::new().write(true).open("/dev/tty")
+}
+
+/// Whether the terminal has been set up for the TUI, so that it is only
+/// restored if it was, as the subcommands print to it as it is.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Whether the TUI is drawn on [`Screen::Tty`], so that the same screen is
+/// restored and set up again around external programs.
+static ON_TTY: AtomicBool = AtomicBool::new(false);
 
 /// Initialize the terminal
-pub fn init() -> io::Result<Tui> {
-    execute!(stdout(), EnterAlternateScreen)?;
-    enable_raw_mode()?;
-    Terminal::new(CrosstermBackend::new(stdout()))
+pub fn init(screen: Screen) -> io::Result<Tui> {
+    ON_TTY.store(screen == Screen::Tty, Ordering::SeqCst);
+    resume()?;
+    Terminal::new(CrosstermBackend::new(screen.output()?))
+}
+
+/// Set the terminal up again after it was restored to run another program.
+pub fn resume() -> io::Result<()> {
+    ACTIVE.store(true, Ordering::SeqCst);
+    execute!(screen().output()?, EnterAlternateScreen)?;
+    enable_raw_mode()
+}
+
+/// The screen the TUI was last set up on.
+pub fn screen() -> Screen {
+    if ON_TTY.load(Ordering::SeqCst) {
+        Screen::Tty
+    } else {
+        Screen::Stdout
+    }
 }
 
 /// Restore the terminal to its original state
 pub fn restore() -> io::Result<()> {
-    execute!(stdout(), LeaveAlternateScreen)?;
+    if !ACTIVE.swap(false, Ordering::SeqCst) {
+        return Ok(());
+    }
+    execute!(screen().output()?, LeaveAlternateScreen)?;
     disable_raw_mode()?;
     Ok(())
 }
+
+/// Restore the terminal before reporting panics and errors, so that they
+/// can be read.
+pub fn install_hooks() -> color_eyre::Result<()> {
+    let (panic_hook, eyre_hook) = HookBuilder::default().into_hooks();
+
+    let panic_hook = panic_hook.into_panic_hook();
+    panic::set_hook(Box::new(move |panic_info| {
+        restore().unwrap();
+        panic_hook(panic_info);
+    }));
+
+    let eyre_hook = eyre_hook.into_eyre_hook();
+    eyre::set_hook(Box::new(
+        move |error: &(dyn std::error::Error + 'static)| {
+            restore().unwrap();
+            eyre_hook(error)
+        },
+    ))?;
+
+    Ok(())
+}
@@ -6,11 +6,17 @@ use ratatui::{
 use crate::app::App;
 
 pub fn ui(f: &mut Frame, app: &mut App) {
+    draw(f, f.size(), app);
+}
+
+/// Draw the browser with its key hints in part of the frame, so that it can
+/// be a panel of another application.
+pub fn draw(f: &mut Frame, area: Rect, app: &mut App) {
     // Create the layout sections.
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([Constraint::Min(4), Constraint::Length(1)])
-        .split(f.size());
+        .split(area);
 
     app.draw(f, chunks[0]);
     app.draw_context_hint(f, chunks[1]);